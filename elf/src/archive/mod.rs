@@ -0,0 +1,327 @@
+use std::str;
+
+use crate::raw::Error;
+
+pub mod writer;
+
+pub const AR_MAGIC: &[u8; 8] = b"!<arch>\n";
+pub const AR_THIN_MAGIC: &[u8; 8] = b"!<thin>\n";
+pub const AR_HEADER_SIZE: usize = 60;
+
+/// A static library (`ar` archive) in the GNU, BSD or GNU thin format.
+/// Members are borrowed from the archive buffer and can be passed directly to
+/// `Headers::parse`.
+#[derive(Debug, Clone)]
+pub struct Archive<'a> {
+    buf: &'a [u8],
+    thin: bool,
+    /// The GNU long name table (`//`)
+    long_names: &'a [u8],
+    symbols: Vec<ArchiveSymbol<'a>>,
+    /// Offset of the first regular member header
+    members_offset: usize,
+}
+
+/// An entry of the archive symbol index, mapping a defined symbol to the member defining it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArchiveSymbol<'a> {
+    pub name: &'a str,
+    /// Offset of the header of the defining member in the archive
+    pub member_offset: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveMember<'a> {
+    pub name: &'a str,
+    pub date: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+    pub size: u64,
+    /// Offset of the member header in the archive
+    pub offset: u64,
+    /// Contents of the member, or `None` in a thin archive, where `name`
+    /// is the path of the member relative to the archive.
+    pub data: Option<&'a [u8]>,
+}
+
+impl<'a> Archive<'a> {
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A) -> Result<Archive<'a>, Error> {
+        let buf = buf.as_ref();
+        let thin = match buf.get(..8) {
+            Some(magic) if magic == AR_MAGIC => false,
+            Some(magic) if magic == AR_THIN_MAGIC => true,
+            _ => return Err(Error::InvalidMagicNumber),
+        };
+
+        let mut archive = Archive {
+            buf,
+            thin,
+            long_names: &[],
+            symbols: vec![],
+            members_offset: AR_MAGIC.len(),
+        };
+
+        // the symbol index and long name table precede the regular members
+        let mut offset = AR_MAGIC.len();
+        while offset < buf.len() {
+            let header = RawHeader::parse(buf, offset)?;
+            let mut name = header.name;
+            let mut data = &[][..];
+            // members of thin archives have no data, but the index and name table do
+            if !thin || matches!(name, b"/" | b"/SYM/" | b"/SYM64/" | b"//") {
+                data = header.data(buf, offset)?;
+            }
+            if let Some(len) = bsd_long_name_len(name) {
+                name = trim_nul(data.get(..len).ok_or_else(truncated)?);
+                data = &data[len..];
+            }
+
+            match name {
+                b"/" | b"/SYM/" => archive.symbols = parse_gnu_symbols(data, 4)?,
+                b"/SYM64/" => archive.symbols = parse_gnu_symbols(data, 8)?,
+                b"//" => archive.long_names = data,
+                b"__.SYMDEF" | b"__.SYMDEF SORTED" => archive.symbols = parse_bsd_symbols(data, 4)?,
+                b"__.SYMDEF_64" | b"__.SYMDEF_64 SORTED" => {
+                    archive.symbols = parse_bsd_symbols(data, 8)?
+                }
+                _ => break,
+            }
+
+            offset = next_header(offset, header.size);
+        }
+        archive.members_offset = offset;
+
+        Ok(archive)
+    }
+
+    pub fn is_thin(&self) -> bool {
+        self.thin
+    }
+
+    pub fn symbols(&self) -> &[ArchiveSymbol<'a>] {
+        &self.symbols
+    }
+
+    /// Iterates over the regular members, skipping the symbol index and long name table.
+    pub fn members(&self) -> MemberIterator<'a> {
+        MemberIterator {
+            archive: self.clone(),
+            offset: self.members_offset,
+        }
+    }
+
+    /// Reads the member whose header is at `offset`, as referenced by the symbol index.
+    pub fn member_at(&self, offset: u64) -> Result<ArchiveMember<'a>, Error> {
+        let offset = offset as usize;
+        let header = RawHeader::parse(self.buf, offset)?;
+
+        let (name, data) = if let Some(len) = bsd_long_name_len(header.name) {
+            let data = header.data(self.buf, offset)?;
+            let name = trim_nul(data.get(..len).ok_or_else(truncated)?);
+            (name, Some(&data[len..]))
+        } else if let Some(index) = header.name.strip_prefix(b"/") {
+            let index = parse_decimal(index)? as usize;
+            let name = self
+                .long_names
+                .get(index..)
+                .ok_or_else(|| Error::Message("invalid long name offset".to_string()))?;
+            let end = name.iter().position(|&b| b == b'\n').unwrap_or(name.len());
+            let name = &name[..end];
+            let data = if self.thin {
+                None
+            } else {
+                Some(header.data(self.buf, offset)?)
+            };
+            (name.strip_suffix(b"/").unwrap_or(name), data)
+        } else {
+            let name = header.name.strip_suffix(b"/").unwrap_or(header.name);
+            let data = if self.thin {
+                None
+            } else {
+                Some(header.data(self.buf, offset)?)
+            };
+            (name, data)
+        };
+
+        Ok(ArchiveMember {
+            name: str::from_utf8(name)
+                .map_err(|_| Error::Message("invalid member name".to_string()))?,
+            date: parse_decimal(header.date)?,
+            uid: parse_decimal(header.uid)? as u32,
+            gid: parse_decimal(header.gid)? as u32,
+            mode: parse_octal(header.mode)?,
+            size: data.map_or(header.size, |d| d.len() as u64),
+            offset: offset as u64,
+            data,
+        })
+    }
+
+    /// Looks up the member defining a symbol through the archive symbol index.
+    pub fn find_symbol(&self, name: &str) -> Result<Option<ArchiveMember<'a>>, Error> {
+        self.symbols
+            .iter()
+            .find(|sym| sym.name == name)
+            .map(|sym| self.member_at(sym.member_offset))
+            .transpose()
+    }
+}
+
+pub struct MemberIterator<'a> {
+    archive: Archive<'a>,
+    offset: usize,
+}
+
+impl<'a> Iterator for MemberIterator<'a> {
+    type Item = Result<ArchiveMember<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.archive.buf.len() {
+            return None;
+        }
+
+        let member = self.archive.member_at(self.offset as u64);
+        match &member {
+            // members of thin archives are stored outside of the archive
+            Ok(_) if self.archive.thin => self.offset += AR_HEADER_SIZE,
+            Ok(_) => match RawHeader::parse(self.archive.buf, self.offset) {
+                Ok(header) => self.offset = next_header(self.offset, header.size),
+                Err(_) => self.offset = self.archive.buf.len(),
+            },
+            Err(_) => self.offset = self.archive.buf.len(),
+        }
+        Some(member)
+    }
+}
+
+/// The fields of a member header, with trailing padding removed.
+struct RawHeader<'a> {
+    name: &'a [u8],
+    date: &'a [u8],
+    uid: &'a [u8],
+    gid: &'a [u8],
+    mode: &'a [u8],
+    size: u64,
+}
+
+impl<'a> RawHeader<'a> {
+    fn parse(buf: &'a [u8], offset: usize) -> Result<RawHeader<'a>, Error> {
+        let hdr = buf
+            .get(offset..(offset + AR_HEADER_SIZE))
+            .ok_or_else(truncated)?;
+        if &hdr[58..60] != b"`\n" {
+            return Err(Error::Message("invalid archive member header".to_string()));
+        }
+
+        Ok(RawHeader {
+            name: trim_spaces(&hdr[0..16]),
+            date: trim_spaces(&hdr[16..28]),
+            uid: trim_spaces(&hdr[28..34]),
+            gid: trim_spaces(&hdr[34..40]),
+            mode: trim_spaces(&hdr[40..48]),
+            size: parse_decimal(trim_spaces(&hdr[48..58]))?,
+        })
+    }
+
+    fn data(&self, buf: &'a [u8], offset: usize) -> Result<&'a [u8], Error> {
+        let start = offset + AR_HEADER_SIZE;
+        buf.get(start..(start + self.size as usize))
+            .ok_or_else(truncated)
+    }
+}
+
+/// Member data is padded to an even offset.
+fn next_header(offset: usize, size: u64) -> usize {
+    let end = offset + AR_HEADER_SIZE + size as usize;
+    end + (end & 1)
+}
+
+/// BSD archives store long names as `#1/<len>`, with the name at the start of the data.
+fn bsd_long_name_len(name: &[u8]) -> Option<usize> {
+    let len = name.strip_prefix(b"#1/")?;
+    parse_decimal(len).ok().map(|len| len as usize)
+}
+
+/// GNU symbol index: a big-endian count, the member offsets, then the NUL-terminated names.
+fn parse_gnu_symbols(data: &[u8], word: usize) -> Result<Vec<ArchiveSymbol<'_>>, Error> {
+    let read = |offset: usize| -> Result<u64, Error> {
+        let bytes = data.get(offset..(offset + word)).ok_or_else(truncated)?;
+        Ok(bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64))
+    };
+
+    let count = read(0)? as usize;
+    let names_offset = word * (count + 1);
+    let mut names = data
+        .get(names_offset..)
+        .ok_or_else(truncated)?
+        .split(|&b| b == 0);
+
+    (0..count)
+        .map(|i| {
+            let name = names.next().ok_or_else(truncated)?;
+            Ok(ArchiveSymbol {
+                name: str::from_utf8(name)
+                    .map_err(|_| Error::Message("invalid symbol name".to_string()))?,
+                member_offset: read(word * (i + 1))?,
+            })
+        })
+        .collect()
+}
+
+/// BSD symbol index (`__.SYMDEF`): the size of the ranlib array, the array of
+/// (name offset, member offset) pairs, the size of the string table, then the strings.
+fn parse_bsd_symbols(data: &[u8], word: usize) -> Result<Vec<ArchiveSymbol<'_>>, Error> {
+    let read = |offset: usize| -> Result<u64, Error> {
+        let bytes = data.get(offset..(offset + word)).ok_or_else(truncated)?;
+        Ok(bytes.iter().rev().fold(0, |acc, &b| (acc << 8) | b as u64))
+    };
+
+    let ranlib_size = read(0)? as usize;
+    let strings_offset = word * 2 + ranlib_size;
+    let strings = data.get(strings_offset..).ok_or_else(truncated)?;
+
+    (0..(ranlib_size / (word * 2)))
+        .map(|i| {
+            let entry = word + i * word * 2;
+            let name = strings.get(read(entry)? as usize..).ok_or_else(truncated)?;
+            Ok(ArchiveSymbol {
+                name: str::from_utf8(trim_nul(name))
+                    .map_err(|_| Error::Message("invalid symbol name".to_string()))?,
+                member_offset: read(entry + word)?,
+            })
+        })
+        .collect()
+}
+
+fn trim_spaces(field: &[u8]) -> &[u8] {
+    let end = field.iter().rposition(|&b| b != b' ').map_or(0, |i| i + 1);
+    &field[..end]
+}
+
+fn trim_nul(name: &[u8]) -> &[u8] {
+    let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    &name[..end]
+}
+
+fn parse_decimal(field: &[u8]) -> Result<u64, Error> {
+    parse_number(field, 10)
+}
+
+fn parse_octal(field: &[u8]) -> Result<u32, Error> {
+    parse_number(field, 8).map(|n| n as u32)
+}
+
+fn parse_number(field: &[u8], radix: u32) -> Result<u64, Error> {
+    let field = trim_spaces(field);
+    if field.is_empty() {
+        return Ok(0);
+    }
+    str::from_utf8(field)
+        .ok()
+        .and_then(|s| u64::from_str_radix(s, radix).ok())
+        .ok_or_else(|| Error::Message("invalid archive header field".to_string()))
+}
+
+fn truncated() -> Error {
+    Error::Message("truncated archive".to_string())
+}
@@ -0,0 +1,324 @@
+use std::{fs, io::Write, path::Path};
+
+use crate::raw::{
+    header::Headers, symbol::SymbolTable, Error, SHN_UNDEF, SHT_SYMTAB, STB_GLOBAL, STB_GNU_UNIQUE,
+    STB_WEAK,
+};
+
+use super::{AR_HEADER_SIZE, AR_MAGIC};
+
+/// Names longer than this, or containing a `/`, are stored in the long name table.
+const MAX_SHORT_NAME: usize = 15;
+
+/// Writes deterministic GNU static archives, equivalent to `ar rcsD`.
+///
+/// Timestamps, owners and groups are zeroed and every member gets mode 644, so the
+/// output only depends on the members and their order. The `/` symbol index lists
+/// the defined global symbols of each ELF member's `.symtab`.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveBuilder {
+    members: Vec<(String, Vec<u8>)>,
+}
+
+impl ArchiveBuilder {
+    pub fn new() -> ArchiveBuilder {
+        ArchiveBuilder::default()
+    }
+
+    pub fn add_member(&mut self, name: &str, data: Vec<u8>) -> &mut Self {
+        self.members.push((name.to_string(), data));
+        self
+    }
+
+    /// Adds a file from disk as a member named after its file name.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self, Error> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::Message("invalid member path".to_string()))?;
+        let data = fs::read(path).map_err(Error::Read)?;
+        Ok(self.add_member(name, data))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        self.write(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let symbols = self
+            .members
+            .iter()
+            .map(|(_, data)| defined_symbols(data))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut long_names = vec![];
+        let header_names: Vec<String> = self
+            .members
+            .iter()
+            .map(|(name, _)| {
+                if name.len() > MAX_SHORT_NAME || name.contains('/') {
+                    let offset = long_names.len();
+                    long_names.extend_from_slice(name.as_bytes());
+                    long_names.extend_from_slice(b"/\n");
+                    format!("/{}", offset)
+                } else {
+                    format!("{}/", name)
+                }
+            })
+            .collect();
+        // GNU ar counts the padding of the long name table in its size
+        if long_names.len() & 1 == 1 {
+            long_names.push(b'\n');
+        }
+
+        // Member offsets depend on the size of the index, whose entries are 32-bit
+        // unless the archive grows past 4 GiB.
+        let mut word = 4;
+        let (index, offsets) = loop {
+            let index = symbol_index(&symbols, &[], word);
+            let mut offset = AR_MAGIC.len() as u64;
+            if !index.is_empty() {
+                offset += member_size(index.len());
+            }
+            if !long_names.is_empty() {
+                offset += member_size(long_names.len());
+            }
+
+            let offsets: Vec<u64> = self
+                .members
+                .iter()
+                .map(|(_, data)| {
+                    let start = offset;
+                    offset += member_size(data.len());
+                    start
+                })
+                .collect();
+
+            if word == 4 && offsets.iter().any(|&o| o > u32::MAX as u64) {
+                word = 8;
+                continue;
+            }
+            break (symbol_index(&symbols, &offsets, word), offsets);
+        };
+        debug_assert_eq!(offsets.len(), self.members.len());
+
+        w.write_all(AR_MAGIC).map_err(Error::Write)?;
+        if !index.is_empty() {
+            let name = if word == 4 { "/" } else { "/SYM64/" };
+            write_member(w, name, 0, &index)?;
+        }
+        if !long_names.is_empty() {
+            write_member(w, "//", 0, &long_names)?;
+        }
+        for (name, (_, data)) in header_names.iter().zip(&self.members) {
+            write_member(w, name, 0o644, data)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Names of the symbols a member defines for other objects to link against.
+/// Members that are not ELF objects, or have no symbol table, define none.
+fn defined_symbols(data: &[u8]) -> Result<Vec<String>, Error> {
+    let elf = match Headers::parse(&data) {
+        Ok(elf) => elf,
+        Err(_) => return Ok(vec![]),
+    };
+    let symtab_hdr = match elf.find_section_header(SHT_SYMTAB) {
+        Some(hdr) => hdr,
+        None => return Ok(vec![]),
+    };
+    let symtab = SymbolTable::parse(&data, &elf, symtab_hdr)?;
+
+    Ok(symtab
+        .symbols_iter()
+        .filter(|sym| {
+            let bind = sym.info >> 4;
+            matches!(bind, STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+                && sym.shndx != SHN_UNDEF
+                && !sym.name.is_empty()
+        })
+        .map(|sym| sym.name.to_string())
+        .collect())
+}
+
+/// Builds the GNU symbol index: a big-endian count, the offset of the defining member
+/// for each symbol, then the NUL-terminated names, padded to an even size. Offsets are
+/// zero when not yet known.
+fn symbol_index(symbols: &[Vec<String>], offsets: &[u64], word: usize) -> Vec<u8> {
+    let count: usize = symbols.iter().map(|names| names.len()).sum();
+    if count == 0 {
+        return vec![];
+    }
+
+    let mut index = vec![];
+    let mut push = |value: u64| index.extend_from_slice(&value.to_be_bytes()[(8 - word)..]);
+    push(count as u64);
+    for (i, names) in symbols.iter().enumerate() {
+        let offset = offsets.get(i).copied().unwrap_or(0);
+        for _ in names {
+            push(offset);
+        }
+    }
+    for name in symbols.iter().flatten() {
+        index.extend_from_slice(name.as_bytes());
+        index.push(0);
+    }
+    // GNU ar pads the index with a NUL counted in its size
+    if index.len() & 1 == 1 {
+        index.push(0);
+    }
+    index
+}
+
+/// Size of a member including its header and padding to an even offset.
+fn member_size(len: usize) -> u64 {
+    (AR_HEADER_SIZE + len + (len & 1)) as u64
+}
+
+fn write_member<W: Write>(w: &mut W, name: &str, mode: u32, data: &[u8]) -> Result<(), Error> {
+    // like GNU ar, the long name table leaves its date, owner and mode blank
+    let header = if name == "//" {
+        format!("{:<48}{:<10}`\n", name, data.len())
+    } else {
+        format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8o}{:<10}`\n",
+            name,
+            0,
+            0,
+            0,
+            mode,
+            data.len()
+        )
+    };
+    debug_assert_eq!(header.len(), AR_HEADER_SIZE);
+
+    w.write_all(header.as_bytes()).map_err(Error::Write)?;
+    w.write_all(data).map_err(Error::Write)?;
+    if data.len() & 1 == 1 {
+        w.write_all(b"\n").map_err(Error::Write)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        archive::Archive,
+        raw::{
+            header::{FileHeader, Ident},
+            ELF_CLASS_64, ELF_DATA_LITTLE, ELF_MAGIC, EM_X86_64, SHT_STRTAB,
+        },
+    };
+
+    /// A relocatable object whose symbol table defines global functions in section 1.
+    fn object(names: &[&str]) -> Vec<u8> {
+        let mut strtab = vec![0];
+        let mut symtab = vec![0; 24];
+        for name in names {
+            symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+            symtab.extend_from_slice(&[(STB_GLOBAL << 4) | 2, 0]);
+            symtab.extend_from_slice(&1u16.to_le_bytes());
+            symtab.extend_from_slice(&[0; 16]);
+            strtab.extend_from_slice(name.as_bytes());
+            strtab.push(0);
+        }
+
+        let strtab_offset = 64;
+        let symtab_offset = align(strtab_offset + strtab.len());
+        let shoff = align(symtab_offset + symtab.len());
+        let header = FileHeader {
+            e_ident: Ident {
+                magic: ELF_MAGIC,
+                class: ELF_CLASS_64,
+                data: ELF_DATA_LITTLE,
+                version: 1,
+                os_abi: 0,
+                abi_version: 0,
+                _padding: [0; 7],
+            },
+            e_type: 1,
+            e_machine: EM_X86_64,
+            e_version: 1,
+            e_entry: 0,
+            e_phoff: 0,
+            e_shoff: shoff as u64,
+            e_flags: 0,
+            e_ehsize: 64,
+            e_phentsize: 0,
+            e_phnum: 0,
+            e_shentsize: 64,
+            e_shnum: 3,
+            e_shstrndx: 1,
+        };
+
+        let mut buf = header.as_bytes().to_vec();
+        buf.extend_from_slice(&strtab);
+        buf.resize(symtab_offset, 0);
+        buf.extend_from_slice(&symtab);
+        buf.resize(shoff, 0);
+        let sections = [
+            (0, 0, 0, 0, 0),
+            (SHT_STRTAB, strtab_offset, strtab.len(), 0, 0),
+            (SHT_SYMTAB, symtab_offset, symtab.len(), 1, 24),
+        ];
+        for (sh_type, offset, size, link, entsize) in sections {
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&sh_type.to_le_bytes());
+            buf.extend_from_slice(&[0; 16]);
+            buf.extend_from_slice(&(offset as u64).to_le_bytes());
+            buf.extend_from_slice(&(size as u64).to_le_bytes());
+            buf.extend_from_slice(&(link as u32).to_le_bytes());
+            buf.extend_from_slice(&0u32.to_le_bytes());
+            buf.extend_from_slice(&8u64.to_le_bytes());
+            buf.extend_from_slice(&(entsize as u64).to_le_bytes());
+        }
+        buf
+    }
+
+    fn align(offset: usize) -> usize {
+        (offset + 7) & !7
+    }
+
+    #[test]
+    fn round_trip() {
+        let short = object(&["foo", "bar"]);
+        let long = object(&["baz"]);
+        let buf = ArchiveBuilder::new()
+            .add_member("short.o", short.clone())
+            .add_member("a_rather_long_member_name.o", long.clone())
+            .add_member("odd.txt", b"odd".to_vec())
+            .to_bytes()
+            .unwrap();
+
+        let archive = Archive::parse(&buf).unwrap();
+        let members = archive.members().collect::<Result<Vec<_>, _>>().unwrap();
+        let names: Vec<_> = members.iter().map(|m| m.name).collect();
+        assert_eq!(names, ["short.o", "a_rather_long_member_name.o", "odd.txt"]);
+        assert_eq!(members[0].data, Some(&short[..]));
+        assert_eq!(members[1].data, Some(&long[..]));
+        assert_eq!(members[2].data, Some(&b"odd"[..]));
+        assert!(members.iter().all(|m| m.mode == 0o644 && m.date == 0));
+
+        let symbols: Vec<_> = archive
+            .symbols()
+            .iter()
+            .map(|s| (s.name, s.member_offset))
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("foo", members[0].offset),
+                ("bar", members[0].offset),
+                ("baz", members[1].offset),
+            ]
+        );
+        let member = archive.find_symbol("baz").unwrap().unwrap();
+        assert_eq!(member.name, "a_rather_long_member_name.o");
+    }
+}
@@ -0,0 +1,412 @@
+use std::borrow::Cow;
+
+use crate::raw::{
+    auxv::AuxVector,
+    header::Headers,
+    mapping::AddressMap,
+    note::{Note, NoteIterator},
+    Error, ET_CORE, NT_AUXV, NT_FILE, NT_FPREGSET, NT_PRPSINFO, NT_PRSTATUS, NT_SIGINFO, PT_NOTE,
+};
+
+use self::registers::{FpRegisters, Registers};
+
+pub mod registers;
+pub mod writer;
+
+/// A process snapshot read from an ELF core file (ET_CORE).
+/// Process and thread state comes from the PT_NOTE segment, and the
+/// dumped memory from the PT_LOAD segments.
+#[derive(Debug, Clone)]
+pub struct CoreDump<'a> {
+    pub machine: u16,
+    pub threads: Vec<ThreadState>,
+    pub process: Option<ProcessInfo>,
+    pub signal: Option<SignalInfo>,
+    /// Raw auxiliary vector entries as (type, value) pairs.
+    pub auxv: Vec<(u64, u64)>,
+    pub files: Vec<MappedFile>,
+    memory: AddressMap<'a>,
+}
+
+impl<'a> CoreDump<'a> {
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<CoreDump<'a>, Error> {
+        if elf.header.e_type != ET_CORE {
+            return Err(Error::Message("not a core file".to_string()));
+        }
+
+        let machine = elf.header.e_machine;
+        let mut core = CoreDump {
+            machine,
+            threads: vec![],
+            process: None,
+            signal: None,
+            auxv: vec![],
+            files: vec![],
+            memory: AddressMap::new(buf, elf),
+        };
+
+        for ph in elf.program_headers.iter() {
+            if ph.get_type() != PT_NOTE {
+                continue;
+            }
+            for note in NoteIterator::parse_file_segment(buf, ph)? {
+                core.add_note(&note?)?;
+            }
+        }
+
+        Ok(core)
+    }
+
+    fn add_note(&mut self, note: &Note) -> Result<(), Error> {
+        if note.name != b"CORE" {
+            return Ok(());
+        }
+
+        match note.n_type {
+            NT_PRSTATUS => self
+                .threads
+                .push(ThreadState::parse(self.machine, note.desc)?),
+            // the kernel writes the thread-specific notes after the NT_PRSTATUS of their thread
+            NT_FPREGSET => {
+                if let Some(thread) = self.threads.last_mut() {
+                    thread.fp_registers = Some(FpRegisters::parse(self.machine, note.desc)?);
+                }
+            }
+            NT_PRPSINFO => self.process = Some(ProcessInfo::parse(note.desc)?),
+            NT_SIGINFO => self.signal = Some(SignalInfo::parse(note.desc)?),
+            NT_AUXV => self.auxv = AuxVector::from_bytes(note.desc).entries,
+            NT_FILE => self.files = MappedFile::parse_all(note.desc)?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// The dumped memory of the process.
+    pub fn memory(&self) -> &AddressMap<'a> {
+        &self.memory
+    }
+
+    /// Reads process memory at a virtual address. Fails if the memory was not
+    /// dumped, e.g. for file-backed text that the kernel omits from the core.
+    pub fn read_memory(&self, vaddr: u64, size: u64) -> Result<&'a [u8], Error> {
+        self.memory.read_file_backed(vaddr, size)
+    }
+
+    /// Like `read_memory`, but memory that was not dumped reads as zeros.
+    pub fn read_memory_lossy(&self, vaddr: u64, size: u64) -> Result<Cow<'a, [u8]>, Error> {
+        self.memory.read(vaddr, size)
+    }
+
+    /// The auxiliary vector of the process, from NT_AUXV.
+    pub fn aux_vector(&self) -> AuxVector {
+        AuxVector {
+            entries: self.auxv.clone(),
+        }
+    }
+
+    /// Finds the file mapped at a virtual address.
+    pub fn find_file(&self, vaddr: u64) -> Option<&MappedFile> {
+        self.files
+            .iter()
+            .find(|f| vaddr >= f.start && vaddr < f.end)
+    }
+}
+
+/// Thread state from NT_PRSTATUS (`struct elf_prstatus`), plus its NT_FPREGSET.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadState {
+    /// The signal the thread was stopped by, or 0.
+    pub signal: i16,
+    pub sigpend: u64,
+    pub sighold: u64,
+    /// Thread ID
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    pub user_time: TimeVal,
+    pub system_time: TimeVal,
+    pub registers: Registers,
+    pub fp_registers: Option<FpRegisters>,
+}
+
+impl ThreadState {
+    pub const REGISTERS_OFFSET: usize = 112;
+
+    /// A thread with the given ID and registers, and no other state.
+    pub fn new(pid: i32, registers: Registers) -> ThreadState {
+        ThreadState {
+            signal: 0,
+            sigpend: 0,
+            sighold: 0,
+            pid,
+            ppid: 0,
+            pgrp: 0,
+            sid: 0,
+            user_time: TimeVal::default(),
+            system_time: TimeVal::default(),
+            registers,
+            fp_registers: None,
+        }
+    }
+
+    /// Encodes the thread as a `struct elf_prstatus`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; Self::REGISTERS_OFFSET];
+        // pr_info.si_signo mirrors pr_cursig
+        buf[0..4].copy_from_slice(&(self.signal as i32).to_le_bytes());
+        buf[12..14].copy_from_slice(&self.signal.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.sigpend.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.sighold.to_le_bytes());
+        buf[32..36].copy_from_slice(&self.pid.to_le_bytes());
+        buf[36..40].copy_from_slice(&self.ppid.to_le_bytes());
+        buf[40..44].copy_from_slice(&self.pgrp.to_le_bytes());
+        buf[44..48].copy_from_slice(&self.sid.to_le_bytes());
+        buf[48..64].copy_from_slice(&self.user_time.to_bytes());
+        buf[64..80].copy_from_slice(&self.system_time.to_bytes());
+        buf.extend_from_slice(&self.registers.to_bytes());
+        // pr_fpvalid, padded to 8 bytes
+        let fpvalid = self.fp_registers.is_some() as i32;
+        buf.extend_from_slice(&fpvalid.to_le_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        buf
+    }
+
+    fn parse(machine: u16, desc: &[u8]) -> Result<ThreadState, Error> {
+        let reg_size = Registers::size(machine)
+            .unwrap_or(desc.len().saturating_sub(Self::REGISTERS_OFFSET + 8));
+        if desc.len() < Self::REGISTERS_OFFSET + reg_size {
+            return Err(Error::Message("truncated prstatus".to_string()));
+        }
+
+        let regs = &desc[Self::REGISTERS_OFFSET..(Self::REGISTERS_OFFSET + reg_size)];
+        Ok(ThreadState {
+            signal: i16::from_le_bytes(desc[12..14].try_into().unwrap()),
+            sigpend: read_u64(desc, 16),
+            sighold: read_u64(desc, 24),
+            pid: read_i32(desc, 32),
+            ppid: read_i32(desc, 36),
+            pgrp: read_i32(desc, 40),
+            sid: read_i32(desc, 44),
+            user_time: TimeVal::parse(&desc[48..]),
+            system_time: TimeVal::parse(&desc[64..]),
+            registers: Registers::parse(machine, regs)?,
+            fp_registers: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeVal {
+    pub sec: i64,
+    pub usec: i64,
+}
+
+impl TimeVal {
+    fn parse(buf: &[u8]) -> TimeVal {
+        TimeVal {
+            sec: read_u64(buf, 0) as i64,
+            usec: read_u64(buf, 8) as i64,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; 16] {
+        let mut buf = [0; 16];
+        buf[..8].copy_from_slice(&self.sec.to_le_bytes());
+        buf[8..].copy_from_slice(&self.usec.to_le_bytes());
+        buf
+    }
+}
+
+/// Process information from NT_PRPSINFO (`struct elf_prpsinfo`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessInfo {
+    pub state: u8,
+    /// State as a letter, as in `ps`
+    pub sname: char,
+    pub zombie: bool,
+    pub nice: i8,
+    pub flags: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub pid: i32,
+    pub ppid: i32,
+    pub pgrp: i32,
+    pub sid: i32,
+    /// Executable name, truncated to 15 characters
+    pub fname: String,
+    /// Command line, truncated to 79 characters
+    pub psargs: String,
+}
+
+impl ProcessInfo {
+    pub const SIZE: usize = 136;
+
+    fn parse(desc: &[u8]) -> Result<ProcessInfo, Error> {
+        if desc.len() < Self::SIZE {
+            return Err(Error::Message("truncated prpsinfo".to_string()));
+        }
+
+        Ok(ProcessInfo {
+            state: desc[0],
+            sname: desc[1] as char,
+            zombie: desc[2] != 0,
+            nice: desc[3] as i8,
+            flags: read_u64(desc, 8),
+            uid: read_u32(desc, 16),
+            gid: read_u32(desc, 20),
+            pid: read_i32(desc, 24),
+            ppid: read_i32(desc, 28),
+            pgrp: read_i32(desc, 32),
+            sid: read_i32(desc, 36),
+            fname: c_string(&desc[40..56]),
+            psargs: c_string(&desc[56..136]),
+        })
+    }
+
+    /// Encodes the process information as a `struct elf_prpsinfo`,
+    /// truncating the names to fit.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; Self::SIZE];
+        buf[0] = self.state;
+        buf[1] = self.sname as u8;
+        buf[2] = self.zombie as u8;
+        buf[3] = self.nice as u8;
+        buf[8..16].copy_from_slice(&self.flags.to_le_bytes());
+        buf[16..20].copy_from_slice(&self.uid.to_le_bytes());
+        buf[20..24].copy_from_slice(&self.gid.to_le_bytes());
+        buf[24..28].copy_from_slice(&self.pid.to_le_bytes());
+        buf[28..32].copy_from_slice(&self.ppid.to_le_bytes());
+        buf[32..36].copy_from_slice(&self.pgrp.to_le_bytes());
+        buf[36..40].copy_from_slice(&self.sid.to_le_bytes());
+        // both fields keep a NUL terminator
+        let fname = &self.fname.as_bytes()[..self.fname.len().min(15)];
+        buf[40..(40 + fname.len())].copy_from_slice(fname);
+        let psargs = &self.psargs.as_bytes()[..self.psargs.len().min(79)];
+        buf[56..(56 + psargs.len())].copy_from_slice(psargs);
+        buf
+    }
+}
+
+/// The signal that caused the dump, from NT_SIGINFO (`siginfo_t`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalInfo {
+    pub signo: i32,
+    pub errno: i32,
+    pub code: i32,
+    /// Faulting address, for SIGILL, SIGFPE, SIGSEGV, SIGBUS and SIGTRAP.
+    pub addr: Option<u64>,
+}
+
+impl SignalInfo {
+    fn parse(desc: &[u8]) -> Result<SignalInfo, Error> {
+        if desc.len() < 24 {
+            return Err(Error::Message("truncated siginfo".to_string()));
+        }
+
+        let signo = read_i32(desc, 0);
+        // SIGILL, SIGTRAP, SIGBUS, SIGFPE and SIGSEGV carry the fault address
+        let addr = match signo {
+            4 | 5 | 7 | 8 | 11 => Some(read_u64(desc, 16)),
+            _ => None,
+        };
+
+        Ok(SignalInfo {
+            signo,
+            errno: read_i32(desc, 4),
+            code: read_i32(desc, 8),
+            addr,
+        })
+    }
+
+    /// Encodes the signal as a 128 byte `siginfo_t`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![0; 128];
+        buf[0..4].copy_from_slice(&self.signo.to_le_bytes());
+        buf[4..8].copy_from_slice(&self.errno.to_le_bytes());
+        buf[8..12].copy_from_slice(&self.code.to_le_bytes());
+        if let Some(addr) = self.addr {
+            buf[16..24].copy_from_slice(&addr.to_le_bytes());
+        }
+        buf
+    }
+}
+
+/// A file mapping from NT_FILE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MappedFile {
+    pub start: u64,
+    pub end: u64,
+    /// Offset into the file in bytes
+    pub offset: u64,
+    pub path: String,
+}
+
+impl MappedFile {
+    fn parse_all(desc: &[u8]) -> Result<Vec<MappedFile>, Error> {
+        if desc.len() < 16 {
+            return Err(Error::Message("truncated file note".to_string()));
+        }
+        let count = read_u64(desc, 0) as usize;
+        let page_size = read_u64(desc, 8);
+
+        let names_offset = count
+            .checked_mul(24)
+            .and_then(|n| n.checked_add(16))
+            .filter(|&n| n <= desc.len())
+            .ok_or_else(|| Error::Message("truncated file note".to_string()))?;
+        let mut names = desc[names_offset..].split(|&b| b == 0);
+
+        (0..count)
+            .map(|i| {
+                let entry = &desc[(16 + i * 24)..];
+                let path = names
+                    .next()
+                    .ok_or_else(|| Error::Message("truncated file note".to_string()))?;
+                let offset = read_u64(entry, 16)
+                    .checked_mul(page_size)
+                    .ok_or_else(|| Error::Message("invalid file note offset".to_string()))?;
+                Ok(MappedFile {
+                    start: read_u64(entry, 0),
+                    end: read_u64(entry, 8),
+                    offset,
+                    path: String::from_utf8_lossy(path).into_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Encodes file mappings as the descriptor of NT_FILE.
+    pub fn encode_all(files: &[MappedFile], page_size: u64) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend_from_slice(&(files.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&page_size.to_le_bytes());
+        for file in files {
+            buf.extend_from_slice(&file.start.to_le_bytes());
+            buf.extend_from_slice(&file.end.to_le_bytes());
+            buf.extend_from_slice(&(file.offset / page_size).to_le_bytes());
+        }
+        for file in files {
+            buf.extend_from_slice(file.path.as_bytes());
+            buf.push(0);
+        }
+        buf
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..(offset + 4)].try_into().unwrap())
+}
+
+fn read_i32(buf: &[u8], offset: usize) -> i32 {
+    read_u32(buf, offset) as i32
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..(offset + 8)].try_into().unwrap())
+}
+
+fn c_string(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
@@ -0,0 +1,322 @@
+use crate::raw::{Error, EM_AARCH64, EM_X86_64};
+
+/// General purpose registers of a thread, as stored in the `pr_reg` field of NT_PRSTATUS.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Registers {
+    X86_64(X86_64Registers),
+    AArch64(AArch64Registers),
+    /// Register set of an unsupported machine, in its raw form.
+    Unknown(Vec<u8>),
+}
+
+impl Registers {
+    /// Size of `pr_reg` for the machine, or `None` if unsupported.
+    pub fn size(machine: u16) -> Option<usize> {
+        match machine {
+            EM_X86_64 => Some(X86_64Registers::SIZE),
+            EM_AARCH64 => Some(AArch64Registers::SIZE),
+            _ => None,
+        }
+    }
+
+    pub fn parse(machine: u16, buf: &[u8]) -> Result<Registers, Error> {
+        let regs = match machine {
+            EM_X86_64 => Registers::X86_64(X86_64Registers::parse(buf)?),
+            EM_AARCH64 => Registers::AArch64(AArch64Registers::parse(buf)?),
+            _ => Registers::Unknown(buf.to_vec()),
+        };
+        Ok(regs)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Registers::X86_64(regs) => regs.to_bytes(),
+            Registers::AArch64(regs) => regs.to_bytes(),
+            Registers::Unknown(buf) => buf.clone(),
+        }
+    }
+
+    pub fn pc(&self) -> Option<u64> {
+        match self {
+            Registers::X86_64(regs) => Some(regs.rip),
+            Registers::AArch64(regs) => Some(regs.pc),
+            Registers::Unknown(_) => None,
+        }
+    }
+
+    pub fn sp(&self) -> Option<u64> {
+        match self {
+            Registers::X86_64(regs) => Some(regs.rsp),
+            Registers::AArch64(regs) => Some(regs.sp),
+            Registers::Unknown(_) => None,
+        }
+    }
+}
+
+/// `struct user_regs_struct` on x86_64 Linux.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct X86_64Registers {
+    pub r15: u64,
+    pub r14: u64,
+    pub r13: u64,
+    pub r12: u64,
+    pub rbp: u64,
+    pub rbx: u64,
+    pub r11: u64,
+    pub r10: u64,
+    pub r9: u64,
+    pub r8: u64,
+    pub rax: u64,
+    pub rcx: u64,
+    pub rdx: u64,
+    pub rsi: u64,
+    pub rdi: u64,
+    pub orig_rax: u64,
+    pub rip: u64,
+    pub cs: u64,
+    pub eflags: u64,
+    pub rsp: u64,
+    pub ss: u64,
+    pub fs_base: u64,
+    pub gs_base: u64,
+    pub ds: u64,
+    pub es: u64,
+    pub fs: u64,
+    pub gs: u64,
+}
+
+impl X86_64Registers {
+    pub const SIZE: usize = 27 * 8;
+
+    pub fn parse(buf: &[u8]) -> Result<X86_64Registers, Error> {
+        let r = words::<27>(buf)?;
+        Ok(X86_64Registers {
+            r15: r[0],
+            r14: r[1],
+            r13: r[2],
+            r12: r[3],
+            rbp: r[4],
+            rbx: r[5],
+            r11: r[6],
+            r10: r[7],
+            r9: r[8],
+            r8: r[9],
+            rax: r[10],
+            rcx: r[11],
+            rdx: r[12],
+            rsi: r[13],
+            rdi: r[14],
+            orig_rax: r[15],
+            rip: r[16],
+            cs: r[17],
+            eflags: r[18],
+            rsp: r[19],
+            ss: r[20],
+            fs_base: r[21],
+            gs_base: r[22],
+            ds: r[23],
+            es: r[24],
+            fs: r[25],
+            gs: r[26],
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [
+            self.r15,
+            self.r14,
+            self.r13,
+            self.r12,
+            self.rbp,
+            self.rbx,
+            self.r11,
+            self.r10,
+            self.r9,
+            self.r8,
+            self.rax,
+            self.rcx,
+            self.rdx,
+            self.rsi,
+            self.rdi,
+            self.orig_rax,
+            self.rip,
+            self.cs,
+            self.eflags,
+            self.rsp,
+            self.ss,
+            self.fs_base,
+            self.gs_base,
+            self.ds,
+            self.es,
+            self.fs,
+            self.gs,
+        ]
+        .iter()
+        .flat_map(|r| r.to_le_bytes())
+        .collect()
+    }
+}
+
+/// `struct user_pt_regs` on AArch64 Linux.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AArch64Registers {
+    /// x0 to x30, where x29 is the frame pointer and x30 the link register.
+    pub x: [u64; 31],
+    pub sp: u64,
+    pub pc: u64,
+    pub pstate: u64,
+}
+
+impl AArch64Registers {
+    pub const SIZE: usize = 34 * 8;
+
+    pub fn parse(buf: &[u8]) -> Result<AArch64Registers, Error> {
+        let r = words::<34>(buf)?;
+        Ok(AArch64Registers {
+            x: r[..31].try_into().unwrap(),
+            sp: r[31],
+            pc: r[32],
+            pstate: r[33],
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.x
+            .iter()
+            .chain([self.sp, self.pc, self.pstate].iter())
+            .flat_map(|r| r.to_le_bytes())
+            .collect()
+    }
+}
+
+/// Floating point and vector registers of a thread, from NT_FPREGSET.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FpRegisters {
+    X86_64(X86_64FpRegisters),
+    AArch64(AArch64FpRegisters),
+    Unknown(Vec<u8>),
+}
+
+impl FpRegisters {
+    pub fn parse(machine: u16, buf: &[u8]) -> Result<FpRegisters, Error> {
+        let regs = match machine {
+            EM_X86_64 => FpRegisters::X86_64(X86_64FpRegisters::parse(buf)?),
+            EM_AARCH64 => FpRegisters::AArch64(AArch64FpRegisters::parse(buf)?),
+            _ => FpRegisters::Unknown(buf.to_vec()),
+        };
+        Ok(regs)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            FpRegisters::X86_64(regs) => regs.to_bytes(),
+            FpRegisters::AArch64(regs) => regs.to_bytes(),
+            FpRegisters::Unknown(buf) => buf.clone(),
+        }
+    }
+}
+
+/// `struct user_fpregs_struct` on x86_64 Linux, the legacy FXSAVE area.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct X86_64FpRegisters {
+    pub cwd: u16,
+    pub swd: u16,
+    pub ftw: u16,
+    pub fop: u16,
+    pub rip: u64,
+    pub rdp: u64,
+    pub mxcsr: u32,
+    pub mxcsr_mask: u32,
+    /// x87 registers st0 to st7, each 80 bits wide in a 16 byte slot.
+    pub st: [u128; 8],
+    pub xmm: [u128; 16],
+}
+
+impl X86_64FpRegisters {
+    pub const SIZE: usize = 512;
+
+    pub fn parse(buf: &[u8]) -> Result<X86_64FpRegisters, Error> {
+        if buf.len() < Self::SIZE {
+            return Err(Error::Message("truncated fp registers".to_string()));
+        }
+        let u16_at = |o: usize| u16::from_le_bytes(buf[o..(o + 2)].try_into().unwrap());
+        let u32_at = |o: usize| u32::from_le_bytes(buf[o..(o + 4)].try_into().unwrap());
+        let u64_at = |o: usize| u64::from_le_bytes(buf[o..(o + 8)].try_into().unwrap());
+        let u128_at = |o: usize| u128::from_le_bytes(buf[o..(o + 16)].try_into().unwrap());
+
+        Ok(X86_64FpRegisters {
+            cwd: u16_at(0),
+            swd: u16_at(2),
+            ftw: u16_at(4),
+            fop: u16_at(6),
+            rip: u64_at(8),
+            rdp: u64_at(16),
+            mxcsr: u32_at(24),
+            mxcsr_mask: u32_at(28),
+            st: std::array::from_fn(|i| u128_at(32 + i * 16)),
+            xmm: std::array::from_fn(|i| u128_at(160 + i * 16)),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(Self::SIZE);
+        buf.extend_from_slice(&self.cwd.to_le_bytes());
+        buf.extend_from_slice(&self.swd.to_le_bytes());
+        buf.extend_from_slice(&self.ftw.to_le_bytes());
+        buf.extend_from_slice(&self.fop.to_le_bytes());
+        buf.extend_from_slice(&self.rip.to_le_bytes());
+        buf.extend_from_slice(&self.rdp.to_le_bytes());
+        buf.extend_from_slice(&self.mxcsr.to_le_bytes());
+        buf.extend_from_slice(&self.mxcsr_mask.to_le_bytes());
+        for r in self.st.iter().chain(self.xmm.iter()) {
+            buf.extend_from_slice(&r.to_le_bytes());
+        }
+        buf.resize(Self::SIZE, 0);
+        buf
+    }
+}
+
+/// `struct user_fpsimd_state` on AArch64 Linux.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AArch64FpRegisters {
+    pub v: [u128; 32],
+    pub fpsr: u32,
+    pub fpcr: u32,
+}
+
+impl AArch64FpRegisters {
+    /// The kernel structure ends with two reserved words after `fpcr`.
+    pub const SIZE: usize = 32 * 16 + 16;
+
+    pub fn parse(buf: &[u8]) -> Result<AArch64FpRegisters, Error> {
+        if buf.len() < Self::SIZE {
+            return Err(Error::Message("truncated fp registers".to_string()));
+        }
+        let u32_at = |o: usize| u32::from_le_bytes(buf[o..(o + 4)].try_into().unwrap());
+
+        Ok(AArch64FpRegisters {
+            v: std::array::from_fn(|i| {
+                u128::from_le_bytes(buf[(i * 16)..(i * 16 + 16)].try_into().unwrap())
+            }),
+            fpsr: u32_at(512),
+            fpcr: u32_at(516),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf: Vec<u8> = self.v.iter().flat_map(|r| r.to_le_bytes()).collect();
+        buf.extend_from_slice(&self.fpsr.to_le_bytes());
+        buf.extend_from_slice(&self.fpcr.to_le_bytes());
+        buf.resize(Self::SIZE, 0);
+        buf
+    }
+}
+
+fn words<const N: usize>(buf: &[u8]) -> Result<[u64; N], Error> {
+    if buf.len() < N * 8 {
+        return Err(Error::Message("truncated register set".to_string()));
+    }
+    Ok(std::array::from_fn(|i| {
+        u64::from_le_bytes(buf[(i * 8)..(i * 8 + 8)].try_into().unwrap())
+    }))
+}
@@ -0,0 +1,494 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    os::unix::fs::FileExt,
+};
+
+use crate::raw::{
+    auxv::AuxVector,
+    header::{FileHeader, Ident, ProgramHeader},
+    Error, AT_PAGESZ, ELF_CLASS_64, ELF_DATA_LITTLE, ELF_MAGIC, EM_AARCH64, EM_X86_64, ET_CORE,
+    NT_AUXV, NT_FILE, NT_FPREGSET, NT_PRPSINFO, NT_PRSTATUS, NT_SIGINFO, PF_R, PF_W, PF_X, PT_LOAD,
+    PT_NOTE,
+};
+
+use super::{MappedFile, ProcessInfo, SignalInfo, ThreadState};
+
+const PAGE_SIZE: u64 = 0x1000;
+
+/// A range of process memory to include in a core file as a PT_LOAD segment.
+#[derive(Debug, Clone)]
+pub struct MemoryRegion {
+    pub start: u64,
+    pub size: u64,
+    /// Segment flags (PF_R, PF_W, PF_X)
+    pub flags: u32,
+    pub data: RegionData,
+}
+
+/// Where the contents of a memory region come from.
+#[derive(Debug, Clone)]
+pub enum RegionData {
+    /// Contents of the start of the region. Memory past the end of the data is
+    /// recorded as not dumped, e.g. when it could not be read.
+    Bytes(Vec<u8>),
+    /// The whole region, read from `/proc/<pid>/mem` of a process while the core
+    /// file is written, so that large mappings are never held in memory. Pages that
+    /// can no longer be read by then are written as zeros.
+    Process(i32),
+}
+
+impl RegionData {
+    /// The number of bytes of a region of `size` bytes that are dumped.
+    fn len(&self, size: u64) -> u64 {
+        match self {
+            RegionData::Bytes(data) => (data.len() as u64).min(size),
+            RegionData::Process(_) => size,
+        }
+    }
+}
+
+/// Writes ELF core files (ET_CORE) that debuggers such as gdb and lldb can open.
+///
+/// The note segment is laid out the way the Linux kernel writes it: the first thread's
+/// NT_PRSTATUS is followed by the process-wide NT_PRPSINFO, NT_SIGINFO, NT_AUXV and
+/// NT_FILE notes, and every thread's NT_PRSTATUS by its NT_FPREGSET.
+#[derive(Debug, Clone)]
+pub struct CoreBuilder {
+    machine: u16,
+    threads: Vec<ThreadState>,
+    process: Option<ProcessInfo>,
+    signal: Option<SignalInfo>,
+    auxv: Vec<(u64, u64)>,
+    files: Vec<MappedFile>,
+    regions: Vec<MemoryRegion>,
+}
+
+impl CoreBuilder {
+    pub fn new(machine: u16) -> CoreBuilder {
+        CoreBuilder {
+            machine,
+            threads: vec![],
+            process: None,
+            signal: None,
+            auxv: vec![],
+            files: vec![],
+            regions: vec![],
+        }
+    }
+
+    /// Snapshots the memory, file mappings, auxiliary vector and process information
+    /// of a running process from `/proc/<pid>`. Registers cannot be read this way,
+    /// so threads must be added by the caller, e.g. from a signal context.
+    ///
+    /// Memory is only probed here, a page at a time: each readable mapping becomes
+    /// one region per run of readable pages, whose contents are read again when the
+    /// core file is written. Pages that cannot be read are recorded as not dumped.
+    pub fn from_proc(pid: i32) -> Result<CoreBuilder, Error> {
+        let machine = if cfg!(target_arch = "x86_64") {
+            EM_X86_64
+        } else if cfg!(target_arch = "aarch64") {
+            EM_AARCH64
+        } else {
+            return Err(Error::Message(
+                "core files are not supported on this machine".to_string(),
+            ));
+        };
+        let mut builder = CoreBuilder::new(machine);
+
+        let proc_dir = format!("/proc/{pid}");
+        let read_file = |name: &str| -> Result<Vec<u8>, Error> {
+            let mut buf = vec![];
+            File::open(format!("{proc_dir}/{name}"))
+                .map_err(Error::Open)?
+                .read_to_end(&mut buf)
+                .map_err(Error::Read)?;
+            Ok(buf)
+        };
+
+        let auxv = AuxVector::from_bytes(&read_file("auxv")?);
+        let page_size = auxv.page_size().unwrap_or(PAGE_SIZE);
+        builder.auxv = auxv.entries;
+
+        let maps = String::from_utf8_lossy(&read_file("maps")?).into_owned();
+        let mem = File::open(format!("{proc_dir}/mem")).map_err(Error::Open)?;
+        let mut page = vec![0; page_size as usize];
+        for line in maps.lines() {
+            let Some(mapping) = ProcMapping::parse(line) else {
+                continue;
+            };
+            // the vsyscall page lies outside the user address space and cannot be read
+            if mapping.path == "[vsyscall]" {
+                continue;
+            }
+
+            if mapping.inode != 0 && mapping.path.starts_with('/') {
+                builder.files.push(MappedFile {
+                    start: mapping.start,
+                    end: mapping.end,
+                    offset: mapping.offset,
+                    path: mapping.path.to_string(),
+                });
+            }
+
+            if mapping.flags & PF_R == 0 {
+                builder.regions.push(MemoryRegion {
+                    start: mapping.start,
+                    size: mapping.end - mapping.start,
+                    flags: mapping.flags,
+                    data: RegionData::Bytes(vec![]),
+                });
+                continue;
+            }
+
+            // split the mapping where pages switch between readable and unreadable
+            let mut run_start = mapping.start;
+            let mut run_readable = None;
+            let mut address = mapping.start;
+            while address < mapping.end {
+                let readable = mem.read_exact_at(&mut page, address).is_ok();
+                if run_readable.is_some_and(|r| r != readable) {
+                    builder.regions.push(process_region(
+                        pid,
+                        run_start,
+                        address,
+                        mapping.flags,
+                        run_readable == Some(true),
+                    ));
+                    run_start = address;
+                }
+                run_readable = Some(readable);
+                address = address.saturating_add(page_size);
+            }
+            builder.regions.push(process_region(
+                pid,
+                run_start,
+                mapping.end,
+                mapping.flags,
+                run_readable == Some(true),
+            ));
+        }
+
+        let stat = String::from_utf8_lossy(&read_file("stat")?).into_owned();
+        let status = String::from_utf8_lossy(&read_file("status")?).into_owned();
+        let cmdline = read_file("cmdline")?;
+        builder.process = ProcessInfo::from_proc(pid, &stat, &status, &cmdline);
+
+        Ok(builder)
+    }
+
+    pub fn add_thread(&mut self, thread: ThreadState) -> &mut Self {
+        self.threads.push(thread);
+        self
+    }
+
+    pub fn set_process(&mut self, process: ProcessInfo) -> &mut Self {
+        self.process = Some(process);
+        self
+    }
+
+    pub fn set_signal(&mut self, signal: SignalInfo) -> &mut Self {
+        self.signal = Some(signal);
+        self
+    }
+
+    pub fn set_auxv(&mut self, auxv: Vec<(u64, u64)>) -> &mut Self {
+        self.auxv = auxv;
+        self
+    }
+
+    pub fn add_file(&mut self, file: MappedFile) -> &mut Self {
+        self.files.push(file);
+        self
+    }
+
+    pub fn add_region(&mut self, region: MemoryRegion) -> &mut Self {
+        self.regions.push(region);
+        self
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        self.write(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        let notes = self.build_notes();
+
+        let phnum = self.regions.len() + 1;
+        if phnum >= 0xffff {
+            return Err(Error::Message("too many memory regions".to_string()));
+        }
+
+        let ehsize = std::mem::size_of::<FileHeader>() as u64;
+        let phentsize = std::mem::size_of::<ProgramHeader>() as u64;
+        let notes_offset = ehsize + phentsize * phnum as u64;
+
+        let header = FileHeader {
+            e_ident: Ident {
+                magic: ELF_MAGIC,
+                class: ELF_CLASS_64,
+                data: ELF_DATA_LITTLE,
+                version: 1,
+                os_abi: 0,
+                abi_version: 0,
+                _padding: [0; 7],
+            },
+            e_type: ET_CORE,
+            e_machine: self.machine,
+            e_version: 1,
+            e_entry: 0,
+            e_phoff: ehsize,
+            e_shoff: 0,
+            e_flags: 0,
+            e_ehsize: ehsize as u16,
+            e_phentsize: phentsize as u16,
+            e_phnum: phnum as u16,
+            e_shentsize: 0x40,
+            e_shnum: 0,
+            e_shstrndx: 0,
+        };
+
+        let mut phdrs = vec![ProgramHeader {
+            p_type: PT_NOTE,
+            p_flags: 0,
+            p_offset: notes_offset,
+            p_vaddr: 0,
+            p_paddr: 0,
+            p_filesz: notes.len() as u64,
+            p_memsz: 0,
+            p_align: 4,
+        }];
+
+        // segment contents start on a page boundary, as in kernel-written cores
+        let mut offset = align_up(notes_offset + notes.len() as u64, PAGE_SIZE);
+        for region in self.regions.iter() {
+            let filesz = region.data.len(region.size);
+            phdrs.push(ProgramHeader {
+                p_type: PT_LOAD,
+                p_flags: region.flags,
+                p_offset: offset,
+                p_vaddr: region.start,
+                p_paddr: 0,
+                p_filesz: filesz,
+                p_memsz: region.size,
+                p_align: PAGE_SIZE,
+            });
+            offset += filesz;
+        }
+
+        let write = |w: &mut W, bytes: &[u8]| w.write_all(bytes).map_err(Error::Write);
+        write(w, header.as_bytes())?;
+        for ph in phdrs.iter() {
+            write(w, ph.as_bytes())?;
+        }
+        write(w, &notes)?;
+
+        let mut mem: Option<(i32, File)> = None;
+        let mut position = notes_offset + notes.len() as u64;
+        for (ph, region) in phdrs[1..].iter().zip(self.regions.iter()) {
+            let padding = ph.p_offset - position;
+            write(w, &vec![0; padding as usize])?;
+            match &region.data {
+                RegionData::Bytes(data) => write(w, &data[..(ph.p_filesz as usize)])?,
+                &RegionData::Process(pid) => {
+                    if !matches!(&mem, Some((open_pid, _)) if *open_pid == pid) {
+                        let file = File::open(format!("/proc/{pid}/mem")).map_err(Error::Open)?;
+                        mem = Some((pid, file));
+                    }
+                    let file = &mem.as_ref().unwrap().1;
+                    let mut chunk = vec![0; PAGE_SIZE as usize];
+                    let mut address = region.start;
+                    let end = region.start + ph.p_filesz;
+                    while address < end {
+                        let len = (end - address).min(PAGE_SIZE) as usize;
+                        if file.read_exact_at(&mut chunk[..len], address).is_err() {
+                            chunk[..len].fill(0);
+                        }
+                        write(w, &chunk[..len])?;
+                        address += len as u64;
+                    }
+                }
+            }
+            position = ph.p_offset + ph.p_filesz;
+        }
+
+        Ok(())
+    }
+
+    fn build_notes(&self) -> Vec<u8> {
+        let mut notes = vec![];
+
+        for (i, thread) in self.threads.iter().enumerate() {
+            push_note(&mut notes, NT_PRSTATUS, &thread.to_bytes());
+
+            if i == 0 {
+                self.push_process_notes(&mut notes);
+            }
+
+            if let Some(fp_registers) = &thread.fp_registers {
+                push_note(&mut notes, NT_FPREGSET, &fp_registers.to_bytes());
+            }
+        }
+        if self.threads.is_empty() {
+            self.push_process_notes(&mut notes);
+        }
+
+        notes
+    }
+
+    fn push_process_notes(&self, notes: &mut Vec<u8>) {
+        if let Some(process) = &self.process {
+            push_note(notes, NT_PRPSINFO, &process.to_bytes());
+        }
+        if let Some(signal) = &self.signal {
+            push_note(notes, NT_SIGINFO, &signal.to_bytes());
+        }
+        if !self.auxv.is_empty() {
+            let mut auxv: Vec<u8> = self
+                .auxv
+                .iter()
+                .flat_map(|&(a_type, value)| [a_type.to_le_bytes(), value.to_le_bytes()])
+                .flatten()
+                .collect();
+            auxv.extend_from_slice(&[0; 16]);
+            push_note(notes, NT_AUXV, &auxv);
+        }
+        if !self.files.is_empty() {
+            let page_size = self
+                .auxv
+                .iter()
+                .find(|&&(a_type, _)| a_type == AT_PAGESZ)
+                .map_or(PAGE_SIZE, |&(_, value)| value);
+            push_note(
+                notes,
+                NT_FILE,
+                &MappedFile::encode_all(&self.files, page_size),
+            );
+        }
+    }
+}
+
+/// A region of a mapping of a process, read when the core file is written if its
+/// pages are readable, and recorded as not dumped otherwise.
+fn process_region(pid: i32, start: u64, end: u64, flags: u32, readable: bool) -> MemoryRegion {
+    MemoryRegion {
+        start,
+        size: end - start,
+        flags,
+        data: if readable {
+            RegionData::Process(pid)
+        } else {
+            RegionData::Bytes(vec![])
+        },
+    }
+}
+
+/// Appends a note owned by "CORE", padded to 4 bytes.
+fn push_note(buf: &mut Vec<u8>, n_type: u32, desc: &[u8]) {
+    const NAME: &[u8] = b"CORE\0";
+
+    buf.extend_from_slice(&(NAME.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&n_type.to_le_bytes());
+    buf.extend_from_slice(NAME);
+    buf.resize(align_up(buf.len() as u64, 4) as usize, 0);
+    buf.extend_from_slice(desc);
+    buf.resize(align_up(buf.len() as u64, 4) as usize, 0);
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    (value + align - 1) & !(align - 1)
+}
+
+/// A line of `/proc/<pid>/maps`.
+struct ProcMapping<'a> {
+    start: u64,
+    end: u64,
+    flags: u32,
+    offset: u64,
+    inode: u64,
+    path: &'a str,
+}
+
+impl<'a> ProcMapping<'a> {
+    fn parse(line: &'a str) -> Option<ProcMapping<'a>> {
+        let mut fields = line.splitn(6, ' ');
+        let (start, end) = fields.next()?.split_once('-')?;
+        let perms = fields.next()?.as_bytes();
+        let offset = fields.next()?;
+        let _dev = fields.next()?;
+        let inode = fields.next()?;
+        let path = fields.next().unwrap_or("").trim_start();
+
+        let mut flags = 0;
+        if perms.first() == Some(&b'r') {
+            flags |= PF_R;
+        }
+        if perms.get(1) == Some(&b'w') {
+            flags |= PF_W;
+        }
+        if perms.get(2) == Some(&b'x') {
+            flags |= PF_X;
+        }
+
+        Some(ProcMapping {
+            start: u64::from_str_radix(start, 16).ok()?,
+            end: u64::from_str_radix(end, 16).ok()?,
+            flags,
+            offset: u64::from_str_radix(offset, 16).ok()?,
+            inode: inode.parse().ok()?,
+            path,
+        })
+    }
+}
+
+impl ProcessInfo {
+    /// Builds process information from the contents of `/proc/<pid>/stat`,
+    /// `/proc/<pid>/status` and `/proc/<pid>/cmdline`.
+    fn from_proc(pid: i32, stat: &str, status: &str, cmdline: &[u8]) -> Option<ProcessInfo> {
+        // the command name may contain spaces and parentheses, so split after the last ')'
+        let (head, rest) = stat.rsplit_once(')')?;
+        let fname = head.split_once('(')?.1.to_string();
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |i: usize| fields.get(i).and_then(|f| f.parse::<i64>().ok());
+
+        let sname = fields.first()?.chars().next()?;
+        let id = |key: &str| {
+            status
+                .lines()
+                .find_map(|l| l.strip_prefix(key))
+                .and_then(|l| l.split_whitespace().next())
+                .and_then(|v| v.parse().ok())
+        };
+
+        let psargs = cmdline
+            .iter()
+            .map(|&b| if b == 0 { ' ' } else { b as char })
+            .collect::<String>();
+
+        Some(ProcessInfo {
+            state: match sname {
+                'R' => 0,
+                'S' => 1,
+                'D' => 2,
+                'T' => 3,
+                'Z' => 4,
+                _ => 5,
+            },
+            sname,
+            zombie: sname == 'Z',
+            nice: field(16)? as i8,
+            flags: field(6)? as u64,
+            uid: id("Uid:")?,
+            gid: id("Gid:")?,
+            pid,
+            ppid: field(1)? as i32,
+            pgrp: field(2)? as i32,
+            sid: field(3)? as i32,
+            fname,
+            psargs: psargs.trim_end().to_string(),
+        })
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    parsed::note::hex,
+    raw::{header::Headers, note, Error},
+};
+
+/// The directory distributions install separate debug files under.
+pub const DEFAULT_DEBUG_DIRECTORY: &str = "/usr/lib/debug";
+
+/// The `.gnu_debuglink` section: the file name of the separate debug file and
+/// the CRC32 of its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugLink<'a> {
+    pub filename: &'a str,
+    pub crc: u32,
+}
+
+/// The `.gnu_debugaltlink` section: the path and build id of the supplementary
+/// debug file shared between objects, as produced by `dwz`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugAltLink<'a> {
+    pub filename: &'a str,
+    pub build_id: &'a [u8],
+}
+
+impl<'a> DebugLink<'a> {
+    pub fn parse<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+    ) -> Result<Option<DebugLink<'a>>, Error> {
+        let hdr = match elf.find_section_header_by_name(".gnu_debuglink") {
+            Some(hdr) => hdr,
+            None => return Ok(None),
+        };
+        let data = hdr.get_section_buffer(buf)?;
+
+        let (filename, len) = split_c_string(data)?;
+        // the CRC follows the name, aligned to 4 bytes
+        let crc_offset = (len + 1 + 3) & !3;
+        let crc = data
+            .get(crc_offset..(crc_offset + 4))
+            .ok_or_else(|| Error::Message("truncated .gnu_debuglink".to_string()))?;
+
+        Ok(Some(DebugLink {
+            filename,
+            crc: u32::from_le_bytes(crc.try_into().unwrap()),
+        }))
+    }
+
+    /// Checks the contents of a candidate debug file against the recorded CRC.
+    pub fn matches(&self, data: &[u8]) -> bool {
+        crc32(data) == self.crc
+    }
+}
+
+impl<'a> DebugAltLink<'a> {
+    pub fn parse<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+    ) -> Result<Option<DebugAltLink<'a>>, Error> {
+        let hdr = match elf.find_section_header_by_name(".gnu_debugaltlink") {
+            Some(hdr) => hdr,
+            None => return Ok(None),
+        };
+        let data = hdr.get_section_buffer(buf)?;
+
+        let (filename, len) = split_c_string(data)?;
+        Ok(Some(DebugAltLink {
+            filename,
+            build_id: &data[(len + 1)..],
+        }))
+    }
+}
+
+/// A separate debug file found on disk.
+#[derive(Debug, Clone)]
+pub struct DebugFile {
+    pub path: PathBuf,
+    pub data: Vec<u8>,
+}
+
+/// Searches for separate debug files the way gdb does: by build id under
+/// `.build-id/xx/yyyy.debug` in each debug directory, then by the `.gnu_debuglink`
+/// name next to the object, in its `.debug` subdirectory, and under each debug
+/// directory mirroring the object's location.
+#[derive(Debug, Clone)]
+pub struct DebugFileLocator {
+    directories: Vec<PathBuf>,
+}
+
+impl Default for DebugFileLocator {
+    fn default() -> Self {
+        DebugFileLocator {
+            directories: vec![PathBuf::from(DEFAULT_DEBUG_DIRECTORY)],
+        }
+    }
+}
+
+impl DebugFileLocator {
+    pub fn new() -> DebugFileLocator {
+        DebugFileLocator::default()
+    }
+
+    /// Adds a directory to search before the ones already configured.
+    pub fn add_directory<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.directories.insert(0, dir.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+
+    /// Finds the debug file of the object at `path`, whose contents are `buf`.
+    pub fn find_debug_file<A: AsRef<[u8]>>(
+        &self,
+        path: &Path,
+        buf: &A,
+        elf: &Headers,
+    ) -> Result<Option<DebugFile>, Error> {
+        if let Some(build_id) = note::find_build_id(buf, elf)? {
+            if let Some(file) = self.find_by_build_id(build_id)? {
+                return Ok(Some(file));
+            }
+        }
+
+        let link = match DebugLink::parse(buf, elf)? {
+            Some(link) => link,
+            None => return Ok(None),
+        };
+        for candidate in self.link_candidates(path, link.filename) {
+            // the debug file may share the object's name, so never return the object itself
+            if candidate == path {
+                continue;
+            }
+            if let Some(data) = read_if_exists(&candidate)? {
+                if link.matches(&data) {
+                    return Ok(Some(DebugFile {
+                        path: candidate,
+                        data,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Finds the supplementary debug file named by `.gnu_debugaltlink` in a debug file at `path`.
+    pub fn find_alt_file<A: AsRef<[u8]>>(
+        &self,
+        path: &Path,
+        buf: &A,
+        elf: &Headers,
+    ) -> Result<Option<DebugFile>, Error> {
+        let link = match DebugAltLink::parse(buf, elf)? {
+            Some(link) => link,
+            None => return Ok(None),
+        };
+
+        // relative names are relative to the directory of the referencing file
+        let named = match path.parent() {
+            Some(dir) => dir.join(link.filename),
+            None => PathBuf::from(link.filename),
+        };
+        if let Some(data) = read_if_exists(&named)? {
+            if build_id_of(&data)?.as_deref() == Some(link.build_id) {
+                return Ok(Some(DebugFile { path: named, data }));
+            }
+        }
+
+        self.find_by_build_id(link.build_id)
+    }
+
+    /// Looks up `.build-id/xx/yyyy.debug`, where `xx` is the first byte of the build id
+    /// and `yyyy` the rest, in hexadecimal.
+    pub fn find_by_build_id(&self, build_id: &[u8]) -> Result<Option<DebugFile>, Error> {
+        let (first, rest) = match build_id.split_first() {
+            Some(split) if !split.1.is_empty() => split,
+            _ => return Ok(None),
+        };
+        let name = format!("{:02x}/{}.debug", first, hex(rest));
+
+        for dir in &self.directories {
+            let candidate = dir.join(".build-id").join(&name);
+            if let Some(data) = read_if_exists(&candidate)? {
+                if build_id_of(&data)?.as_deref() == Some(build_id) {
+                    return Ok(Some(DebugFile {
+                        path: candidate,
+                        data,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn link_candidates(&self, path: &Path, filename: &str) -> Vec<PathBuf> {
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut candidates = vec![dir.join(filename), dir.join(".debug").join(filename)];
+
+        if let Ok(dir) = fs::canonicalize(if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        }) {
+            let relative = dir.strip_prefix("/").unwrap_or(&dir);
+            for debug_dir in &self.directories {
+                candidates.push(debug_dir.join(relative).join(filename));
+            }
+        }
+        candidates
+    }
+}
+
+/// The CRC32 (IEEE 802.3, as used by zlib) used by `.gnu_debuglink`.
+pub fn crc32(data: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 != 0 {
+                    0xedb8_8320 ^ (crc >> 1)
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    let crc = data.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+fn split_c_string(data: &[u8]) -> Result<(&str, usize), Error> {
+    let len = data
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or_else(|| Error::Message("unterminated debug link file name".to_string()))?;
+    let name = std::str::from_utf8(&data[..len])
+        .map_err(|_| Error::Message("invalid debug link file name".to_string()))?;
+    Ok((name, len))
+}
+
+fn read_if_exists(path: &Path) -> Result<Option<Vec<u8>>, Error> {
+    match fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Read(e)),
+    }
+}
+
+/// The build id of an ELF file, or `None` if it is not an ELF file or has no build id.
+fn build_id_of(data: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let elf = match Headers::parse(&data) {
+        Ok(elf) => elf,
+        Err(_) => return Ok(None),
+    };
+    Ok(note::find_build_id(&data, &elf)?.map(|id| id.to_vec()))
+}
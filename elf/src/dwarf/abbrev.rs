@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use crate::raw::Error;
+
+use super::{reader::Reader, DW_CHILDREN_YES, DW_FORM_IMPLICIT_CONST};
+
+/// The abbreviation declarations of a unit, describing the tag and attribute
+/// layout of each DIE in `.debug_info`.
+#[derive(Debug, Clone, Default)]
+pub struct Abbreviations {
+    abbrevs: HashMap<u64, Abbreviation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Abbreviation {
+    pub code: u64,
+    pub tag: u64,
+    pub has_children: bool,
+    pub attributes: Vec<AttributeSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttributeSpec {
+    pub name: u64,
+    pub form: u64,
+    /// The value of attributes using DW_FORM_implicit_const, stored in the abbreviation
+    pub implicit_const: i64,
+}
+
+impl Abbreviations {
+    /// Parses the abbreviation table at `offset` in `.debug_abbrev`, as referenced by a unit header.
+    pub fn parse(debug_abbrev: &[u8], offset: u64) -> Result<Abbreviations, Error> {
+        let mut r = Reader::at(debug_abbrev, offset as usize);
+        let mut abbrevs = HashMap::new();
+
+        loop {
+            let code = r.uleb128()?;
+            if code == 0 {
+                break;
+            }
+            let tag = r.uleb128()?;
+            let has_children = r.u8()? == DW_CHILDREN_YES;
+
+            let mut attributes = vec![];
+            loop {
+                let name = r.uleb128()?;
+                let form = r.uleb128()?;
+                if name == 0 && form == 0 {
+                    break;
+                }
+                let implicit_const = if form == DW_FORM_IMPLICIT_CONST {
+                    r.sleb128()?
+                } else {
+                    0
+                };
+                attributes.push(AttributeSpec {
+                    name,
+                    form,
+                    implicit_const,
+                });
+            }
+
+            abbrevs.insert(
+                code,
+                Abbreviation {
+                    code,
+                    tag,
+                    has_children,
+                    attributes,
+                },
+            );
+        }
+
+        Ok(Abbreviations { abbrevs })
+    }
+
+    pub fn get(&self, code: u64) -> Option<&Abbreviation> {
+        self.abbrevs.get(&code)
+    }
+}
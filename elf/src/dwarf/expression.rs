@@ -0,0 +1,175 @@
+use crate::raw::Error;
+
+use super::{reader::Reader, *};
+
+/// Limits the number of operations, since branches can make expressions loop.
+const MAX_OPERATIONS: usize = 10_000;
+
+/// The machine state a DWARF expression can read.
+pub trait ExpressionContext {
+    /// The value of a DWARF register, if known.
+    fn register(&self, register: u16) -> Option<u64>;
+
+    /// Reads `size` bytes (at most 8) of target memory as a little-endian integer.
+    fn read_memory(&self, address: u64, size: u8) -> Option<u64>;
+}
+
+/// Evaluates a DWARF expression that computes a value or address, such as the CFA
+/// expressions of call frame information. `initial` is pushed on the stack first.
+///
+/// Register location operations (DW_OP_reg*) and composite locations (DW_OP_piece)
+/// describe where a value lives rather than compute one, so they are not supported.
+pub fn evaluate<C: ExpressionContext>(
+    expr: &[u8],
+    address_size: u8,
+    initial: Option<u64>,
+    context: &C,
+) -> Result<u64, Error> {
+    let mut stack: Vec<u64> = initial.into_iter().collect();
+    let mut r = Reader::new(expr);
+
+    let unsupported = |op: u8| Error::Message(format!("unsupported DWARF operation 0x{:02x}", op));
+    let underflow = || Error::Message("DWARF expression stack underflow".to_string());
+    let unknown_register = |reg: u16| Error::Message(format!("register {} is not known", reg));
+    let unreadable = |address: u64| Error::UnmappedAddress(address);
+
+    for _ in 0..MAX_OPERATIONS {
+        if r.is_empty() {
+            return stack.pop().ok_or_else(underflow);
+        }
+
+        let op = r.u8()?;
+        match op {
+            DW_OP_ADDR => stack.push(r.sized(address_size)?),
+            DW_OP_DEREF | DW_OP_DEREF_SIZE => {
+                let size = if op == DW_OP_DEREF_SIZE {
+                    r.u8()?
+                } else {
+                    address_size
+                };
+                let address = stack.pop().ok_or_else(underflow)?;
+                let value = context
+                    .read_memory(address, size)
+                    .ok_or_else(|| unreadable(address))?;
+                stack.push(value);
+            }
+            DW_OP_CONST1U => stack.push(r.u8()? as u64),
+            DW_OP_CONST1S => stack.push(r.i8()? as i64 as u64),
+            DW_OP_CONST2U => stack.push(r.u16()? as u64),
+            DW_OP_CONST2S => stack.push(r.u16()? as i16 as i64 as u64),
+            DW_OP_CONST4U => stack.push(r.u32()? as u64),
+            DW_OP_CONST4S => stack.push(r.u32()? as i32 as i64 as u64),
+            DW_OP_CONST8U | DW_OP_CONST8S => stack.push(r.u64()?),
+            DW_OP_CONSTU => stack.push(r.uleb128()?),
+            DW_OP_CONSTS => stack.push(r.sleb128()? as u64),
+            DW_OP_DUP => stack.push(*stack.last().ok_or_else(underflow)?),
+            DW_OP_DROP => {
+                stack.pop().ok_or_else(underflow)?;
+            }
+            DW_OP_OVER | DW_OP_PICK => {
+                let index = if op == DW_OP_PICK {
+                    r.u8()? as usize
+                } else {
+                    1
+                };
+                let value = stack
+                    .len()
+                    .checked_sub(index + 1)
+                    .map(|i| stack[i])
+                    .ok_or_else(underflow)?;
+                stack.push(value);
+            }
+            DW_OP_SWAP => {
+                let len = stack.len();
+                if len < 2 {
+                    return Err(underflow());
+                }
+                stack.swap(len - 1, len - 2);
+            }
+            DW_OP_ROT => {
+                let len = stack.len();
+                if len < 3 {
+                    return Err(underflow());
+                }
+                stack[(len - 3)..].rotate_right(1);
+            }
+            DW_OP_ABS => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push((value as i64).unsigned_abs());
+            }
+            DW_OP_NEG => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push((value as i64).wrapping_neg() as u64);
+            }
+            DW_OP_NOT => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push(!value);
+            }
+            DW_OP_PLUS_UCONST => {
+                let value = stack.pop().ok_or_else(underflow)?;
+                stack.push(value.wrapping_add(r.uleb128()?));
+            }
+            DW_OP_AND | DW_OP_DIV | DW_OP_MINUS | DW_OP_MOD | DW_OP_MUL | DW_OP_OR | DW_OP_PLUS
+            | DW_OP_SHL | DW_OP_SHR | DW_OP_SHRA | DW_OP_XOR | DW_OP_EQ | DW_OP_GE | DW_OP_GT
+            | DW_OP_LE | DW_OP_LT | DW_OP_NE => {
+                let b = stack.pop().ok_or_else(underflow)?;
+                let a = stack.pop().ok_or_else(underflow)?;
+                let (sa, sb) = (a as i64, b as i64);
+                let value = match op {
+                    DW_OP_AND => a & b,
+                    DW_OP_DIV if b == 0 => {
+                        return Err(Error::Message("division by zero".to_string()))
+                    }
+                    DW_OP_DIV => sa.wrapping_div(sb) as u64,
+                    DW_OP_MINUS => a.wrapping_sub(b),
+                    DW_OP_MOD if b == 0 => {
+                        return Err(Error::Message("division by zero".to_string()))
+                    }
+                    DW_OP_MOD => a % b,
+                    DW_OP_MUL => a.wrapping_mul(b),
+                    DW_OP_OR => a | b,
+                    DW_OP_PLUS => a.wrapping_add(b),
+                    DW_OP_SHL => a.checked_shl(b as u32).unwrap_or(0),
+                    DW_OP_SHR => a.checked_shr(b as u32).unwrap_or(0),
+                    DW_OP_SHRA => sa.checked_shr(b as u32).unwrap_or(sa >> 63) as u64,
+                    DW_OP_XOR => a ^ b,
+                    DW_OP_EQ => (sa == sb) as u64,
+                    DW_OP_GE => (sa >= sb) as u64,
+                    DW_OP_GT => (sa > sb) as u64,
+                    DW_OP_LE => (sa <= sb) as u64,
+                    DW_OP_LT => (sa < sb) as u64,
+                    _ => (sa != sb) as u64,
+                };
+                stack.push(value);
+            }
+            DW_OP_SKIP | DW_OP_BRA => {
+                let offset = r.u16()? as i16 as isize;
+                let taken = op == DW_OP_SKIP || stack.pop().ok_or_else(underflow)? != 0;
+                if taken {
+                    let target = r.offset() as isize + offset;
+                    if target < 0 || target as usize > expr.len() {
+                        return Err(Error::Message("branch out of expression".to_string()));
+                    }
+                    r = Reader::at(expr, target as usize);
+                }
+            }
+            DW_OP_LIT0..=DW_OP_LIT31 => stack.push((op - DW_OP_LIT0) as u64),
+            DW_OP_BREG0..=DW_OP_BREG31 | DW_OP_BREGX => {
+                let register = if op == DW_OP_BREGX {
+                    r.uleb128()? as u16
+                } else {
+                    (op - DW_OP_BREG0) as u16
+                };
+                let offset = r.sleb128()?;
+                let value = context
+                    .register(register)
+                    .ok_or_else(|| unknown_register(register))?;
+                stack.push(value.wrapping_add(offset as u64));
+            }
+            DW_OP_NOP => {}
+            _ => return Err(unsupported(op)),
+        }
+    }
+
+    Err(Error::Message("DWARF expression too long".to_string()))
+}
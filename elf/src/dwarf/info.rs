@@ -0,0 +1,580 @@
+use crate::raw::Error;
+
+use super::{
+    abbrev::{Abbreviations, AttributeSpec},
+    line::LineProgram,
+    lists::{self, LocationListEntry, Range},
+    reader::{self, Format, Reader},
+    *,
+};
+
+/// A compilation, type or partial unit in `.debug_info`, with the bases its root DIE
+/// declares for indexed strings, addresses and lists.
+#[derive(Debug, Clone)]
+pub struct Unit<'a> {
+    /// Offset of the unit header in `.debug_info`
+    pub offset: u64,
+    pub format: Format,
+    pub version: u16,
+    pub unit_type: u8,
+    pub address_size: u8,
+    pub abbrev_offset: u64,
+    /// The id linking skeleton and split units
+    pub dwo_id: Option<u64>,
+    /// The signature and type DIE offset of type units
+    pub type_signature: Option<(u64, u64)>,
+    pub str_offsets_base: u64,
+    pub addr_base: u64,
+    pub rnglists_base: u64,
+    pub loclists_base: u64,
+    /// The base address for range and location lists, from the DW_AT_low_pc of the root DIE
+    pub base_address: u64,
+    pub abbrevs: Abbreviations,
+    sections: DwarfSections<'a>,
+    /// Offset of the first DIE in `.debug_info`
+    entries_offset: usize,
+    /// Offset of the end of the unit in `.debug_info`
+    end_offset: usize,
+}
+
+/// A debugging information entry.
+#[derive(Debug, Clone)]
+pub struct Die<'a> {
+    /// Offset of the entry in `.debug_info`
+    pub offset: u64,
+    /// Depth of the entry relative to where iteration started
+    pub depth: usize,
+    pub tag: u64,
+    pub has_children: bool,
+    pub attributes: Vec<Attribute<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    pub name: u64,
+    pub value: AttributeValue<'a>,
+}
+
+/// An attribute value, decoded according to its form. Indexed and section-relative
+/// values are resolved through the `Unit` they belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeValue<'a> {
+    Address(u64),
+    /// An index into `.debug_addr`
+    AddressIndex(u64),
+    Block(&'a [u8]),
+    Constant(u64),
+    Signed(i64),
+    Data16(&'a [u8]),
+    Exprloc(&'a [u8]),
+    Flag(bool),
+    /// An offset into another debug section, such as `.debug_line` or `.debug_rnglists`
+    SecOffset(u64),
+    /// An offset of a DIE in `.debug_info`; unit-relative references are made absolute
+    Reference(u64),
+    TypeSignature(u64),
+    /// A DIE offset in the supplementary object file
+    Supplementary(u64),
+    /// An offset into `.debug_str` of the supplementary object file
+    SupStrRef(u64),
+    String(&'a str),
+    /// An offset into `.debug_str`
+    DebugStrRef(u64),
+    /// An offset into `.debug_line_str`
+    DebugLineStrRef(u64),
+    /// An index into `.debug_str_offsets`
+    DebugStrIndex(u64),
+    LocListIndex(u64),
+    RangeListIndex(u64),
+}
+
+impl AttributeValue<'_> {
+    /// The value of constant, flag and offset forms.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            AttributeValue::Constant(v)
+            | AttributeValue::SecOffset(v)
+            | AttributeValue::Reference(v) => Some(v),
+            AttributeValue::Signed(v) => Some(v as u64),
+            AttributeValue::Flag(v) => Some(v as u64),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Die<'a> {
+    pub fn attr(&self, name: u64) -> Option<&AttributeValue<'a>> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| &attr.value)
+    }
+}
+
+impl<'a> Unit<'a> {
+    /// Parses the unit header at `offset` in `.debug_info` and the bases declared by its root DIE.
+    pub fn parse(sections: &DwarfSections<'a>, offset: u64) -> Result<Unit<'a>, Error> {
+        let mut r = Reader::at(sections.debug_info, offset as usize);
+        let (unit_length, format) = r.initial_length()?;
+        let end_offset = usize::try_from(unit_length)
+            .ok()
+            .and_then(|len| r.offset().checked_add(len))
+            .filter(|&end| end <= sections.debug_info.len())
+            .ok_or_else(reader::truncated)?;
+
+        let version = r.u16()?;
+        let (unit_type, address_size, abbrev_offset) = match version {
+            2..=4 => {
+                let abbrev_offset = r.offset_of(format)?;
+                (DW_UT_COMPILE, r.u8()?, abbrev_offset)
+            }
+            5 => {
+                let unit_type = r.u8()?;
+                let address_size = r.u8()?;
+                (unit_type, address_size, r.offset_of(format)?)
+            }
+            _ => {
+                return Err(Error::Message(format!(
+                    "unsupported DWARF version {}",
+                    version
+                )))
+            }
+        };
+
+        let mut dwo_id = None;
+        let mut type_signature = None;
+        match unit_type {
+            DW_UT_SKELETON | DW_UT_SPLIT_COMPILE => dwo_id = Some(r.u64()?),
+            DW_UT_TYPE | DW_UT_SPLIT_TYPE => {
+                type_signature = Some((r.u64()?, r.offset_of(format)?));
+            }
+            _ => {}
+        }
+
+        // bases point past the header of their section's contribution
+        let header_size = match format {
+            Format::Dwarf32 => 8,
+            Format::Dwarf64 => 16,
+        };
+        let mut unit = Unit {
+            offset,
+            format,
+            version,
+            unit_type,
+            address_size,
+            abbrev_offset,
+            dwo_id,
+            type_signature,
+            str_offsets_base: header_size,
+            addr_base: header_size,
+            rnglists_base: header_size,
+            loclists_base: header_size,
+            base_address: 0,
+            abbrevs: Abbreviations::parse(sections.debug_abbrev, abbrev_offset)?,
+            sections: *sections,
+            entries_offset: r.offset(),
+            end_offset,
+        };
+
+        let root = unit.root()?;
+        for attr in &root.attributes {
+            match (attr.name, attr.value) {
+                (DW_AT_STR_OFFSETS_BASE, value) => {
+                    unit.str_offsets_base = value.as_u64().unwrap_or(header_size)
+                }
+                (DW_AT_ADDR_BASE | DW_AT_GNU_ADDR_BASE, value) => {
+                    unit.addr_base = value.as_u64().unwrap_or(header_size)
+                }
+                (DW_AT_RNGLISTS_BASE, value) => {
+                    unit.rnglists_base = value.as_u64().unwrap_or(header_size)
+                }
+                (DW_AT_LOCLISTS_BASE, value) => {
+                    unit.loclists_base = value.as_u64().unwrap_or(header_size)
+                }
+                _ => {}
+            }
+        }
+        // the base address may be indexed, so it is resolved once addr_base is known
+        if let Some(low_pc) = root.attr(DW_AT_LOW_PC) {
+            unit.base_address = unit.address(low_pc)?.unwrap_or(0);
+        }
+
+        Ok(unit)
+    }
+
+    pub fn sections(&self) -> &DwarfSections<'a> {
+        &self.sections
+    }
+
+    pub fn contains(&self, offset: u64) -> bool {
+        self.offset <= offset && offset < self.end_offset as u64
+    }
+
+    /// Iterates over the DIEs of the unit in depth-first order, decoding them as they are visited.
+    pub fn entries(&self) -> EntryIterator<'_, 'a> {
+        EntryIterator {
+            unit: self,
+            reader: Reader::at(
+                &self.sections.debug_info[..self.end_offset],
+                self.entries_offset,
+            ),
+            depth: 0,
+            done: false,
+        }
+    }
+
+    /// Iterates over the DIE at `offset` and its descendants.
+    pub fn entries_at(&self, offset: u64) -> EntryIterator<'_, 'a> {
+        EntryIterator {
+            unit: self,
+            reader: Reader::at(
+                &self.sections.debug_info[..self.end_offset],
+                offset as usize,
+            ),
+            depth: 0,
+            done: !self.contains(offset),
+        }
+    }
+
+    pub fn entry_at(&self, offset: u64) -> Result<Die<'a>, Error> {
+        self.entries_at(offset).next().unwrap_or_else(|| {
+            Err(Error::Message(format!(
+                "no DIE at offset 0x{:x} in unit 0x{:x}",
+                offset, self.offset
+            )))
+        })
+    }
+
+    /// The DW_TAG_compile_unit (or other unit) DIE.
+    pub fn root(&self) -> Result<Die<'a>, Error> {
+        self.entry_at(self.entries_offset as u64)
+    }
+
+    /// The immediate children of a DIE.
+    pub fn children(&self, die: &Die) -> Result<Vec<Die<'a>>, Error> {
+        if !die.has_children {
+            return Ok(vec![]);
+        }
+        self.entries_at(die.offset)
+            .skip(1)
+            .filter(|entry| !matches!(entry, Ok(e) if e.depth != 1))
+            .collect()
+    }
+
+    /// Resolves string forms, including indexed strings, to the string they reference.
+    pub fn string(&self, value: &AttributeValue<'a>) -> Result<Option<&'a str>, Error> {
+        let s = match *value {
+            AttributeValue::String(s) => s,
+            AttributeValue::DebugStrRef(offset) => reader::str_at(self.sections.debug_str, offset)?,
+            AttributeValue::SupStrRef(offset) => {
+                reader::str_at(self.sections.debug_str_sup, offset)?
+            }
+            AttributeValue::DebugLineStrRef(offset) => {
+                reader::str_at(self.sections.debug_line_str, offset)?
+            }
+            AttributeValue::DebugStrIndex(index) => {
+                let size = self.format.offset_size() as u64;
+                let mut r = Reader::at(
+                    self.sections.debug_str_offsets,
+                    (self.str_offsets_base + index * size) as usize,
+                );
+                reader::str_at(self.sections.debug_str, r.offset_of(self.format)?)?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(s))
+    }
+
+    /// Resolves address forms, including indexed addresses in `.debug_addr`.
+    pub fn address(&self, value: &AttributeValue<'a>) -> Result<Option<u64>, Error> {
+        match *value {
+            AttributeValue::Address(address) => Ok(Some(address)),
+            AttributeValue::AddressIndex(index) => self.address_index(index).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn address_index(&self, index: u64) -> Result<u64, Error> {
+        let size = self.address_size as u64;
+        let mut r = Reader::at(
+            self.sections.debug_addr,
+            (self.addr_base + index * size) as usize,
+        );
+        r.sized(self.address_size)
+    }
+
+    /// The value of DW_AT_name.
+    pub fn name(&self, die: &Die<'a>) -> Result<Option<&'a str>, Error> {
+        match die.attr(DW_AT_NAME) {
+            Some(value) => self.string(value),
+            None => Ok(None),
+        }
+    }
+
+    /// The address ranges covered by a DIE, from DW_AT_low_pc and DW_AT_high_pc
+    /// or from the range list referenced by DW_AT_ranges.
+    pub fn ranges(&self, die: &Die<'a>) -> Result<Vec<Range>, Error> {
+        if let Some(ranges) = die.attr(DW_AT_RANGES) {
+            return match *ranges {
+                AttributeValue::RangeListIndex(index) => {
+                    let offset =
+                        self.list_offset(self.sections.debug_rnglists, self.rnglists_base, index)?;
+                    lists::parse_rnglist(self, offset)
+                }
+                AttributeValue::SecOffset(offset) if self.version >= 5 => {
+                    lists::parse_rnglist(self, offset)
+                }
+                AttributeValue::SecOffset(offset) => lists::parse_debug_ranges(self, offset),
+                _ => Err(Error::Message("invalid DW_AT_ranges form".to_string())),
+            };
+        }
+
+        let low_pc = match die.attr(DW_AT_LOW_PC) {
+            Some(value) => self.address(value)?,
+            None => None,
+        };
+        let (low_pc, high_pc) = match (low_pc, die.attr(DW_AT_HIGH_PC)) {
+            (Some(low_pc), Some(high_pc)) => match self.address(high_pc)? {
+                Some(high_pc) => (low_pc, high_pc),
+                // constant forms are an offset from DW_AT_low_pc
+                None => (low_pc, low_pc + high_pc.as_u64().unwrap_or(0)),
+            },
+            _ => return Ok(vec![]),
+        };
+        Ok(vec![Range {
+            begin: low_pc,
+            end: high_pc,
+        }])
+    }
+
+    /// The location list referenced by an attribute such as DW_AT_location.
+    pub fn locations(
+        &self,
+        value: &AttributeValue<'a>,
+    ) -> Result<Vec<LocationListEntry<'a>>, Error> {
+        match *value {
+            AttributeValue::LocListIndex(index) => {
+                let offset =
+                    self.list_offset(self.sections.debug_loclists, self.loclists_base, index)?;
+                lists::parse_loclist(self, offset)
+            }
+            AttributeValue::SecOffset(offset) if self.version >= 5 => {
+                lists::parse_loclist(self, offset)
+            }
+            AttributeValue::SecOffset(offset) => lists::parse_debug_loc(self, offset),
+            // a single location description that is valid everywhere
+            AttributeValue::Exprloc(expr) | AttributeValue::Block(expr) => {
+                Ok(vec![LocationListEntry { range: None, expr }])
+            }
+            _ => Err(Error::Message("invalid location form".to_string())),
+        }
+    }
+
+    /// The line number program referenced by DW_AT_stmt_list of the root DIE.
+    pub fn line_program(&self) -> Result<Option<LineProgram<'a>>, Error> {
+        let root = self.root()?;
+        match root.attr(DW_AT_STMT_LIST).and_then(|value| value.as_u64()) {
+            Some(offset) => LineProgram::parse(&self.sections, offset).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Offset tables in `.debug_rnglists` and `.debug_loclists` hold list offsets
+    /// relative to the base.
+    fn list_offset(&self, section: &[u8], base: u64, index: u64) -> Result<u64, Error> {
+        let size = self.format.offset_size() as u64;
+        let mut r = Reader::at(section, (base + index * size) as usize);
+        Ok(base + r.offset_of(self.format)?)
+    }
+
+    fn read_value(
+        &self,
+        r: &mut Reader<'a>,
+        spec: &AttributeSpec,
+    ) -> Result<AttributeValue<'a>, Error> {
+        read_value(r, spec.form, spec.implicit_const, self)
+    }
+}
+
+fn read_value<'a>(
+    r: &mut Reader<'a>,
+    form: u64,
+    implicit_const: i64,
+    unit: &Unit<'a>,
+) -> Result<AttributeValue<'a>, Error> {
+    let format = unit.format;
+    let unit_ref = |offset: u64| AttributeValue::Reference(unit.offset + offset);
+
+    let value = match form {
+        DW_FORM_ADDR => AttributeValue::Address(r.sized(unit.address_size)?),
+        DW_FORM_BLOCK1 => AttributeValue::Block(r.u8().and_then(|len| r.bytes(len as usize))?),
+        DW_FORM_BLOCK2 => AttributeValue::Block(r.u16().and_then(|len| r.bytes(len as usize))?),
+        DW_FORM_BLOCK4 => AttributeValue::Block(r.u32().and_then(|len| r.bytes(len as usize))?),
+        DW_FORM_BLOCK => AttributeValue::Block(r.uleb128().and_then(|len| r.bytes(len as usize))?),
+        DW_FORM_DATA1 => AttributeValue::Constant(r.u8()? as u64),
+        DW_FORM_DATA2 => AttributeValue::Constant(r.u16()? as u64),
+        DW_FORM_DATA4 => AttributeValue::Constant(r.u32()? as u64),
+        DW_FORM_DATA8 => AttributeValue::Constant(r.u64()?),
+        DW_FORM_DATA16 => AttributeValue::Data16(r.bytes(16)?),
+        DW_FORM_UDATA => AttributeValue::Constant(r.uleb128()?),
+        DW_FORM_SDATA => AttributeValue::Signed(r.sleb128()?),
+        DW_FORM_IMPLICIT_CONST => AttributeValue::Signed(implicit_const),
+        DW_FORM_EXPRLOC => {
+            AttributeValue::Exprloc(r.uleb128().and_then(|len| r.bytes(len as usize))?)
+        }
+        DW_FORM_FLAG => AttributeValue::Flag(r.u8()? != 0),
+        DW_FORM_FLAG_PRESENT => AttributeValue::Flag(true),
+        DW_FORM_SEC_OFFSET => AttributeValue::SecOffset(r.offset_of(format)?),
+        DW_FORM_REF1 => unit_ref(r.u8()? as u64),
+        DW_FORM_REF2 => unit_ref(r.u16()? as u64),
+        DW_FORM_REF4 => unit_ref(r.u32()? as u64),
+        DW_FORM_REF8 => unit_ref(r.u64()?),
+        DW_FORM_REF_UDATA => unit_ref(r.uleb128()?),
+        // DWARF 2 encodes DW_FORM_ref_addr with the size of an address
+        DW_FORM_REF_ADDR if unit.version == 2 => {
+            AttributeValue::Reference(r.sized(unit.address_size)?)
+        }
+        DW_FORM_REF_ADDR => AttributeValue::Reference(r.offset_of(format)?),
+        DW_FORM_REF_SIG8 => AttributeValue::TypeSignature(r.u64()?),
+        DW_FORM_REF_SUP4 => AttributeValue::Supplementary(r.u32()? as u64),
+        DW_FORM_REF_SUP8 => AttributeValue::Supplementary(r.u64()?),
+        DW_FORM_GNU_REF_ALT => AttributeValue::Supplementary(r.offset_of(format)?),
+        DW_FORM_STRP_SUP | DW_FORM_GNU_STRP_ALT => AttributeValue::SupStrRef(r.offset_of(format)?),
+        DW_FORM_STRING => AttributeValue::String(r.str()?),
+        DW_FORM_STRP => AttributeValue::DebugStrRef(r.offset_of(format)?),
+        DW_FORM_LINE_STRP => AttributeValue::DebugLineStrRef(r.offset_of(format)?),
+        DW_FORM_STRX => AttributeValue::DebugStrIndex(r.uleb128()?),
+        DW_FORM_STRX1 => AttributeValue::DebugStrIndex(r.u8()? as u64),
+        DW_FORM_STRX2 => AttributeValue::DebugStrIndex(r.u16()? as u64),
+        DW_FORM_STRX3 => AttributeValue::DebugStrIndex(read_u24(r)?),
+        DW_FORM_STRX4 => AttributeValue::DebugStrIndex(r.u32()? as u64),
+        DW_FORM_ADDRX => AttributeValue::AddressIndex(r.uleb128()?),
+        DW_FORM_ADDRX1 => AttributeValue::AddressIndex(r.u8()? as u64),
+        DW_FORM_ADDRX2 => AttributeValue::AddressIndex(r.u16()? as u64),
+        DW_FORM_ADDRX3 => AttributeValue::AddressIndex(read_u24(r)?),
+        DW_FORM_ADDRX4 => AttributeValue::AddressIndex(r.u32()? as u64),
+        DW_FORM_LOCLISTX => AttributeValue::LocListIndex(r.uleb128()?),
+        DW_FORM_RNGLISTX => AttributeValue::RangeListIndex(r.uleb128()?),
+        DW_FORM_INDIRECT => {
+            let form = r.uleb128()?;
+            return read_value(r, form, implicit_const, unit);
+        }
+        _ => {
+            return Err(Error::Message(format!(
+                "unsupported attribute form 0x{:x}",
+                form
+            )))
+        }
+    };
+    Ok(value)
+}
+
+fn read_u24(r: &mut Reader) -> Result<u64, Error> {
+    let bytes = r.bytes(3)?;
+    Ok(bytes[0] as u64 | (bytes[1] as u64) << 8 | (bytes[2] as u64) << 16)
+}
+
+/// Iterates over DIEs in depth-first order, stopping at the end of the subtree
+/// where iteration started.
+pub struct EntryIterator<'u, 'a> {
+    unit: &'u Unit<'a>,
+    reader: Reader<'a>,
+    depth: usize,
+    done: bool,
+}
+
+impl<'a> Iterator for EntryIterator<'_, 'a> {
+    type Item = Result<Die<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done && !self.reader.is_empty() {
+            let offset = self.reader.offset() as u64;
+            let code = match self.reader.uleb128() {
+                Ok(code) => code,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            };
+
+            // a null entry ends the list of siblings
+            if code == 0 {
+                self.depth = self.depth.saturating_sub(1);
+                if self.depth == 0 {
+                    self.done = true;
+                }
+                continue;
+            }
+
+            let die = self.read_die(offset, code);
+            match &die {
+                Ok(die) if die.has_children => self.depth += 1,
+                Ok(_) if self.depth == 0 => self.done = true,
+                Ok(_) => {}
+                Err(_) => self.done = true,
+            }
+            return Some(die);
+        }
+        None
+    }
+}
+
+impl<'a> EntryIterator<'_, 'a> {
+    fn read_die(&mut self, offset: u64, code: u64) -> Result<Die<'a>, Error> {
+        let abbrev = self.unit.abbrevs.get(code).ok_or_else(|| {
+            Error::Message(format!(
+                "unknown abbreviation {} at offset 0x{:x}",
+                code, offset
+            ))
+        })?;
+
+        let mut attributes = Vec::with_capacity(abbrev.attributes.len());
+        for spec in &abbrev.attributes {
+            attributes.push(Attribute {
+                name: spec.name,
+                value: self.unit.read_value(&mut self.reader, spec)?,
+            });
+        }
+
+        Ok(Die {
+            offset,
+            depth: self.depth,
+            tag: abbrev.tag,
+            has_children: abbrev.has_children,
+            attributes,
+        })
+    }
+}
+
+/// Iterates over the units of `.debug_info`, parsing each header and abbreviation
+/// table only when it is reached.
+pub struct UnitIterator<'a> {
+    sections: DwarfSections<'a>,
+    offset: u64,
+}
+
+impl<'a> UnitIterator<'a> {
+    pub fn new(sections: &DwarfSections<'a>) -> UnitIterator<'a> {
+        UnitIterator {
+            sections: *sections,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for UnitIterator<'a> {
+    type Item = Result<Unit<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset as usize >= self.sections.debug_info.len() {
+            return None;
+        }
+
+        let unit = Unit::parse(&self.sections, self.offset);
+        match &unit {
+            Ok(unit) => self.offset = unit.end_offset as u64,
+            Err(_) => self.offset = self.sections.debug_info.len() as u64,
+        }
+        Some(unit)
+    }
+}
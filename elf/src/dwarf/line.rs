@@ -0,0 +1,451 @@
+use crate::raw::{header::Headers, Error};
+
+use super::{
+    reader::{self, Format, Reader},
+    DwarfSections, DW_FORM_BLOCK, DW_FORM_BLOCK1, DW_FORM_BLOCK2, DW_FORM_BLOCK4, DW_FORM_DATA1,
+    DW_FORM_DATA16, DW_FORM_DATA2, DW_FORM_DATA4, DW_FORM_DATA8, DW_FORM_LINE_STRP, DW_FORM_STRING,
+    DW_FORM_STRP, DW_FORM_UDATA, DW_LNCT_DIRECTORY_INDEX, DW_LNCT_MD5, DW_LNCT_PATH, DW_LNCT_SIZE,
+    DW_LNCT_TIMESTAMP, DW_LNE_DEFINE_FILE, DW_LNE_END_SEQUENCE, DW_LNE_SET_ADDRESS,
+    DW_LNE_SET_DISCRIMINATOR, DW_LNS_ADVANCE_LINE, DW_LNS_ADVANCE_PC, DW_LNS_CONST_ADD_PC,
+    DW_LNS_COPY, DW_LNS_FIXED_ADVANCE_PC, DW_LNS_NEGATE_STMT, DW_LNS_SET_BASIC_BLOCK,
+    DW_LNS_SET_COLUMN, DW_LNS_SET_EPILOGUE_BEGIN, DW_LNS_SET_FILE, DW_LNS_SET_ISA,
+    DW_LNS_SET_PROLOGUE_END,
+};
+
+/// The header of a line number program in `.debug_line`.
+#[derive(Debug, Clone)]
+pub struct LineProgramHeader<'a> {
+    /// Offset of the program in `.debug_line`, as referenced by DW_AT_stmt_list
+    pub offset: u64,
+    pub format: Format,
+    pub version: u16,
+    pub address_size: u8,
+    pub minimum_instruction_length: u8,
+    pub maximum_operations_per_instruction: u8,
+    pub default_is_stmt: bool,
+    pub line_base: i8,
+    pub line_range: u8,
+    pub opcode_base: u8,
+    pub standard_opcode_lengths: &'a [u8],
+    pub include_directories: Vec<&'a str>,
+    pub file_names: Vec<FileEntry<'a>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileEntry<'a> {
+    pub path: &'a str,
+    pub directory_index: u64,
+    pub timestamp: u64,
+    pub size: u64,
+    pub md5: Option<[u8; 16]>,
+}
+
+/// A row of the line number matrix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRow {
+    pub address: u64,
+    pub file: u64,
+    pub line: u64,
+    pub column: u64,
+    pub is_stmt: bool,
+    pub basic_block: bool,
+    pub end_sequence: bool,
+    pub prologue_end: bool,
+    pub epilogue_begin: bool,
+    pub isa: u64,
+    pub discriminator: u64,
+}
+
+/// A line number program and the rows it produces.
+#[derive(Debug, Clone)]
+pub struct LineProgram<'a> {
+    pub header: LineProgramHeader<'a>,
+    pub rows: Vec<LineRow>,
+}
+
+/// The source location of an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineLocation<'a> {
+    pub directory: Option<&'a str>,
+    pub file: &'a str,
+    pub line: u64,
+    pub column: u64,
+}
+
+impl LineLocation<'_> {
+    /// The path of the source file, relative to its directory unless it is absolute.
+    pub fn path(&self) -> String {
+        match self.directory {
+            Some(dir) if !self.file.starts_with('/') && !dir.is_empty() => {
+                format!("{}/{}", dir.trim_end_matches('/'), self.file)
+            }
+            _ => self.file.to_string(),
+        }
+    }
+}
+
+impl<'a> LineProgram<'a> {
+    /// Parses the line number program at `offset` in `.debug_line` and runs it.
+    pub fn parse(sections: &DwarfSections<'a>, offset: u64) -> Result<LineProgram<'a>, Error> {
+        let mut r = Reader::at(sections.debug_line, offset as usize);
+        let (unit_length, format) = r.initial_length()?;
+        let mut r = r.split(unit_length)?;
+
+        let version = r.u16()?;
+        if !(2..=5).contains(&version) {
+            return Err(Error::Message(format!(
+                "unsupported line table version {}",
+                version
+            )));
+        }
+
+        let mut address_size = 8;
+        if version >= 5 {
+            address_size = r.u8()?;
+            let _segment_selector_size = r.u8()?;
+        }
+        let header_length = r.offset_of(format)?;
+        let program_offset = usize::try_from(header_length)
+            .ok()
+            .and_then(|len| r.offset().checked_add(len))
+            .ok_or_else(reader::truncated)?;
+
+        let minimum_instruction_length = r.u8()?;
+        let maximum_operations_per_instruction = if version >= 4 { r.u8()? } else { 1 };
+        let default_is_stmt = r.u8()? != 0;
+        let line_base = r.i8()?;
+        let line_range = r.u8()?;
+        let opcode_base = r.u8()?;
+        let standard_opcode_lengths = r.bytes(opcode_base.saturating_sub(1) as usize)?;
+        if line_range == 0 {
+            return Err(Error::Message("invalid line range 0".to_string()));
+        }
+
+        let mut header = LineProgramHeader {
+            offset,
+            format,
+            version,
+            address_size,
+            minimum_instruction_length,
+            maximum_operations_per_instruction,
+            default_is_stmt,
+            line_base,
+            line_range,
+            opcode_base,
+            standard_opcode_lengths,
+            include_directories: vec![],
+            file_names: vec![],
+        };
+
+        if version >= 5 {
+            for entry in read_entries(&mut r, &header, sections)? {
+                header.include_directories.push(entry.path);
+            }
+            header.file_names = read_entries(&mut r, &header, sections)?;
+        } else {
+            loop {
+                let dir = r.str()?;
+                if dir.is_empty() {
+                    break;
+                }
+                header.include_directories.push(dir);
+            }
+            loop {
+                let path = r.str()?;
+                if path.is_empty() {
+                    break;
+                }
+                header.file_names.push(read_file_entry(&mut r, path)?);
+            }
+        }
+
+        let mut program = r.clone();
+        program.skip(program_offset.saturating_sub(r.offset()))?;
+        let rows = run_program(&mut program, &mut header)?;
+
+        Ok(LineProgram { header, rows })
+    }
+
+    /// Finds the row covering an address, i.e. the last row at or before it in the
+    /// sequence containing it.
+    pub fn find_row(&self, address: u64) -> Option<&LineRow> {
+        let mut start = 0;
+        for (i, row) in self.rows.iter().enumerate() {
+            if !row.end_sequence {
+                continue;
+            }
+            let sequence = &self.rows[start..i];
+            start = i + 1;
+
+            match sequence.first() {
+                Some(first) if first.address <= address && address < row.address => {}
+                _ => continue,
+            }
+            let index = sequence.partition_point(|r| r.address <= address);
+            return sequence.get(index - 1);
+        }
+        None
+    }
+
+    /// Resolves the file index of a row to its directory and path.
+    pub fn file(&self, index: u64) -> Option<(Option<&'a str>, &'a str)> {
+        // file indices are 1-based before DWARF 5
+        let index = if self.header.version >= 5 {
+            index
+        } else {
+            index.checked_sub(1)?
+        };
+        let entry = self.header.file_names.get(index as usize)?;
+
+        let directory = if self.header.version >= 5 {
+            self.header
+                .include_directories
+                .get(entry.directory_index as usize)
+                .copied()
+        } else {
+            // directory 0 is the compilation directory, which is only known from .debug_info
+            entry
+                .directory_index
+                .checked_sub(1)
+                .and_then(|i| self.header.include_directories.get(i as usize))
+                .copied()
+        };
+        Some((directory, entry.path))
+    }
+
+    pub fn find_location(&self, address: u64) -> Option<LineLocation<'a>> {
+        let row = self.find_row(address)?;
+        let (directory, file) = self.file(row.file)?;
+        Some(LineLocation {
+            directory,
+            file,
+            line: row.line,
+            column: row.column,
+        })
+    }
+}
+
+/// All line number programs of an object, for looking up the source location of addresses.
+#[derive(Debug, Clone)]
+pub struct LineTable<'a> {
+    pub programs: Vec<LineProgram<'a>>,
+}
+
+impl<'a> LineTable<'a> {
+    /// Locates `.debug_line` through the section headers and parses every program in it.
+    pub fn load<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers) -> Result<LineTable<'a>, Error> {
+        LineTable::parse(&DwarfSections::load(buf, elf)?)
+    }
+
+    pub fn parse(sections: &DwarfSections<'a>) -> Result<LineTable<'a>, Error> {
+        let mut programs = vec![];
+        let mut offset = 0;
+        while (offset as usize) < sections.debug_line.len() {
+            let mut r = Reader::at(sections.debug_line, offset as usize);
+            let (unit_length, _) = r.initial_length()?;
+            programs.push(LineProgram::parse(sections, offset)?);
+            offset = r.offset() as u64 + unit_length;
+        }
+        Ok(LineTable { programs })
+    }
+
+    pub fn find_location(&self, address: u64) -> Option<LineLocation<'a>> {
+        self.programs
+            .iter()
+            .find_map(|program| program.find_location(address))
+    }
+}
+
+fn read_file_entry<'a>(r: &mut Reader<'a>, path: &'a str) -> Result<FileEntry<'a>, Error> {
+    Ok(FileEntry {
+        path,
+        directory_index: r.uleb128()?,
+        timestamp: r.uleb128()?,
+        size: r.uleb128()?,
+        md5: None,
+    })
+}
+
+/// Reads a DWARF 5 directory or file name table, whose entries are described by
+/// a list of (content type, form) pairs.
+fn read_entries<'a>(
+    r: &mut Reader<'a>,
+    header: &LineProgramHeader,
+    sections: &DwarfSections<'a>,
+) -> Result<Vec<FileEntry<'a>>, Error> {
+    let format_count = r.u8()?;
+    let mut formats = vec![];
+    for _ in 0..format_count {
+        formats.push((r.uleb128()?, r.uleb128()?));
+    }
+
+    let count = r.uleb128()?;
+    let mut entries = vec![];
+    for _ in 0..count {
+        let mut entry = FileEntry::default();
+        for &(content, form) in &formats {
+            match (content, form) {
+                (DW_LNCT_PATH, DW_FORM_STRING) => entry.path = r.str()?,
+                (DW_LNCT_PATH, DW_FORM_LINE_STRP) => {
+                    let offset = r.offset_of(header.format)?;
+                    entry.path = reader::str_at(sections.debug_line_str, offset)?;
+                }
+                (DW_LNCT_PATH, DW_FORM_STRP) => {
+                    let offset = r.offset_of(header.format)?;
+                    entry.path = reader::str_at(sections.debug_str, offset)?;
+                }
+                (DW_LNCT_MD5, DW_FORM_DATA16) => {
+                    entry.md5 = Some(r.bytes(16)?.try_into().unwrap());
+                }
+                (_, _) => {
+                    let value = read_constant(r, form)?;
+                    match content {
+                        DW_LNCT_DIRECTORY_INDEX => entry.directory_index = value,
+                        DW_LNCT_TIMESTAMP => entry.timestamp = value,
+                        DW_LNCT_SIZE => entry.size = value,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reads a constant, skipping over blocks, which are only used by vendor content types.
+fn read_constant(r: &mut Reader, form: u64) -> Result<u64, Error> {
+    let value = match form {
+        DW_FORM_DATA1 => r.u8()? as u64,
+        DW_FORM_DATA2 => r.u16()? as u64,
+        DW_FORM_DATA4 => r.u32()? as u64,
+        DW_FORM_DATA8 => r.u64()?,
+        DW_FORM_UDATA => r.uleb128()?,
+        DW_FORM_DATA16 => r.skip(16).map(|_| 0)?,
+        DW_FORM_BLOCK1 => r.u8().and_then(|len| r.skip(len as usize)).map(|_| 0)?,
+        DW_FORM_BLOCK2 => r.u16().and_then(|len| r.skip(len as usize)).map(|_| 0)?,
+        DW_FORM_BLOCK4 => r.u32().and_then(|len| r.skip(len as usize)).map(|_| 0)?,
+        DW_FORM_BLOCK => r
+            .uleb128()
+            .and_then(|len| r.skip(len as usize))
+            .map(|_| 0)?,
+        _ => {
+            return Err(Error::Message(format!(
+                "unsupported form 0x{:x} in line table header",
+                form
+            )))
+        }
+    };
+    Ok(value)
+}
+
+/// Runs the line number state machine, returning the rows it emits.
+fn run_program<'a>(
+    r: &mut Reader<'a>,
+    header: &mut LineProgramHeader<'a>,
+) -> Result<Vec<LineRow>, Error> {
+    let initial = LineRow {
+        address: 0,
+        file: 1,
+        line: 1,
+        column: 0,
+        is_stmt: header.default_is_stmt,
+        basic_block: false,
+        end_sequence: false,
+        prologue_end: false,
+        epilogue_begin: false,
+        isa: 0,
+        discriminator: 0,
+    };
+    let min_inst = header.minimum_instruction_length as u64;
+    let line_range = header.line_range;
+
+    let mut rows = vec![];
+    let mut state = initial;
+
+    let emit = |rows: &mut Vec<LineRow>, state: &mut LineRow| {
+        rows.push(*state);
+        state.basic_block = false;
+        state.prologue_end = false;
+        state.epilogue_begin = false;
+        state.discriminator = 0;
+    };
+
+    while !r.is_empty() {
+        let opcode = r.u8()?;
+        if opcode >= header.opcode_base {
+            let adjusted = opcode - header.opcode_base;
+            state.address = state
+                .address
+                .wrapping_add((adjusted / line_range) as u64 * min_inst);
+            let delta = header.line_base as i64 + (adjusted % line_range) as i64;
+            state.line = state.line.wrapping_add(delta as u64);
+            emit(&mut rows, &mut state);
+            continue;
+        }
+
+        match opcode {
+            0 => {
+                let len = r.uleb128()?;
+                let mut ext = r.split(len)?;
+                if len == 0 {
+                    continue;
+                }
+                match ext.u8()? {
+                    DW_LNE_END_SEQUENCE => {
+                        state.end_sequence = true;
+                        emit(&mut rows, &mut state);
+                        state = initial;
+                    }
+                    DW_LNE_SET_ADDRESS => {
+                        let size = match len - 1 {
+                            size @ 1..=8 => size as u8,
+                            size => {
+                                return Err(Error::Message(format!(
+                                    "invalid DW_LNE_set_address size {}",
+                                    size
+                                )))
+                            }
+                        };
+                        state.address = ext.sized(size)?
+                    }
+                    DW_LNE_DEFINE_FILE => {
+                        let path = ext.str()?;
+                        let entry = read_file_entry(&mut ext, path)?;
+                        header.file_names.push(entry);
+                    }
+                    DW_LNE_SET_DISCRIMINATOR => state.discriminator = ext.uleb128()?,
+                    _ => {}
+                }
+            }
+            DW_LNS_COPY => emit(&mut rows, &mut state),
+            DW_LNS_ADVANCE_PC => {
+                state.address = state.address.wrapping_add(r.uleb128()? * min_inst)
+            }
+            DW_LNS_ADVANCE_LINE => state.line = state.line.wrapping_add(r.sleb128()? as u64),
+            DW_LNS_SET_FILE => state.file = r.uleb128()?,
+            DW_LNS_SET_COLUMN => state.column = r.uleb128()?,
+            DW_LNS_NEGATE_STMT => state.is_stmt = !state.is_stmt,
+            DW_LNS_SET_BASIC_BLOCK => state.basic_block = true,
+            DW_LNS_CONST_ADD_PC => {
+                let adjusted = 255 - header.opcode_base;
+                state.address = state
+                    .address
+                    .wrapping_add((adjusted / line_range) as u64 * min_inst);
+            }
+            DW_LNS_FIXED_ADVANCE_PC => state.address = state.address.wrapping_add(r.u16()? as u64),
+            DW_LNS_SET_PROLOGUE_END => state.prologue_end = true,
+            DW_LNS_SET_EPILOGUE_BEGIN => state.epilogue_begin = true,
+            DW_LNS_SET_ISA => state.isa = r.uleb128()?,
+            _ => {
+                // unknown standard opcodes declare how many ULEB128 operands to skip
+                let operands = header.standard_opcode_lengths[opcode as usize - 1];
+                for _ in 0..operands {
+                    r.uleb128()?;
+                }
+            }
+        }
+    }
+
+    Ok(rows)
+}
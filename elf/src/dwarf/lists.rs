@@ -0,0 +1,211 @@
+use crate::raw::Error;
+
+use super::{
+    info::Unit, reader::Reader, DW_LLE_BASE_ADDRESS, DW_LLE_BASE_ADDRESSX, DW_LLE_DEFAULT_LOCATION,
+    DW_LLE_END_OF_LIST, DW_LLE_GNU_VIEW_PAIR, DW_LLE_OFFSET_PAIR, DW_LLE_STARTX_ENDX,
+    DW_LLE_STARTX_LENGTH, DW_LLE_START_END, DW_LLE_START_LENGTH, DW_RLE_BASE_ADDRESS,
+    DW_RLE_BASE_ADDRESSX, DW_RLE_END_OF_LIST, DW_RLE_OFFSET_PAIR, DW_RLE_STARTX_ENDX,
+    DW_RLE_STARTX_LENGTH, DW_RLE_START_END, DW_RLE_START_LENGTH,
+};
+
+/// A half-open address range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub begin: u64,
+    pub end: u64,
+}
+
+impl Range {
+    pub fn contains(&self, address: u64) -> bool {
+        self.begin <= address && address < self.end
+    }
+}
+
+/// A location description and the addresses it is valid for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocationListEntry<'a> {
+    /// `None` for the default location, which applies where no other entry does
+    pub range: Option<Range>,
+    /// The DWARF expression describing the location
+    pub expr: &'a [u8],
+}
+
+/// Parses a DWARF 5 range list at `offset` in `.debug_rnglists`.
+pub fn parse_rnglist(unit: &Unit, offset: u64) -> Result<Vec<Range>, Error> {
+    let mut r = Reader::at(unit.sections().debug_rnglists, offset as usize);
+    let mut base = unit.base_address;
+    let mut ranges = vec![];
+
+    loop {
+        let range = match r.u8()? {
+            DW_RLE_END_OF_LIST => break,
+            DW_RLE_BASE_ADDRESSX => {
+                base = unit.address_index(r.uleb128()?)?;
+                continue;
+            }
+            DW_RLE_BASE_ADDRESS => {
+                base = r.sized(unit.address_size)?;
+                continue;
+            }
+            DW_RLE_STARTX_ENDX => Range {
+                begin: unit.address_index(r.uleb128()?)?,
+                end: unit.address_index(r.uleb128()?)?,
+            },
+            DW_RLE_STARTX_LENGTH => {
+                let begin = unit.address_index(r.uleb128()?)?;
+                Range {
+                    begin,
+                    end: begin + r.uleb128()?,
+                }
+            }
+            DW_RLE_OFFSET_PAIR => Range {
+                begin: base + r.uleb128()?,
+                end: base + r.uleb128()?,
+            },
+            DW_RLE_START_END => Range {
+                begin: r.sized(unit.address_size)?,
+                end: r.sized(unit.address_size)?,
+            },
+            DW_RLE_START_LENGTH => {
+                let begin = r.sized(unit.address_size)?;
+                Range {
+                    begin,
+                    end: begin + r.uleb128()?,
+                }
+            }
+            kind => {
+                return Err(Error::Message(format!(
+                    "unknown range list entry 0x{:x}",
+                    kind
+                )))
+            }
+        };
+        ranges.push(range);
+    }
+
+    Ok(ranges)
+}
+
+/// Parses a pre-DWARF 5 range list at `offset` in `.debug_ranges`.
+pub fn parse_debug_ranges(unit: &Unit, offset: u64) -> Result<Vec<Range>, Error> {
+    let mut r = Reader::at(unit.sections().debug_ranges, offset as usize);
+    let mut base = unit.base_address;
+    let mut ranges = vec![];
+
+    loop {
+        let begin = r.sized(unit.address_size)?;
+        let end = r.sized(unit.address_size)?;
+        match (begin, end) {
+            (0, 0) => break,
+            // an all-ones begin selects a new base address
+            (u64::MAX, _) => base = end,
+            (begin, end) => ranges.push(Range {
+                begin: base + begin,
+                end: base + end,
+            }),
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// Parses a DWARF 5 location list at `offset` in `.debug_loclists`.
+pub fn parse_loclist<'a>(
+    unit: &Unit<'a>,
+    offset: u64,
+) -> Result<Vec<LocationListEntry<'a>>, Error> {
+    let mut r = Reader::at(unit.sections().debug_loclists, offset as usize);
+    let mut base = unit.base_address;
+    let mut entries = vec![];
+
+    loop {
+        let range = match r.u8()? {
+            DW_LLE_END_OF_LIST => break,
+            DW_LLE_BASE_ADDRESSX => {
+                base = unit.address_index(r.uleb128()?)?;
+                continue;
+            }
+            DW_LLE_BASE_ADDRESS => {
+                base = r.sized(unit.address_size)?;
+                continue;
+            }
+            // GNU location views precede the entry they apply to
+            DW_LLE_GNU_VIEW_PAIR => {
+                r.uleb128()?;
+                r.uleb128()?;
+                continue;
+            }
+            DW_LLE_DEFAULT_LOCATION => None,
+            DW_LLE_STARTX_ENDX => Some(Range {
+                begin: unit.address_index(r.uleb128()?)?,
+                end: unit.address_index(r.uleb128()?)?,
+            }),
+            DW_LLE_STARTX_LENGTH => {
+                let begin = unit.address_index(r.uleb128()?)?;
+                Some(Range {
+                    begin,
+                    end: begin + r.uleb128()?,
+                })
+            }
+            DW_LLE_OFFSET_PAIR => Some(Range {
+                begin: base + r.uleb128()?,
+                end: base + r.uleb128()?,
+            }),
+            DW_LLE_START_END => Some(Range {
+                begin: r.sized(unit.address_size)?,
+                end: r.sized(unit.address_size)?,
+            }),
+            DW_LLE_START_LENGTH => {
+                let begin = r.sized(unit.address_size)?;
+                Some(Range {
+                    begin,
+                    end: begin + r.uleb128()?,
+                })
+            }
+            kind => {
+                return Err(Error::Message(format!(
+                    "unknown location list entry 0x{:x}",
+                    kind
+                )))
+            }
+        };
+        let len = r.uleb128()?;
+        entries.push(LocationListEntry {
+            range,
+            expr: r.bytes(len as usize)?,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Parses a pre-DWARF 5 location list at `offset` in `.debug_loc`.
+pub fn parse_debug_loc<'a>(
+    unit: &Unit<'a>,
+    offset: u64,
+) -> Result<Vec<LocationListEntry<'a>>, Error> {
+    let mut r = Reader::at(unit.sections().debug_loc, offset as usize);
+    let mut base = unit.base_address;
+    let mut entries = vec![];
+
+    loop {
+        let begin = r.sized(unit.address_size)?;
+        let end = r.sized(unit.address_size)?;
+        match (begin, end) {
+            (0, 0) => break,
+            (u64::MAX, _) => base = end,
+            (begin, end) => {
+                let len = r.u16()?;
+                entries.push(LocationListEntry {
+                    range: Some(Range {
+                        begin: base + begin,
+                        end: base + end,
+                    }),
+                    expr: r.bytes(len as usize)?,
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
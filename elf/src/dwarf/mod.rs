@@ -0,0 +1,461 @@
+use std::borrow::Cow;
+
+use crate::raw::{compression::SectionStorage, header::Headers, Error};
+
+pub(crate) mod reader;
+
+pub mod abbrev;
+pub mod expression;
+pub mod info;
+pub mod line;
+pub mod lists;
+
+pub use reader::Format;
+
+pub const DW_UT_COMPILE: u8 = 0x01;
+pub const DW_UT_TYPE: u8 = 0x02;
+pub const DW_UT_PARTIAL: u8 = 0x03;
+pub const DW_UT_SKELETON: u8 = 0x04;
+pub const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+pub const DW_UT_SPLIT_TYPE: u8 = 0x06;
+
+pub const DW_CHILDREN_NO: u8 = 0x00;
+pub const DW_CHILDREN_YES: u8 = 0x01;
+
+pub const DW_TAG_ARRAY_TYPE: u64 = 0x01;
+pub const DW_TAG_CLASS_TYPE: u64 = 0x02;
+pub const DW_TAG_ENTRY_POINT: u64 = 0x03;
+pub const DW_TAG_ENUMERATION_TYPE: u64 = 0x04;
+pub const DW_TAG_FORMAL_PARAMETER: u64 = 0x05;
+pub const DW_TAG_IMPORTED_DECLARATION: u64 = 0x08;
+pub const DW_TAG_LABEL: u64 = 0x0a;
+pub const DW_TAG_LEXICAL_BLOCK: u64 = 0x0b;
+pub const DW_TAG_MEMBER: u64 = 0x0d;
+pub const DW_TAG_POINTER_TYPE: u64 = 0x0f;
+pub const DW_TAG_REFERENCE_TYPE: u64 = 0x10;
+pub const DW_TAG_COMPILE_UNIT: u64 = 0x11;
+pub const DW_TAG_STRING_TYPE: u64 = 0x12;
+pub const DW_TAG_STRUCTURE_TYPE: u64 = 0x13;
+pub const DW_TAG_SUBROUTINE_TYPE: u64 = 0x15;
+pub const DW_TAG_TYPEDEF: u64 = 0x16;
+pub const DW_TAG_UNION_TYPE: u64 = 0x17;
+pub const DW_TAG_UNSPECIFIED_PARAMETERS: u64 = 0x18;
+pub const DW_TAG_VARIANT: u64 = 0x19;
+pub const DW_TAG_COMMON_BLOCK: u64 = 0x1a;
+pub const DW_TAG_COMMON_INCLUSION: u64 = 0x1b;
+pub const DW_TAG_INHERITANCE: u64 = 0x1c;
+pub const DW_TAG_INLINED_SUBROUTINE: u64 = 0x1d;
+pub const DW_TAG_MODULE: u64 = 0x1e;
+pub const DW_TAG_PTR_TO_MEMBER_TYPE: u64 = 0x1f;
+pub const DW_TAG_SET_TYPE: u64 = 0x20;
+pub const DW_TAG_SUBRANGE_TYPE: u64 = 0x21;
+pub const DW_TAG_WITH_STMT: u64 = 0x22;
+pub const DW_TAG_ACCESS_DECLARATION: u64 = 0x23;
+pub const DW_TAG_BASE_TYPE: u64 = 0x24;
+pub const DW_TAG_CATCH_BLOCK: u64 = 0x25;
+pub const DW_TAG_CONST_TYPE: u64 = 0x26;
+pub const DW_TAG_CONSTANT: u64 = 0x27;
+pub const DW_TAG_ENUMERATOR: u64 = 0x28;
+pub const DW_TAG_FILE_TYPE: u64 = 0x29;
+pub const DW_TAG_FRIEND: u64 = 0x2a;
+pub const DW_TAG_NAMELIST: u64 = 0x2b;
+pub const DW_TAG_NAMELIST_ITEM: u64 = 0x2c;
+pub const DW_TAG_PACKED_TYPE: u64 = 0x2d;
+pub const DW_TAG_SUBPROGRAM: u64 = 0x2e;
+pub const DW_TAG_TEMPLATE_TYPE_PARAMETER: u64 = 0x2f;
+pub const DW_TAG_TEMPLATE_VALUE_PARAMETER: u64 = 0x30;
+pub const DW_TAG_THROWN_TYPE: u64 = 0x31;
+pub const DW_TAG_TRY_BLOCK: u64 = 0x32;
+pub const DW_TAG_VARIANT_PART: u64 = 0x33;
+pub const DW_TAG_VARIABLE: u64 = 0x34;
+pub const DW_TAG_VOLATILE_TYPE: u64 = 0x35;
+pub const DW_TAG_DWARF_PROCEDURE: u64 = 0x36;
+pub const DW_TAG_RESTRICT_TYPE: u64 = 0x37;
+pub const DW_TAG_INTERFACE_TYPE: u64 = 0x38;
+pub const DW_TAG_NAMESPACE: u64 = 0x39;
+pub const DW_TAG_IMPORTED_MODULE: u64 = 0x3a;
+pub const DW_TAG_UNSPECIFIED_TYPE: u64 = 0x3b;
+pub const DW_TAG_PARTIAL_UNIT: u64 = 0x3c;
+pub const DW_TAG_IMPORTED_UNIT: u64 = 0x3d;
+pub const DW_TAG_CONDITION: u64 = 0x3f;
+pub const DW_TAG_SHARED_TYPE: u64 = 0x40;
+pub const DW_TAG_TYPE_UNIT: u64 = 0x41;
+pub const DW_TAG_RVALUE_REFERENCE_TYPE: u64 = 0x42;
+pub const DW_TAG_TEMPLATE_ALIAS: u64 = 0x43;
+pub const DW_TAG_COARRAY_TYPE: u64 = 0x44;
+pub const DW_TAG_GENERIC_SUBRANGE: u64 = 0x45;
+pub const DW_TAG_DYNAMIC_TYPE: u64 = 0x46;
+pub const DW_TAG_ATOMIC_TYPE: u64 = 0x47;
+pub const DW_TAG_CALL_SITE: u64 = 0x48;
+pub const DW_TAG_CALL_SITE_PARAMETER: u64 = 0x49;
+pub const DW_TAG_SKELETON_UNIT: u64 = 0x4a;
+pub const DW_TAG_IMMUTABLE_TYPE: u64 = 0x4b;
+pub const DW_TAG_GNU_CALL_SITE: u64 = 0x4109;
+pub const DW_TAG_GNU_CALL_SITE_PARAMETER: u64 = 0x410a;
+
+pub const DW_AT_SIBLING: u64 = 0x01;
+pub const DW_AT_LOCATION: u64 = 0x02;
+pub const DW_AT_NAME: u64 = 0x03;
+pub const DW_AT_ORDERING: u64 = 0x09;
+pub const DW_AT_BYTE_SIZE: u64 = 0x0b;
+pub const DW_AT_BIT_OFFSET: u64 = 0x0c;
+pub const DW_AT_BIT_SIZE: u64 = 0x0d;
+pub const DW_AT_STMT_LIST: u64 = 0x10;
+pub const DW_AT_LOW_PC: u64 = 0x11;
+pub const DW_AT_HIGH_PC: u64 = 0x12;
+pub const DW_AT_LANGUAGE: u64 = 0x13;
+pub const DW_AT_DISCR: u64 = 0x15;
+pub const DW_AT_DISCR_VALUE: u64 = 0x16;
+pub const DW_AT_VISIBILITY: u64 = 0x17;
+pub const DW_AT_IMPORT: u64 = 0x18;
+pub const DW_AT_STRING_LENGTH: u64 = 0x19;
+pub const DW_AT_COMMON_REFERENCE: u64 = 0x1a;
+pub const DW_AT_COMP_DIR: u64 = 0x1b;
+pub const DW_AT_CONST_VALUE: u64 = 0x1c;
+pub const DW_AT_CONTAINING_TYPE: u64 = 0x1d;
+pub const DW_AT_DEFAULT_VALUE: u64 = 0x1e;
+pub const DW_AT_INLINE: u64 = 0x20;
+pub const DW_AT_IS_OPTIONAL: u64 = 0x21;
+pub const DW_AT_LOWER_BOUND: u64 = 0x22;
+pub const DW_AT_PRODUCER: u64 = 0x25;
+pub const DW_AT_PROTOTYPED: u64 = 0x27;
+pub const DW_AT_RETURN_ADDR: u64 = 0x2a;
+pub const DW_AT_START_SCOPE: u64 = 0x2c;
+pub const DW_AT_BIT_STRIDE: u64 = 0x2e;
+pub const DW_AT_UPPER_BOUND: u64 = 0x2f;
+pub const DW_AT_ABSTRACT_ORIGIN: u64 = 0x31;
+pub const DW_AT_ACCESSIBILITY: u64 = 0x32;
+pub const DW_AT_ADDRESS_CLASS: u64 = 0x33;
+pub const DW_AT_ARTIFICIAL: u64 = 0x34;
+pub const DW_AT_BASE_TYPES: u64 = 0x35;
+pub const DW_AT_CALLING_CONVENTION: u64 = 0x36;
+pub const DW_AT_COUNT: u64 = 0x37;
+pub const DW_AT_DATA_MEMBER_LOCATION: u64 = 0x38;
+pub const DW_AT_DECL_COLUMN: u64 = 0x39;
+pub const DW_AT_DECL_FILE: u64 = 0x3a;
+pub const DW_AT_DECL_LINE: u64 = 0x3b;
+pub const DW_AT_DECLARATION: u64 = 0x3c;
+pub const DW_AT_DISCR_LIST: u64 = 0x3d;
+pub const DW_AT_ENCODING: u64 = 0x3e;
+pub const DW_AT_EXTERNAL: u64 = 0x3f;
+pub const DW_AT_FRAME_BASE: u64 = 0x40;
+pub const DW_AT_FRIEND: u64 = 0x41;
+pub const DW_AT_IDENTIFIER_CASE: u64 = 0x42;
+pub const DW_AT_MACRO_INFO: u64 = 0x43;
+pub const DW_AT_NAMELIST_ITEM: u64 = 0x44;
+pub const DW_AT_PRIORITY: u64 = 0x45;
+pub const DW_AT_SEGMENT: u64 = 0x46;
+pub const DW_AT_SPECIFICATION: u64 = 0x47;
+pub const DW_AT_STATIC_LINK: u64 = 0x48;
+pub const DW_AT_TYPE: u64 = 0x49;
+pub const DW_AT_USE_LOCATION: u64 = 0x4a;
+pub const DW_AT_VARIABLE_PARAMETER: u64 = 0x4b;
+pub const DW_AT_VIRTUALITY: u64 = 0x4c;
+pub const DW_AT_VTABLE_ELEM_LOCATION: u64 = 0x4d;
+pub const DW_AT_ALLOCATED: u64 = 0x4e;
+pub const DW_AT_ASSOCIATED: u64 = 0x4f;
+pub const DW_AT_DATA_LOCATION: u64 = 0x50;
+pub const DW_AT_BYTE_STRIDE: u64 = 0x51;
+pub const DW_AT_ENTRY_PC: u64 = 0x52;
+pub const DW_AT_USE_UTF8: u64 = 0x53;
+pub const DW_AT_EXTENSION: u64 = 0x54;
+pub const DW_AT_RANGES: u64 = 0x55;
+pub const DW_AT_TRAMPOLINE: u64 = 0x56;
+pub const DW_AT_CALL_COLUMN: u64 = 0x57;
+pub const DW_AT_CALL_FILE: u64 = 0x58;
+pub const DW_AT_CALL_LINE: u64 = 0x59;
+pub const DW_AT_DESCRIPTION: u64 = 0x5a;
+pub const DW_AT_BINARY_SCALE: u64 = 0x5b;
+pub const DW_AT_DECIMAL_SCALE: u64 = 0x5c;
+pub const DW_AT_SMALL: u64 = 0x5d;
+pub const DW_AT_DECIMAL_SIGN: u64 = 0x5e;
+pub const DW_AT_DIGIT_COUNT: u64 = 0x5f;
+pub const DW_AT_PICTURE_STRING: u64 = 0x60;
+pub const DW_AT_MUTABLE: u64 = 0x61;
+pub const DW_AT_THREADS_SCALED: u64 = 0x62;
+pub const DW_AT_EXPLICIT: u64 = 0x63;
+pub const DW_AT_OBJECT_POINTER: u64 = 0x64;
+pub const DW_AT_ENDIANITY: u64 = 0x65;
+pub const DW_AT_ELEMENTAL: u64 = 0x66;
+pub const DW_AT_PURE: u64 = 0x67;
+pub const DW_AT_RECURSIVE: u64 = 0x68;
+pub const DW_AT_SIGNATURE: u64 = 0x69;
+pub const DW_AT_MAIN_SUBPROGRAM: u64 = 0x6a;
+pub const DW_AT_DATA_BIT_OFFSET: u64 = 0x6b;
+pub const DW_AT_CONST_EXPR: u64 = 0x6c;
+pub const DW_AT_ENUM_CLASS: u64 = 0x6d;
+pub const DW_AT_LINKAGE_NAME: u64 = 0x6e;
+pub const DW_AT_STRING_LENGTH_BIT_SIZE: u64 = 0x6f;
+pub const DW_AT_STRING_LENGTH_BYTE_SIZE: u64 = 0x70;
+pub const DW_AT_RANK: u64 = 0x71;
+pub const DW_AT_STR_OFFSETS_BASE: u64 = 0x72;
+pub const DW_AT_ADDR_BASE: u64 = 0x73;
+pub const DW_AT_RNGLISTS_BASE: u64 = 0x74;
+pub const DW_AT_DWO_NAME: u64 = 0x76;
+pub const DW_AT_REFERENCE: u64 = 0x77;
+pub const DW_AT_RVALUE_REFERENCE: u64 = 0x78;
+pub const DW_AT_MACROS: u64 = 0x79;
+pub const DW_AT_CALL_ALL_CALLS: u64 = 0x7a;
+pub const DW_AT_CALL_ALL_SOURCE_CALLS: u64 = 0x7b;
+pub const DW_AT_CALL_ALL_TAIL_CALLS: u64 = 0x7c;
+pub const DW_AT_CALL_RETURN_PC: u64 = 0x7d;
+pub const DW_AT_CALL_VALUE: u64 = 0x7e;
+pub const DW_AT_CALL_ORIGIN: u64 = 0x7f;
+pub const DW_AT_CALL_PARAMETER: u64 = 0x80;
+pub const DW_AT_CALL_PC: u64 = 0x81;
+pub const DW_AT_CALL_TAIL_CALL: u64 = 0x82;
+pub const DW_AT_CALL_TARGET: u64 = 0x83;
+pub const DW_AT_CALL_TARGET_CLOBBERED: u64 = 0x84;
+pub const DW_AT_CALL_DATA_LOCATION: u64 = 0x85;
+pub const DW_AT_CALL_DATA_VALUE: u64 = 0x86;
+pub const DW_AT_NORETURN: u64 = 0x87;
+pub const DW_AT_ALIGNMENT: u64 = 0x88;
+pub const DW_AT_EXPORT_SYMBOLS: u64 = 0x89;
+pub const DW_AT_DELETED: u64 = 0x8a;
+pub const DW_AT_DEFAULTED: u64 = 0x8b;
+pub const DW_AT_LOCLISTS_BASE: u64 = 0x8c;
+pub const DW_AT_MIPS_LINKAGE_NAME: u64 = 0x2007;
+pub const DW_AT_GNU_DWO_NAME: u64 = 0x2130;
+pub const DW_AT_GNU_DWO_ID: u64 = 0x2131;
+pub const DW_AT_GNU_RANGES_BASE: u64 = 0x2132;
+pub const DW_AT_GNU_ADDR_BASE: u64 = 0x2133;
+pub const DW_AT_GNU_LOCVIEWS: u64 = 0x2137;
+pub const DW_AT_GNU_ENTRY_VIEW: u64 = 0x2138;
+
+pub const DW_RLE_END_OF_LIST: u8 = 0x00;
+pub const DW_RLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_RLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_RLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_RLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_RLE_BASE_ADDRESS: u8 = 0x05;
+pub const DW_RLE_START_END: u8 = 0x06;
+pub const DW_RLE_START_LENGTH: u8 = 0x07;
+
+pub const DW_LLE_END_OF_LIST: u8 = 0x00;
+pub const DW_LLE_BASE_ADDRESSX: u8 = 0x01;
+pub const DW_LLE_STARTX_ENDX: u8 = 0x02;
+pub const DW_LLE_STARTX_LENGTH: u8 = 0x03;
+pub const DW_LLE_OFFSET_PAIR: u8 = 0x04;
+pub const DW_LLE_DEFAULT_LOCATION: u8 = 0x05;
+pub const DW_LLE_BASE_ADDRESS: u8 = 0x06;
+pub const DW_LLE_START_END: u8 = 0x07;
+pub const DW_LLE_START_LENGTH: u8 = 0x08;
+pub const DW_LLE_GNU_VIEW_PAIR: u8 = 0x09;
+
+pub const DW_OP_ADDR: u8 = 0x03;
+pub const DW_OP_DEREF: u8 = 0x06;
+pub const DW_OP_CONST1U: u8 = 0x08;
+pub const DW_OP_CONST1S: u8 = 0x09;
+pub const DW_OP_CONST2U: u8 = 0x0a;
+pub const DW_OP_CONST2S: u8 = 0x0b;
+pub const DW_OP_CONST4U: u8 = 0x0c;
+pub const DW_OP_CONST4S: u8 = 0x0d;
+pub const DW_OP_CONST8U: u8 = 0x0e;
+pub const DW_OP_CONST8S: u8 = 0x0f;
+pub const DW_OP_CONSTU: u8 = 0x10;
+pub const DW_OP_CONSTS: u8 = 0x11;
+pub const DW_OP_DUP: u8 = 0x12;
+pub const DW_OP_DROP: u8 = 0x13;
+pub const DW_OP_OVER: u8 = 0x14;
+pub const DW_OP_PICK: u8 = 0x15;
+pub const DW_OP_SWAP: u8 = 0x16;
+pub const DW_OP_ROT: u8 = 0x17;
+pub const DW_OP_XDEREF: u8 = 0x18;
+pub const DW_OP_ABS: u8 = 0x19;
+pub const DW_OP_AND: u8 = 0x1a;
+pub const DW_OP_DIV: u8 = 0x1b;
+pub const DW_OP_MINUS: u8 = 0x1c;
+pub const DW_OP_MOD: u8 = 0x1d;
+pub const DW_OP_MUL: u8 = 0x1e;
+pub const DW_OP_NEG: u8 = 0x1f;
+pub const DW_OP_NOT: u8 = 0x20;
+pub const DW_OP_OR: u8 = 0x21;
+pub const DW_OP_PLUS: u8 = 0x22;
+pub const DW_OP_PLUS_UCONST: u8 = 0x23;
+pub const DW_OP_SHL: u8 = 0x24;
+pub const DW_OP_SHR: u8 = 0x25;
+pub const DW_OP_SHRA: u8 = 0x26;
+pub const DW_OP_XOR: u8 = 0x27;
+pub const DW_OP_BRA: u8 = 0x28;
+pub const DW_OP_EQ: u8 = 0x29;
+pub const DW_OP_GE: u8 = 0x2a;
+pub const DW_OP_GT: u8 = 0x2b;
+pub const DW_OP_LE: u8 = 0x2c;
+pub const DW_OP_LT: u8 = 0x2d;
+pub const DW_OP_NE: u8 = 0x2e;
+pub const DW_OP_SKIP: u8 = 0x2f;
+pub const DW_OP_LIT0: u8 = 0x30;
+pub const DW_OP_LIT31: u8 = 0x4f;
+pub const DW_OP_REG0: u8 = 0x50;
+pub const DW_OP_REG31: u8 = 0x6f;
+pub const DW_OP_BREG0: u8 = 0x70;
+pub const DW_OP_BREG31: u8 = 0x8f;
+pub const DW_OP_REGX: u8 = 0x90;
+pub const DW_OP_FBREG: u8 = 0x91;
+pub const DW_OP_BREGX: u8 = 0x92;
+pub const DW_OP_PIECE: u8 = 0x93;
+pub const DW_OP_DEREF_SIZE: u8 = 0x94;
+pub const DW_OP_NOP: u8 = 0x96;
+pub const DW_OP_CALL_FRAME_CFA: u8 = 0x9c;
+pub const DW_OP_STACK_VALUE: u8 = 0x9f;
+
+pub const DW_LNS_COPY: u8 = 0x01;
+pub const DW_LNS_ADVANCE_PC: u8 = 0x02;
+pub const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+pub const DW_LNS_SET_FILE: u8 = 0x04;
+pub const DW_LNS_SET_COLUMN: u8 = 0x05;
+pub const DW_LNS_NEGATE_STMT: u8 = 0x06;
+pub const DW_LNS_SET_BASIC_BLOCK: u8 = 0x07;
+pub const DW_LNS_CONST_ADD_PC: u8 = 0x08;
+pub const DW_LNS_FIXED_ADVANCE_PC: u8 = 0x09;
+pub const DW_LNS_SET_PROLOGUE_END: u8 = 0x0a;
+pub const DW_LNS_SET_EPILOGUE_BEGIN: u8 = 0x0b;
+pub const DW_LNS_SET_ISA: u8 = 0x0c;
+
+pub const DW_LNE_END_SEQUENCE: u8 = 0x01;
+pub const DW_LNE_SET_ADDRESS: u8 = 0x02;
+pub const DW_LNE_DEFINE_FILE: u8 = 0x03;
+pub const DW_LNE_SET_DISCRIMINATOR: u8 = 0x04;
+
+pub const DW_LNCT_PATH: u64 = 0x1;
+pub const DW_LNCT_DIRECTORY_INDEX: u64 = 0x2;
+pub const DW_LNCT_TIMESTAMP: u64 = 0x3;
+pub const DW_LNCT_SIZE: u64 = 0x4;
+pub const DW_LNCT_MD5: u64 = 0x5;
+
+pub const DW_FORM_ADDR: u64 = 0x01;
+pub const DW_FORM_BLOCK2: u64 = 0x03;
+pub const DW_FORM_BLOCK4: u64 = 0x04;
+pub const DW_FORM_DATA2: u64 = 0x05;
+pub const DW_FORM_DATA4: u64 = 0x06;
+pub const DW_FORM_DATA8: u64 = 0x07;
+pub const DW_FORM_STRING: u64 = 0x08;
+pub const DW_FORM_BLOCK: u64 = 0x09;
+pub const DW_FORM_BLOCK1: u64 = 0x0a;
+pub const DW_FORM_DATA1: u64 = 0x0b;
+pub const DW_FORM_FLAG: u64 = 0x0c;
+pub const DW_FORM_SDATA: u64 = 0x0d;
+pub const DW_FORM_STRP: u64 = 0x0e;
+pub const DW_FORM_UDATA: u64 = 0x0f;
+pub const DW_FORM_REF_ADDR: u64 = 0x10;
+pub const DW_FORM_REF1: u64 = 0x11;
+pub const DW_FORM_REF2: u64 = 0x12;
+pub const DW_FORM_REF4: u64 = 0x13;
+pub const DW_FORM_REF8: u64 = 0x14;
+pub const DW_FORM_REF_UDATA: u64 = 0x15;
+pub const DW_FORM_INDIRECT: u64 = 0x16;
+pub const DW_FORM_SEC_OFFSET: u64 = 0x17;
+pub const DW_FORM_EXPRLOC: u64 = 0x18;
+pub const DW_FORM_FLAG_PRESENT: u64 = 0x19;
+pub const DW_FORM_STRX: u64 = 0x1a;
+pub const DW_FORM_ADDRX: u64 = 0x1b;
+pub const DW_FORM_REF_SUP4: u64 = 0x1c;
+pub const DW_FORM_STRP_SUP: u64 = 0x1d;
+pub const DW_FORM_DATA16: u64 = 0x1e;
+pub const DW_FORM_LINE_STRP: u64 = 0x1f;
+pub const DW_FORM_REF_SIG8: u64 = 0x20;
+pub const DW_FORM_IMPLICIT_CONST: u64 = 0x21;
+pub const DW_FORM_LOCLISTX: u64 = 0x22;
+pub const DW_FORM_RNGLISTX: u64 = 0x23;
+pub const DW_FORM_REF_SUP8: u64 = 0x24;
+pub const DW_FORM_STRX1: u64 = 0x25;
+pub const DW_FORM_STRX2: u64 = 0x26;
+pub const DW_FORM_STRX3: u64 = 0x27;
+pub const DW_FORM_STRX4: u64 = 0x28;
+pub const DW_FORM_ADDRX1: u64 = 0x29;
+pub const DW_FORM_ADDRX2: u64 = 0x2a;
+pub const DW_FORM_ADDRX3: u64 = 0x2b;
+pub const DW_FORM_ADDRX4: u64 = 0x2c;
+pub const DW_FORM_GNU_REF_ALT: u64 = 0x1f20;
+pub const DW_FORM_GNU_STRP_ALT: u64 = 0x1f21;
+
+/// The contents of the `.debug_*` sections of an object, located through the
+/// section headers. Sections the object does not contain are empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DwarfSections<'a> {
+    pub debug_abbrev: &'a [u8],
+    pub debug_addr: &'a [u8],
+    pub debug_info: &'a [u8],
+    pub debug_line: &'a [u8],
+    pub debug_line_str: &'a [u8],
+    pub debug_loc: &'a [u8],
+    pub debug_loclists: &'a [u8],
+    pub debug_ranges: &'a [u8],
+    pub debug_rnglists: &'a [u8],
+    pub debug_str: &'a [u8],
+    pub debug_str_offsets: &'a [u8],
+    /// `.debug_str` of the supplementary object file, e.g. from `.gnu_debugaltlink`
+    pub debug_str_sup: &'a [u8],
+}
+
+impl<'a> DwarfSections<'a> {
+    /// Borrows the debug sections of an object. Fails if a section is compressed, in
+    /// which case `load_with` decompresses it into a `SectionStorage`.
+    pub fn load<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers) -> Result<DwarfSections<'a>, Error> {
+        DwarfSections::read(buf, elf, |name, data| match data {
+            Cow::Borrowed(data) => Ok(data),
+            Cow::Owned(_) => Err(Error::Message(format!("section {} is compressed", name))),
+        })
+    }
+
+    /// Reads the debug sections of an object, decompressing sections with SHF_COMPRESSED
+    /// and legacy `.zdebug_*` sections into `storage`.
+    pub fn load_with<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+        storage: &'a SectionStorage,
+    ) -> Result<DwarfSections<'a>, Error> {
+        DwarfSections::read(buf, elf, |_, data| Ok(storage.store(data)))
+    }
+
+    fn read<A: AsRef<[u8]>, F>(
+        buf: &'a A,
+        elf: &Headers,
+        keep: F,
+    ) -> Result<DwarfSections<'a>, Error>
+    where
+        F: Fn(&str, Cow<'a, [u8]>) -> Result<&'a [u8], Error>,
+    {
+        let section = |name: &str| -> Result<&'a [u8], Error> {
+            match elf.find_section_data_by_name(buf, name)? {
+                Some(data) => keep(name, data),
+                None => Ok(&[]),
+            }
+        };
+
+        Ok(DwarfSections {
+            debug_abbrev: section(".debug_abbrev")?,
+            debug_addr: section(".debug_addr")?,
+            debug_info: section(".debug_info")?,
+            debug_line: section(".debug_line")?,
+            debug_line_str: section(".debug_line_str")?,
+            debug_loc: section(".debug_loc")?,
+            debug_loclists: section(".debug_loclists")?,
+            debug_ranges: section(".debug_ranges")?,
+            debug_rnglists: section(".debug_rnglists")?,
+            debug_str: section(".debug_str")?,
+            debug_str_offsets: section(".debug_str_offsets")?,
+            debug_str_sup: &[],
+        })
+    }
+
+    /// Fills the sections missing from `self` with those of `other`, e.g. to combine
+    /// a separate debug file with the stripped object it belongs to.
+    pub fn merge(self, other: &DwarfSections<'a>) -> DwarfSections<'a> {
+        let pick = |a: &'a [u8], b: &'a [u8]| if a.is_empty() { b } else { a };
+        DwarfSections {
+            debug_abbrev: pick(self.debug_abbrev, other.debug_abbrev),
+            debug_addr: pick(self.debug_addr, other.debug_addr),
+            debug_info: pick(self.debug_info, other.debug_info),
+            debug_line: pick(self.debug_line, other.debug_line),
+            debug_line_str: pick(self.debug_line_str, other.debug_line_str),
+            debug_loc: pick(self.debug_loc, other.debug_loc),
+            debug_loclists: pick(self.debug_loclists, other.debug_loclists),
+            debug_ranges: pick(self.debug_ranges, other.debug_ranges),
+            debug_rnglists: pick(self.debug_rnglists, other.debug_rnglists),
+            debug_str: pick(self.debug_str, other.debug_str),
+            debug_str_offsets: pick(self.debug_str_offsets, other.debug_str_offsets),
+            debug_str_sup: pick(self.debug_str_sup, other.debug_str_sup),
+        }
+    }
+}
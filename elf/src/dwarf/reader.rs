@@ -0,0 +1,160 @@
+use crate::raw::Error;
+
+/// A cursor over little-endian DWARF data.
+#[derive(Debug, Clone)]
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Reader<'a> {
+        Reader { buf, offset: 0 }
+    }
+
+    pub fn at(buf: &'a [u8], offset: usize) -> Reader<'a> {
+        Reader { buf, offset }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset >= self.buf.len()
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.offset.checked_add(len).ok_or_else(truncated)?;
+        let bytes = self.buf.get(self.offset..end).ok_or_else(truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<(), Error> {
+        self.bytes(len).map(|_| ())
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn i8(&mut self) -> Result<i8, Error> {
+        Ok(self.u8()? as i8)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Reads an unsigned integer of 1, 2, 4 or 8 bytes.
+    pub fn sized(&mut self, size: u8) -> Result<u64, Error> {
+        match size {
+            1 => self.u8().map(u64::from),
+            2 => self.u16().map(u64::from),
+            4 => self.u32().map(u64::from),
+            8 => self.u64(),
+            _ => Err(Error::Message(format!("unsupported value size {}", size))),
+        }
+    }
+
+    pub fn uleb128(&mut self) -> Result<u64, Error> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                result |= ((byte & 0x7f) as u64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+    }
+
+    pub fn sleb128(&mut self) -> Result<i64, Error> {
+        let mut result = 0i64;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            if shift < 64 {
+                result |= ((byte & 0x7f) as i64) << shift;
+            }
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Reads a NUL-terminated string.
+    pub fn str(&mut self) -> Result<&'a str, Error> {
+        let rest = self.buf.get(self.offset..).ok_or_else(truncated)?;
+        let len = rest.iter().position(|&b| b == 0).ok_or_else(truncated)?;
+        let s = std::str::from_utf8(&rest[..len])
+            .map_err(|_| Error::Message("invalid utf-8 string".to_string()))?;
+        self.offset += len + 1;
+        Ok(s)
+    }
+
+    /// Reads a unit length, returning it with the format it implies.
+    pub fn initial_length(&mut self) -> Result<(u64, Format), Error> {
+        match self.u32()? {
+            0xffff_ffff => Ok((self.u64()?, Format::Dwarf64)),
+            len if len >= 0xffff_fff0 => {
+                Err(Error::Message(format!("reserved unit length 0x{:x}", len)))
+            }
+            len => Ok((len as u64, Format::Dwarf32)),
+        }
+    }
+
+    /// Reads a section offset, whose size depends on the unit format.
+    pub fn offset_of(&mut self, format: Format) -> Result<u64, Error> {
+        match format {
+            Format::Dwarf32 => self.u32().map(u64::from),
+            Format::Dwarf64 => self.u64(),
+        }
+    }
+
+    /// Splits off the next `len` bytes as their own reader.
+    pub fn split(&mut self, len: u64) -> Result<Reader<'a>, Error> {
+        Ok(Reader::new(self.bytes(len as usize)?))
+    }
+}
+
+/// The 32-bit or 64-bit DWARF format, which determines the size of section offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Dwarf32,
+    Dwarf64,
+}
+
+impl Format {
+    pub fn offset_size(&self) -> u8 {
+        match self {
+            Format::Dwarf32 => 4,
+            Format::Dwarf64 => 8,
+        }
+    }
+}
+
+/// Reads a NUL-terminated string at an offset of a string section.
+pub(crate) fn str_at(section: &[u8], offset: u64) -> Result<&str, Error> {
+    Reader::at(section, offset as usize).str()
+}
+
+pub(crate) fn truncated() -> Error {
+    Error::Message("truncated DWARF data".to_string())
+}
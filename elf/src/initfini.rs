@@ -0,0 +1,235 @@
+use std::{collections::HashMap, ffi::c_char};
+
+use crate::{
+    raw::{
+        dynamic::{DynamicInfo, DynamicTable},
+        header::Headers,
+        mapping::AddressMap,
+        relocation::{Rela, RelocationTable},
+        Error, DT_FINI_ARRAY, DT_INIT_ARRAY, DT_PREINIT_ARRAY, DT_RELA, DT_RELASZ, EM_AARCH64,
+        EM_X86_64, PT_DYNAMIC, R_AARCH64_RELATIV, R_X86_64_RELATIVE, SHT_FINI_ARRAY,
+        SHT_INIT_ARRAY, SHT_PREINIT_ARRAY,
+    },
+    symbolizer::Symbolizer,
+};
+
+/// Where a constructor or destructor is registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitKind {
+    /// `.preinit_array`, only run for executables
+    PreinitArray,
+    /// DT_INIT, the legacy `_init` function
+    Init,
+    /// `.init_array`
+    InitArray,
+    /// `.fini_array`
+    FiniArray,
+    /// DT_FINI, the legacy `_fini` function
+    Fini,
+}
+
+/// A constructor or destructor function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitFunction<'a> {
+    pub kind: InitKind,
+    pub address: u64,
+    /// Set by `InitFini::symbolize`
+    pub function: Option<&'a str>,
+}
+
+impl InitFunction<'_> {
+    /// Calls a constructor with the arguments the dynamic linker passes.
+    ///
+    /// # Safety
+    /// `address` must be the runtime address of a function in a loaded and relocated image.
+    pub unsafe fn call_init(
+        &self,
+        argc: i32,
+        argv: *const *const c_char,
+        env: *const *const c_char,
+    ) {
+        let f: extern "C" fn(i32, *const *const c_char, *const *const c_char) =
+            std::mem::transmute(self.address as usize);
+        f(argc, argv, env)
+    }
+
+    /// Calls a destructor.
+    ///
+    /// # Safety
+    /// `address` must be the runtime address of a function in a loaded and relocated image.
+    pub unsafe fn call_fini(&self) {
+        let f: extern "C" fn() = std::mem::transmute(self.address as usize);
+        f()
+    }
+}
+
+/// The constructors and destructors of an object, in the order the dynamic linker runs
+/// them: `.preinit_array`, DT_INIT and `.init_array` at startup, then `.fini_array` in
+/// reverse and DT_FINI at exit.
+#[derive(Debug, Clone, Default)]
+pub struct InitFini<'a> {
+    pub constructors: Vec<InitFunction<'a>>,
+    pub destructors: Vec<InitFunction<'a>>,
+}
+
+impl<'a> InitFini<'a> {
+    /// Reads the constructors and destructors from an ELF file, as link-time addresses.
+    /// Array entries of position-independent objects may be zero in the file, e.g. when
+    /// linked with `--no-apply-dynamic-relocs`, so are resolved through their RELATIVE
+    /// relocations in DT_RELA. Entries relocated by DT_RELR hold their addend in place.
+    ///
+    /// Static executables have no dynamic table, so their arrays are read from the
+    /// sections instead.
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<InitFini<'a>, Error> {
+        let hdr = match elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+        {
+            Some(hdr) => hdr,
+            None => return InitFini::parse_sections(buf, elf),
+        };
+        let dynamic = DynamicTable::parse_file_segment(buf, hdr)?;
+        let info = DynamicInfo::parse(buf, elf)?;
+
+        let map = AddressMap::new(buf, elf);
+        let relative = relative_relocations(&map, &dynamic, elf.header.e_machine)?;
+        let resolve = |tag: u64, values: &[u64]| -> Vec<u64> {
+            let base = match dynamic.find_entry(tag) {
+                Some(entry) => entry.get_value(),
+                None => return vec![],
+            };
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| {
+                    let slot = base + (i * std::mem::size_of::<u64>()) as u64;
+                    relative.get(&slot).copied().unwrap_or(value)
+                })
+                .collect()
+        };
+
+        Ok(InitFini::from_parts(
+            resolve(DT_PREINIT_ARRAY, &info.preinit_array),
+            info.init,
+            resolve(DT_INIT_ARRAY, &info.init_array),
+            resolve(DT_FINI_ARRAY, &info.fini_array),
+            info.fini,
+        ))
+    }
+
+    fn parse_sections<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers<'a>,
+    ) -> Result<InitFini<'a>, Error> {
+        let get_array = |sh_type: u32| -> Result<Vec<u64>, Error> {
+            match elf.find_section_header(sh_type) {
+                Some(hdr) => Ok(hdr
+                    .get_section_buffer(buf)?
+                    .chunks_exact(8)
+                    .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+                    .collect()),
+                None => Ok(vec![]),
+            }
+        };
+
+        Ok(InitFini::from_parts(
+            get_array(SHT_PREINIT_ARRAY)?,
+            None,
+            get_array(SHT_INIT_ARRAY)?,
+            get_array(SHT_FINI_ARRAY)?,
+            None,
+        ))
+    }
+
+    /// Reads the constructors and destructors of an image mapped at `base_addr`, as
+    /// runtime addresses that a loader can call.
+    /// Precondition: the relocations of the image have been applied, and the dynamic
+    /// table has not been relocated in place.
+    pub fn from_image(base_addr: usize, dynamic: &DynamicTable) -> Result<InitFini<'a>, Error> {
+        let info = DynamicInfo::from_image(base_addr, dynamic)?;
+        let base = base_addr as u64;
+        Ok(InitFini::from_parts(
+            info.preinit_array,
+            info.init.map(|init| base + init),
+            info.init_array,
+            info.fini_array,
+            info.fini.map(|fini| base + fini),
+        ))
+    }
+
+    fn from_parts(
+        preinit_array: Vec<u64>,
+        init: Option<u64>,
+        init_array: Vec<u64>,
+        fini_array: Vec<u64>,
+        fini: Option<u64>,
+    ) -> InitFini<'a> {
+        let function = |kind: InitKind| {
+            move |address: u64| InitFunction {
+                kind,
+                address,
+                function: None,
+            }
+        };
+
+        let mut constructors: Vec<InitFunction> = preinit_array
+            .into_iter()
+            .map(function(InitKind::PreinitArray))
+            .collect();
+        constructors.extend(init.map(function(InitKind::Init)));
+        constructors.extend(init_array.into_iter().map(function(InitKind::InitArray)));
+
+        let mut destructors: Vec<InitFunction> = fini_array
+            .into_iter()
+            .rev()
+            .map(function(InitKind::FiniArray))
+            .collect();
+        destructors.extend(fini.map(function(InitKind::Fini)));
+
+        InitFini {
+            constructors,
+            destructors,
+        }
+    }
+
+    /// Names the functions through a symbolizer for the same object.
+    pub fn symbolize(&mut self, symbolizer: &Symbolizer<'a>) -> Result<(), Error> {
+        for f in self
+            .constructors
+            .iter_mut()
+            .chain(self.destructors.iter_mut())
+        {
+            // the outermost frame is the function itself rather than an inlined call
+            f.function = symbolizer
+                .symbolize(f.address)?
+                .last()
+                .and_then(|frame| frame.function);
+        }
+        Ok(())
+    }
+}
+
+/// Maps the locations of the RELATIVE relocations in DT_RELA to their addends.
+fn relative_relocations(
+    map: &AddressMap,
+    dynamic: &DynamicTable,
+    machine: u16,
+) -> Result<HashMap<u64, u64>, Error> {
+    let relative_type = match machine {
+        EM_X86_64 => R_X86_64_RELATIVE,
+        EM_AARCH64 => R_AARCH64_RELATIV,
+        _ => return Ok(HashMap::new()),
+    };
+    let (addr, size) = match (dynamic.find_entry(DT_RELA), dynamic.find_entry(DT_RELASZ)) {
+        (Some(addr), Some(size)) => (addr.get_value(), size.get_value()),
+        _ => return Ok(HashMap::new()),
+    };
+
+    let relocs = RelocationTable::<Rela>::from_bytes(map.read_file_backed(addr, size)?)?;
+    Ok(relocs
+        .iter()
+        .filter(|reloc| reloc.get_type() == relative_type)
+        .map(|reloc| (reloc.get_offset(), reloc.get_addend() as u64))
+        .collect())
+}
@@ -8,3 +8,35 @@ pub mod raw;
 
 /// A type-safe builder for constructing valid ELF objects.
 pub mod builder;
+
+/// Reading of core dumps: thread registers, process information and memory.
+pub mod coredump;
+
+/// Reading of static libraries (`ar` archives) and the objects they contain.
+pub mod archive;
+
+/// Reading of DWARF debugging information: units, DIEs and line tables.
+pub mod dwarf;
+
+/// Mapping of addresses to functions and source lines, like `addr2line`.
+pub mod symbolizer;
+
+/// Locating separate debug files through build ids and `.gnu_debuglink`.
+pub mod debuglink;
+
+/// Stack unwinding with call frame information: `.eh_frame`, `.debug_frame` and ARM EHABI.
+pub mod unwind;
+
+/// Enumeration of the constructors and destructors an object registers with the dynamic linker.
+pub mod initfini;
+
+/// Thread-local storage: TLS templates, static TLS layout and TLS relocations.
+pub mod tls;
+
+/// A dynamic linker loading shared objects and their dependencies into the process,
+/// on x86_64 and AArch64 Linux.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub mod loader;
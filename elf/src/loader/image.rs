@@ -0,0 +1,205 @@
+use std::{fs::File, os::fd::AsRawFd};
+
+use crate::raw::{
+    header::{Headers, ProgramHeader},
+    Error, ET_DYN, PF_R, PF_W, PF_X, PT_GNU_RELRO, PT_LOAD,
+};
+
+/// The loadable segments of a shared object mapped into memory, as the dynamic
+/// linker maps them: a single reservation covering all segments, with each segment
+/// mapped from the file over it and its zero-filled part cleared.
+#[derive(Debug)]
+pub struct Image {
+    base: usize,
+    start: usize,
+    size: usize,
+    /// The page range made read-only after relocation, PT_GNU_RELRO
+    relro: Option<(usize, usize)>,
+}
+
+impl Image {
+    /// Maps the PT_LOAD segments of a shared object, with their final protections.
+    pub fn map(file: &File, elf: &Headers) -> Result<Image, Error> {
+        if elf.header.e_type != ET_DYN {
+            return Err(Error::Message(
+                "only position-independent objects can be loaded".to_string(),
+            ));
+        }
+
+        let page_size = page_size();
+        let segments: Vec<_> = elf
+            .program_headers
+            .iter()
+            .filter(|ph| ph.get_type() == PT_LOAD)
+            .collect();
+        let low = segments
+            .iter()
+            .map(|ph| ph.get_vaddr())
+            .min()
+            .ok_or_else(|| Error::Message("no PT_LOAD program headers".to_string()))?;
+        let high = segments
+            .iter()
+            .map(|ph| ph.get_vaddr() + ph.get_memsz())
+            .max()
+            .unwrap_or(low);
+        let low = align_down(low as usize, page_size);
+        let size = align_up(high as usize, page_size) - low;
+
+        // reserve the whole address range, so segments keep their relative placement
+        let start = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if start == libc::MAP_FAILED {
+            return Err(Error::Message(format!(
+                "failed to reserve {} bytes: {}",
+                size,
+                std::io::Error::last_os_error()
+            )));
+        }
+
+        let mut image = Image {
+            base: start as usize - low,
+            start: start as usize,
+            size,
+            relro: None,
+        };
+
+        for ph in segments {
+            image.map_segment(file, ph, page_size)?;
+        }
+
+        image.relro = elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_GNU_RELRO)
+            .map(|ph| {
+                let start = align_down(image.base + ph.get_vaddr() as usize, page_size);
+                let end = align_down(
+                    image.base + (ph.get_vaddr() + ph.get_memsz()) as usize,
+                    page_size,
+                );
+                (start, end)
+            });
+
+        Ok(image)
+    }
+
+    fn map_segment(&self, file: &File, ph: &ProgramHeader, page_size: usize) -> Result<(), Error> {
+        let (vaddr, memsz, filesz) = (ph.get_vaddr(), ph.get_memsz(), ph.get_filesz());
+        let flags = ph.get_flags();
+        let mut prot = 0;
+        if flags & PF_R != 0 {
+            prot |= libc::PROT_READ;
+        }
+        if flags & PF_W != 0 {
+            prot |= libc::PROT_WRITE;
+        }
+        if flags & PF_X != 0 {
+            prot |= libc::PROT_EXEC;
+        }
+
+        let start = align_down(self.base + vaddr as usize, page_size);
+        let file_end = self.base + (vaddr + filesz) as usize;
+        let mem_end = align_up(self.base + (vaddr + memsz) as usize, page_size);
+
+        if filesz > 0 {
+            let ptr = unsafe {
+                libc::mmap(
+                    start as *mut libc::c_void,
+                    align_up(file_end, page_size) - start,
+                    prot,
+                    libc::MAP_PRIVATE | libc::MAP_FIXED,
+                    file.as_raw_fd(),
+                    align_down(ph.get_offset() as usize, page_size) as libc::off_t,
+                )
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(Error::Message(format!(
+                    "failed to map segment at 0x{:x}: {}",
+                    vaddr,
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+
+        // the zero-filled part of the segment, e.g. .bss
+        let zero_start = align_up(file_end, page_size);
+        if filesz < memsz {
+            if file_end < zero_start && filesz > 0 {
+                if prot & libc::PROT_WRITE == 0 {
+                    return Err(Error::Message(
+                        "zero-filled part of a read-only segment".to_string(),
+                    ));
+                }
+                unsafe { std::ptr::write_bytes(file_end as *mut u8, 0, zero_start - file_end) };
+            }
+            let zero_start = if filesz > 0 { zero_start } else { start };
+            if zero_start < mem_end {
+                let ptr = unsafe {
+                    libc::mmap(
+                        zero_start as *mut libc::c_void,
+                        mem_end - zero_start,
+                        prot,
+                        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED,
+                        -1,
+                        0,
+                    )
+                };
+                if ptr == libc::MAP_FAILED {
+                    return Err(Error::Message(format!(
+                        "failed to map zero-filled segment at 0x{:x}: {}",
+                        vaddr,
+                        std::io::Error::last_os_error()
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The address the object is loaded at, added to its virtual addresses.
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Makes the PT_GNU_RELRO range read-only, once relocations have been applied.
+    pub fn protect_relro(&self) -> Result<(), Error> {
+        if let Some((start, end)) = self.relro.filter(|(start, end)| start < end) {
+            let result =
+                unsafe { libc::mprotect(start as *mut libc::c_void, end - start, libc::PROT_READ) };
+            if result != 0 {
+                return Err(Error::Message(format!(
+                    "failed to protect relro: {}",
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.start as *mut libc::c_void, self.size) };
+    }
+}
+
+pub(crate) fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+fn align_down(value: usize, align: usize) -> usize {
+    value & !(align - 1)
+}
+
+fn align_up(value: usize, align: usize) -> usize {
+    align_down(value + align - 1, align)
+}
@@ -0,0 +1,223 @@
+use std::arch::global_asm;
+#[cfg(target_arch = "x86_64")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::raw::{
+    relocation::{Rela, RelocationTable},
+    Error, DT_PLTGOT,
+};
+
+use super::{bind, LinkedObject};
+
+/// What GOT[1] of a lazily bound object points to: the symbol scope of the program
+/// and the index of the object in it.
+#[derive(Debug)]
+pub(crate) struct LazyScope {
+    objects: *const LinkedObject,
+    len: usize,
+    index: usize,
+}
+
+impl LazyScope {
+    /// The objects must not be moved or dropped while the scope is in use.
+    pub(crate) fn new(objects: &[LinkedObject], index: usize) -> LazyScope {
+        LazyScope {
+            objects: objects.as_ptr(),
+            len: objects.len(),
+            index,
+        }
+    }
+}
+
+/// Sets up GOT[1] and GOT[2] of an object, so that the first call through a PLT
+/// entry whose slot still points back at the entry jumps to the resolver trampoline.
+/// Returns `false`, without changing anything, if the object has no PLT or the
+/// trampoline cannot preserve the registers of this processor.
+pub(crate) fn install(object: &LinkedObject, scope: &LazyScope) -> bool {
+    let got = match object.dynamic.find_value(DT_PLTGOT) {
+        Some(addr) => (object.base + addr as usize) as *mut usize,
+        None => return false,
+    };
+    #[cfg(target_arch = "x86_64")]
+    match xsave_size() {
+        Some(size) => XSAVE_SIZE.store(size, Ordering::Relaxed),
+        None => return false,
+    }
+    unsafe {
+        got.add(1).write(scope as *const LazyScope as usize);
+        got.add(2)
+            .write(elf_loader_lazy_trampoline as *const () as usize);
+    }
+    true
+}
+
+/// Called by the trampoline on the first call through a PLT entry: binds the
+/// symbol of the DT_JMPREL relocation at `reloc_index`, patches its slot so later
+/// calls go straight to the definition, and returns the address to jump to.
+/// A symbol that cannot be bound ends the process, as there is no caller to report
+/// the error to.
+extern "C" fn resolve_lazy(scope: *const LazyScope, reloc_index: usize) -> usize {
+    let scope = unsafe { &*scope };
+    let objects = unsafe { std::slice::from_raw_parts(scope.objects, scope.len) };
+    match bind_slot(objects, scope.index, reloc_index) {
+        Ok(address) => address,
+        Err(err) => {
+            eprintln!("symbol lookup error: {}", err);
+            std::process::abort();
+        }
+    }
+}
+
+fn bind_slot(objects: &[LinkedObject], index: usize, reloc_index: usize) -> Result<usize, Error> {
+    let object = &objects[index];
+    let table = RelocationTable::<Rela>::parse_jmprel_dynamic(object.base, &object.dynamic)?
+        .ok_or_else(|| Error::Message(format!("{}: no PLT relocations", object.name)))?;
+    if reloc_index >= table.len() {
+        return Err(Error::Message(format!(
+            "{}: invalid PLT relocation {}",
+            object.name, reloc_index
+        )));
+    }
+
+    let reloc = table.get_relocation(reloc_index);
+    let address = match bind(objects, index, reloc.get_symbol() as usize)? {
+        Some(address) => address.wrapping_add(reloc.get_addend() as usize),
+        None => {
+            return Err(Error::Message(format!(
+                "{}: call to an undefined weak function",
+                object.name
+            )))
+        }
+    };
+
+    let slot = (object.base + reloc.get_offset() as usize) as *mut usize;
+    unsafe { slot.write_unaligned(address) };
+    Ok(address)
+}
+
+extern "C" {
+    fn elf_loader_lazy_trampoline();
+}
+
+/// The size of the XSAVE area the trampoline saves the vector registers to, read
+/// by the trampoline.
+#[cfg(target_arch = "x86_64")]
+static XSAVE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+/// The size of the XSAVE area for the state components enabled by the OS, rounded
+/// up to the 64-byte alignment of the area, or `None` if XSAVE is not enabled.
+#[cfg(target_arch = "x86_64")]
+fn xsave_size() -> Option<usize> {
+    use std::arch::x86_64::{__cpuid, __cpuid_count};
+
+    // CPUID.1:ECX.OSXSAVE
+    if __cpuid(1).ecx & (1 << 27) == 0 {
+        return None;
+    }
+    Some((__cpuid_count(0xd, 0).ebx as usize).next_multiple_of(64))
+}
+
+// PLT0 pushes GOT[1] above the index of the relocation pushed by the PLT entry and
+// the return address of the caller. The argument registers are preserved around
+// the call to the resolver: the general-purpose ones on the stack, and all the
+// vector state (including the upper halves of ymm/zmm registers and the AVX-512
+// mask registers) in a 64-byte aligned XSAVE area. The XSAVE header must be zeroed
+// for XRSTOR to accept it. Both pushed words are popped before jumping to the bound
+// function.
+#[cfg(target_arch = "x86_64")]
+global_asm!(
+    ".text",
+    ".globl elf_loader_lazy_trampoline",
+    ".hidden elf_loader_lazy_trampoline",
+    ".type elf_loader_lazy_trampoline, @function",
+    "elf_loader_lazy_trampoline:",
+    "endbr64",
+    "push rax",
+    "push rcx",
+    "push rdx",
+    "push rsi",
+    "push rdi",
+    "push r8",
+    "push r9",
+    "push rbx",
+    "mov rbx, rsp",
+    "and rsp, -64",
+    "sub rsp, [rip + {size}]",
+    "xor eax, eax",
+    "mov [rsp + 512], rax",
+    "mov [rsp + 520], rax",
+    "mov [rsp + 528], rax",
+    "mov [rsp + 536], rax",
+    "mov [rsp + 544], rax",
+    "mov [rsp + 552], rax",
+    "mov [rsp + 560], rax",
+    "mov [rsp + 568], rax",
+    "mov eax, -1",
+    "mov edx, -1",
+    "xsave [rsp]",
+    "mov rdi, [rbx + 64]",
+    "mov rsi, [rbx + 72]",
+    "call {resolve}",
+    "mov r11, rax",
+    "mov eax, -1",
+    "mov edx, -1",
+    "xrstor [rsp]",
+    "mov rsp, rbx",
+    "pop rbx",
+    "pop r9",
+    "pop r8",
+    "pop rdi",
+    "pop rsi",
+    "pop rdx",
+    "pop rcx",
+    "pop rax",
+    "add rsp, 16",
+    "jmp r11",
+    ".size elf_loader_lazy_trampoline, . - elf_loader_lazy_trampoline",
+    resolve = sym resolve_lazy,
+    size = sym XSAVE_SIZE,
+);
+
+// PLT0 pushes the address of the GOT slot of the PLT entry and the return address
+// of the caller, and leaves the address of GOT[2] in x16. Slots follow GOT[2] in
+// the order of the DT_JMPREL relocations, which gives the relocation index.
+#[cfg(target_arch = "aarch64")]
+global_asm!(
+    ".text",
+    ".globl elf_loader_lazy_trampoline",
+    ".hidden elf_loader_lazy_trampoline",
+    ".type elf_loader_lazy_trampoline, %function",
+    "elf_loader_lazy_trampoline:",
+    "hint #34",
+    "sub sp, sp, #208",
+    "stp x0, x1, [sp, #0]",
+    "stp x2, x3, [sp, #16]",
+    "stp x4, x5, [sp, #32]",
+    "stp x6, x7, [sp, #48]",
+    "stp x8, x30, [sp, #64]",
+    "stp q0, q1, [sp, #80]",
+    "stp q2, q3, [sp, #112]",
+    "stp q4, q5, [sp, #144]",
+    "stp q6, q7, [sp, #176]",
+    "ldr x0, [x16, #-8]",
+    "ldr x1, [sp, #208]",
+    "sub x1, x1, x16",
+    "sub x1, x1, #8",
+    "lsr x1, x1, #3",
+    "bl {resolve}",
+    "mov x17, x0",
+    "ldp x0, x1, [sp, #0]",
+    "ldp x2, x3, [sp, #16]",
+    "ldp x4, x5, [sp, #32]",
+    "ldp x6, x7, [sp, #48]",
+    "ldp x8, x30, [sp, #64]",
+    "ldp q0, q1, [sp, #80]",
+    "ldp q2, q3, [sp, #112]",
+    "ldp q4, q5, [sp, #144]",
+    "ldp q6, q7, [sp, #176]",
+    "add sp, sp, #208",
+    "ldp x16, x30, [sp], #16",
+    "br x17",
+    ".size elf_loader_lazy_trampoline, . - elf_loader_lazy_trampoline",
+    resolve = sym resolve_lazy,
+);
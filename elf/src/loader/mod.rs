@@ -0,0 +1,743 @@
+use std::{
+    collections::VecDeque,
+    ffi::{c_char, c_int, c_void, CStr},
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    initfini::{InitFini, InitKind},
+    raw::{
+        dynamic::{DynamicInfo, DynamicTable},
+        header::{Headers, ProgramHeader},
+        mapping::AddressMap,
+        relocation::{call_ifunc_resolver, Rela, RelocationTable},
+        symbol::ElfSymbol,
+        Error, DF_TEXTREL, DT_JMPREL, DT_PLTRELSZ, DT_RELA, DT_RELASZ, DT_RELR, DT_RELRSZ,
+        DT_TEXTREL, EM_AARCH64, EM_X86_64, PT_DYNAMIC, PT_TLS, R_AARCH64_ABS64, R_AARCH64_GLOB_DAT,
+        R_AARCH64_IRELATIVE, R_AARCH64_JUMP_SLOT, R_AARCH64_NONE, R_AARCH64_RELATIV, R_X86_64_64,
+        R_X86_64_GLOB_DAT, R_X86_64_IRELATIVE, R_X86_64_JUMP_SLOT, R_X86_64_NONE,
+        R_X86_64_RELATIVE, STB_LOCAL, STB_WEAK, STT_GNU_IFUNC, STT_TLS, VER_NDX_GLOBAL,
+    },
+    tls::TlsRelocation,
+};
+
+use self::{image::Image, lazy::LazyScope, object::HostTls};
+pub use self::{object::LinkedObject, search::SearchPaths};
+
+pub mod image;
+mod lazy;
+mod object;
+pub mod search;
+
+/// The machine of the running process, the only one whose objects can be loaded.
+const HOST_MACHINE: u16 = if cfg!(target_arch = "x86_64") {
+    EM_X86_64
+} else {
+    EM_AARCH64
+};
+
+/// Loads a shared object and its DT_NEEDED dependencies into the process without the
+/// system dynamic linker, e.g. to run plugins in their own symbol scope.
+#[derive(Debug, Clone, Default)]
+pub struct Linker {
+    search: SearchPaths,
+    host_objects: bool,
+    lazy_binding: bool,
+}
+
+impl Linker {
+    pub fn new(search: SearchPaths) -> Linker {
+        Linker {
+            search,
+            host_objects: false,
+            lazy_binding: false,
+        }
+    }
+
+    /// Satisfies DT_NEEDED entries with the objects the system dynamic linker has
+    /// already loaded into the process, matched by name, instead of loading another
+    /// copy. This is needed for libraries that must not be loaded twice, like libc.
+    pub fn use_host_objects(mut self, host_objects: bool) -> Linker {
+        self.host_objects = host_objects;
+        self
+    }
+
+    /// Binds PLT calls on their first call instead of at load time. Objects linked
+    /// with DT_BIND_NOW or DF_1_NOW are still bound eagerly.
+    /// An unresolved symbol then ends the process when its function is first called,
+    /// instead of failing `load`. On x86_64 processors without XSAVE enabled by the
+    /// OS, which the resolver needs to preserve the vector registers, objects are
+    /// bound eagerly.
+    pub fn lazy_binding(mut self, lazy_binding: bool) -> Linker {
+        self.lazy_binding = lazy_binding;
+        self
+    }
+
+    /// Loads a shared object and its dependencies, binds their symbols and runs their
+    /// constructors.
+    ///
+    /// Dependencies are loaded in breadth-first order, which is also the order of the
+    /// symbol scope: a reference binds the first definition found, weak or not. An
+    /// undefined weak reference binds zero. Relocations are applied and constructors
+    /// run in reverse order, so dependencies are ready before the objects using them.
+    ///
+    /// Objects with thread-local storage of their own (PT_TLS) cannot be loaded, as
+    /// their TLS blocks would have to be added to every thread of the process. TLS
+    /// relocations against objects of the system dynamic linker, such as `errno` in
+    /// libc, are supported; see `use_host_objects`. The whole scope is found and
+    /// checked for these, for text relocations and for unsupported relocation types
+    /// before any object is mapped.
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<Program, Error> {
+        let path = path.as_ref();
+        let host = if self.host_objects {
+            host_objects()?
+        } else {
+            vec![]
+        };
+
+        let mut scope = vec![Candidate::open(path, None)?.ok_or_else(|| {
+            Error::Message(format!(
+                "{}: not a shared object for this machine",
+                path.display()
+            ))
+        })?];
+
+        let mut queue = VecDeque::from([0]);
+        let mut host = host.into_iter().map(Some).collect::<Vec<_>>();
+        while let Some(index) = queue.pop_front() {
+            let needed = scope[index].needed.clone();
+            for name in needed.iter() {
+                if scope.iter().any(|c| c.matches_name(name)) {
+                    continue;
+                }
+
+                if let Some(slot) = host.iter_mut().find(|o| {
+                    o.as_ref()
+                        .is_some_and(|o| matches_name(&o.name, o.path.as_deref(), name))
+                }) {
+                    scope.push(Candidate::host(slot.take().unwrap(), index));
+                    queue.push_back(scope.len() - 1);
+                    continue;
+                }
+
+                if let Some(candidate) = self.find_library(&scope, index, name)? {
+                    scope.push(candidate);
+                    queue.push_back(scope.len() - 1);
+                }
+            }
+        }
+
+        let mut program = Program {
+            objects: vec![],
+            initialized: vec![],
+            scopes: vec![],
+        };
+        for candidate in scope {
+            program.objects.push(candidate.map()?);
+        }
+
+        // the objects are not moved from here on, so GOT[1] can point into the scopes
+        program.scopes = (0..program.objects.len())
+            .map(|index| LazyScope::new(&program.objects, index))
+            .collect();
+
+        for index in (0..program.objects.len()).rev() {
+            if program.objects[index].is_host() {
+                continue;
+            }
+            let lazy = self.lazy_binding && !program.objects[index].info.binds_now();
+            program.relocate(index, lazy.then(|| &program.scopes[index]))?;
+            if let Some(image) = &program.objects[index].image {
+                image.protect_relro()?;
+            }
+        }
+
+        for index in (0..program.objects.len()).rev() {
+            program.run_constructors(index)?;
+            program.initialized.push(index);
+        }
+
+        Ok(program)
+    }
+
+    /// Searches the library paths for a DT_NEEDED library of an object, skipping
+    /// files that are not shared objects for this machine. Returns `None` if the file
+    /// found is already in the scope under another name.
+    fn find_library(
+        &self,
+        scope: &[Candidate],
+        requester: usize,
+        name: &str,
+    ) -> Result<Option<Candidate>, Error> {
+        let object = &scope[requester];
+
+        // DT_RPATH of the requesting object and the objects that loaded it
+        let mut rpaths = vec![];
+        let mut loader = Some(requester);
+        while let Some(index) = loader {
+            let o = &scope[index];
+            if let Some(rpath) = &o.rpath {
+                rpaths.push((rpath.as_str(), o.origin()));
+            }
+            loader = o.parent;
+        }
+        let runpath = object
+            .runpath
+            .as_deref()
+            .map(|runpath| (runpath, object.origin()));
+
+        for candidate in self.search.candidates(name, &rpaths, runpath) {
+            if !candidate.is_file() {
+                continue;
+            }
+            // the same file may be named differently, e.g. through a symbolic link
+            let canonical = candidate.canonicalize().ok();
+            if canonical.is_some()
+                && scope
+                    .iter()
+                    .any(|o| o.path.as_ref().and_then(|p| p.canonicalize().ok()) == canonical)
+            {
+                return Ok(None);
+            }
+            if let Some(candidate) = Candidate::open(&candidate, Some(requester))? {
+                return Ok(Some(candidate));
+            }
+        }
+
+        Err(Error::Message(format!(
+            "cannot find {}, needed by {}",
+            name, object.name
+        )))
+    }
+}
+
+/// An object of the scope of a program, found before any object is mapped.
+struct Candidate {
+    /// The DT_SONAME of the object, or its file name
+    name: String,
+    path: Option<PathBuf>,
+    parent: Option<usize>,
+    needed: Vec<String>,
+    rpath: Option<String>,
+    runpath: Option<String>,
+    source: Source,
+}
+
+enum Source {
+    /// An object already loaded by the system dynamic linker
+    Host(Box<LinkedObject>),
+    /// A file to map, and its contents
+    File(File, Vec<u8>),
+}
+
+impl Candidate {
+    fn host(object: LinkedObject, parent: usize) -> Candidate {
+        Candidate {
+            name: object.name.clone(),
+            path: object.path.clone(),
+            parent: Some(parent),
+            needed: object.info.needed.iter().map(|n| n.to_string()).collect(),
+            rpath: object.info.rpath.map(str::to_string),
+            runpath: object.info.runpath.map(str::to_string),
+            source: Source::Host(Box::new(object)),
+        }
+    }
+
+    /// Reads an object from a file, or returns `None` if it is not a shared object for
+    /// this machine. Fails if the object cannot be loaded, see `check_loadable`.
+    fn open(path: &Path, parent: Option<usize>) -> Result<Option<Candidate>, Error> {
+        let file = File::open(path).map_err(Error::Open)?;
+        let buf = std::fs::read(path).map_err(Error::Read)?;
+        let elf = match Headers::parse(&buf) {
+            Ok(elf) if elf.header.e_machine == HOST_MACHINE => elf,
+            _ => return Ok(None),
+        };
+        let info = DynamicInfo::parse(&buf, &elf)?;
+        check_loadable(path, &buf, &elf, &info)?;
+
+        let name = info.soname.map(str::to_string).unwrap_or_else(|| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string()
+        });
+        let needed = info.needed.iter().map(|n| n.to_string()).collect();
+        let rpath = info.rpath.map(str::to_string);
+        let runpath = info.runpath.map(str::to_string);
+        Ok(Some(Candidate {
+            name,
+            path: Some(path.to_path_buf()),
+            parent,
+            needed,
+            rpath,
+            runpath,
+            source: Source::File(file, buf),
+        }))
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        matches_name(&self.name, self.path.as_deref(), name)
+    }
+
+    /// The directory of the object, which `$ORIGIN` expands to.
+    fn origin(&self) -> Option<&Path> {
+        self.path.as_deref().and_then(|path| path.parent())
+    }
+
+    /// Maps the object if it is a file.
+    fn map(self) -> Result<LinkedObject, Error> {
+        match self.source {
+            Source::Host(mut object) => {
+                object.parent = self.parent;
+                Ok(*object)
+            }
+            Source::File(file, buf) => {
+                let elf = Headers::parse(&buf)?;
+                let image = Image::map(&file, &elf)?;
+                // the program headers are not necessarily part of a loaded segment
+                let program_headers: Vec<ProgramHeader> = elf.program_headers.to_vec();
+                let path = self.path.unwrap_or_default();
+                LinkedObject::from_image(image, &program_headers, &path, self.parent)
+            }
+        }
+    }
+}
+
+/// Checks the parts of an object the linker does not support, from its file: thread-
+/// local storage of its own, text relocations and relocation types it cannot apply.
+fn check_loadable(path: &Path, buf: &[u8], elf: &Headers, info: &DynamicInfo) -> Result<(), Error> {
+    let error = |message: &str| Error::Message(format!("{}: {}", path.display(), message));
+    if elf.program_headers.iter().any(|ph| ph.get_type() == PT_TLS) {
+        return Err(error("objects with thread-local storage are not supported"));
+    }
+
+    let hdr = elf
+        .program_headers
+        .iter()
+        .find(|ph| ph.get_type() == PT_DYNAMIC)
+        .ok_or_else(|| error("no PT_DYNAMIC program header"))?;
+    let dynamic = DynamicTable::parse_file_segment(&buf, hdr)?;
+    if dynamic.find_value(DT_TEXTREL).is_some() || info.flags & DF_TEXTREL != 0 {
+        return Err(error("text relocations are not supported"));
+    }
+
+    let map = AddressMap::new(&buf, elf);
+    for (addr, size) in [(DT_RELA, DT_RELASZ), (DT_JMPREL, DT_PLTRELSZ)] {
+        let (addr, size) = match (dynamic.find_value(addr), dynamic.find_value(size)) {
+            (Some(addr), Some(size)) => (addr, size),
+            _ => continue,
+        };
+        let relocs = RelocationTable::<Rela>::from_bytes(map.read_file_backed(addr, size)?)?;
+        let unsupported = relocs
+            .iter()
+            .map(|reloc| reloc.get_type())
+            .find(|&r_type| relocation_kind(r_type).is_none());
+        if let Some(r_type) = unsupported {
+            return Err(error(&format!("unsupported relocation type {}", r_type)));
+        }
+    }
+    Ok(())
+}
+
+/// Shared objects loaded by a `Linker`, with their dependencies. Dropping the program
+/// runs the destructors and unmaps the objects.
+#[derive(Debug)]
+pub struct Program {
+    /// The symbol scope, the loaded object first
+    objects: Vec<LinkedObject>,
+    /// The objects whose constructors have run, in the order they ran
+    initialized: Vec<usize>,
+    /// What GOT[1] of lazily bound objects points to, one per object
+    scopes: Vec<LazyScope>,
+}
+
+impl Program {
+    pub fn objects(&self) -> &[LinkedObject] {
+        &self.objects
+    }
+
+    /// Finds an object by its DT_SONAME or file name.
+    pub fn find_object(&self, name: &str) -> Option<&LinkedObject> {
+        self.objects
+            .iter()
+            .find(|o| matches_name(&o.name, o.path.as_deref(), name))
+    }
+
+    /// The address of a symbol of the default version, searched in scope order.
+    /// Indirect functions are resolved to the implementation they select.
+    pub fn lookup(&self, name: &str) -> Option<usize> {
+        self.lookup_version(name, None)
+    }
+
+    /// The address of a symbol of a specific version, searched in scope order.
+    pub fn lookup_version(&self, name: &str, version: Option<&str>) -> Option<usize> {
+        self.objects.iter().find_map(|object| {
+            let (_, sym) = object.find_definition(name, version, true)?;
+            symbol_address(object, sym.value, sym.info & 0xf).ok()
+        })
+    }
+
+    /// Applies the relocations of an object: DT_RELR, DT_RELA and DT_JMPREL. With a
+    /// lazy binding scope, JUMP_SLOT relocations only relocate the slot to point back
+    /// at its PLT entry, and are bound on the first call.
+    fn relocate(&self, index: usize, scope: Option<&LazyScope>) -> Result<(), Error> {
+        let object = &self.objects[index];
+        let base = object.base;
+        let dynamic = &object.dynamic;
+
+        if let (Some(addr), Some(size)) =
+            (dynamic.find_value(DT_RELR), dynamic.find_value(DT_RELRSZ))
+        {
+            apply_relr(base, addr, size);
+        }
+
+        if dynamic.has_relocations() {
+            let table = RelocationTable::<Rela>::parse_rela_dynamic(base, dynamic)?;
+            for reloc in table.iter() {
+                self.apply(index, reloc)?;
+            }
+        }
+
+        if let Some(table) = RelocationTable::<Rela>::parse_jmprel_dynamic(base, dynamic)? {
+            let lazy = scope.is_some_and(|scope| lazy::install(object, scope));
+            for reloc in table.iter() {
+                if lazy && is_jump_slot(reloc.get_type()) {
+                    let slot = (base + reloc.get_offset() as usize) as *mut usize;
+                    unsafe { slot.write_unaligned(slot.read_unaligned().wrapping_add(base)) };
+                } else {
+                    self.apply(index, reloc)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, index: usize, reloc: &Rela) -> Result<(), Error> {
+        let object = &self.objects[index];
+        let target = (object.base + reloc.get_offset() as usize) as *mut u64;
+        let addend = reloc.get_addend() as u64;
+
+        let kind = relocation_kind(reloc.get_type()).ok_or_else(|| {
+            Error::Message(format!(
+                "{}: unsupported relocation type {}",
+                object.name,
+                reloc.get_type()
+            ))
+        })?;
+        let value = match kind {
+            RelocationKind::None => return Ok(()),
+            RelocationKind::Relative => (object.base as u64).wrapping_add(addend),
+            RelocationKind::IRelative => symbol_address(object, addend, STT_GNU_IFUNC)? as u64,
+            RelocationKind::Symbol => {
+                match bind(&self.objects, index, reloc.get_symbol() as usize)? {
+                    Some(address) => (address as u64).wrapping_add(addend),
+                    None => 0,
+                }
+            }
+            RelocationKind::Tls(relocation) => bind_tls(
+                &self.objects,
+                index,
+                reloc.get_symbol() as usize,
+                relocation,
+                addend,
+            )?,
+        };
+
+        unsafe { target.write_unaligned(value) };
+        Ok(())
+    }
+
+    fn run_constructors(&self, index: usize) -> Result<(), Error> {
+        let object = &self.objects[index];
+        if object.is_host() {
+            return Ok(());
+        }
+
+        let init_fini = InitFini::from_image(object.base, &object.dynamic)?;
+        // DT_PREINIT_ARRAY is only run for executables
+        for f in init_fini
+            .constructors
+            .iter()
+            .filter(|f| f.kind != InitKind::PreinitArray)
+        {
+            unsafe { f.call_init(0, std::ptr::null(), environ) };
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        // destructors run in the reverse order of the constructors, and only for
+        // objects that were initialized
+        for &index in self.initialized.iter().rev() {
+            let object = &self.objects[index];
+            if object.is_host() {
+                continue;
+            }
+            if let Ok(init_fini) = InitFini::from_image(object.base, &object.dynamic) {
+                for f in init_fini.destructors.iter() {
+                    unsafe { f.call_fini() };
+                }
+            }
+        }
+    }
+}
+
+extern "C" {
+    static environ: *const *const c_char;
+}
+
+/// How a relocation type of the host machine is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RelocationKind {
+    None,
+    Relative,
+    IRelative,
+    /// The address of the symbol plus the addend
+    Symbol,
+    Tls(TlsRelocation),
+}
+
+/// The kind of a relocation type, or `None` if the linker cannot apply it.
+fn relocation_kind(r_type: u32) -> Option<RelocationKind> {
+    if let Some(relocation) = TlsRelocation::from_type(HOST_MACHINE, r_type) {
+        return Some(RelocationKind::Tls(relocation));
+    }
+    Some(match (HOST_MACHINE, r_type) {
+        (EM_X86_64, R_X86_64_NONE) | (EM_AARCH64, R_AARCH64_NONE) => RelocationKind::None,
+        (EM_X86_64, R_X86_64_RELATIVE) | (EM_AARCH64, R_AARCH64_RELATIV) => {
+            RelocationKind::Relative
+        }
+        (EM_X86_64, R_X86_64_IRELATIVE) | (EM_AARCH64, R_AARCH64_IRELATIVE) => {
+            RelocationKind::IRelative
+        }
+        (EM_X86_64, R_X86_64_64 | R_X86_64_GLOB_DAT | R_X86_64_JUMP_SLOT)
+        | (EM_AARCH64, R_AARCH64_ABS64 | R_AARCH64_GLOB_DAT | R_AARCH64_JUMP_SLOT) => {
+            RelocationKind::Symbol
+        }
+        _ => return None,
+    })
+}
+
+fn is_jump_slot(r_type: u32) -> bool {
+    matches!(
+        (HOST_MACHINE, r_type),
+        (EM_X86_64, R_X86_64_JUMP_SLOT) | (EM_AARCH64, R_AARCH64_JUMP_SLOT)
+    )
+}
+
+/// Whether an object is named `name` by its DT_SONAME, its file name or its path.
+fn matches_name(object_name: &str, path: Option<&Path>, name: &str) -> bool {
+    object_name == name
+        || path.is_some_and(|path| {
+            path.file_name().is_some_and(|n| n == name) || path == Path::new(name)
+        })
+}
+
+/// Binds a symbol reference of an object, returning its address, or `None` for an
+/// undefined weak reference.
+pub(crate) fn bind(
+    objects: &[LinkedObject],
+    requester: usize,
+    index: usize,
+) -> Result<Option<usize>, Error> {
+    match find_reference(objects, requester, index)? {
+        Some((_, def)) if def.info & 0xf == STT_TLS => Err(Error::Message(format!(
+            "{}: {} is a thread-local symbol",
+            objects[requester].name, def.name
+        ))),
+        Some((definer, def)) => symbol_address(definer, def.value, def.info & 0xf).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Computes the value of a TLS relocation against a thread-local symbol, which must
+/// be defined by an object of the system dynamic linker. TPOFF relocations assume the
+/// object's TLS block is in the static TLS area, as it is for the objects loaded at
+/// startup, and are relative to the thread pointer of the calling thread.
+fn bind_tls(
+    objects: &[LinkedObject],
+    requester: usize,
+    index: usize,
+    relocation: TlsRelocation,
+    addend: u64,
+) -> Result<u64, Error> {
+    let object = &objects[requester];
+    let error = |message: String| Error::Message(format!("{}: {}", object.name, message));
+    let (definer, def) = match find_reference(objects, requester, index)? {
+        Some((definer, def)) if index != 0 && def.info & 0xf == STT_TLS => (definer, def),
+        _ => {
+            return Err(error(
+                "TLS relocation without a thread-local symbol".to_string(),
+            ))
+        }
+    };
+    let tls = definer.tls.ok_or_else(|| {
+        error(format!(
+            "{} is defined by {}, which has no TLS block of the system dynamic linker",
+            def.name, definer.name
+        ))
+    })?;
+
+    let offset = def.value.wrapping_add(addend);
+    Ok(match relocation {
+        TlsRelocation::DtpMod => tls.module_id,
+        TlsRelocation::DtpOff => offset,
+        TlsRelocation::TpOff => {
+            let block = tls.offset.ok_or_else(|| {
+                error(format!(
+                    "the TLS block of {} is not allocated in this thread",
+                    definer.name
+                ))
+            })?;
+            (block as u64).wrapping_add(offset)
+        }
+    })
+}
+
+/// Finds the definition a symbol reference of an object binds to, and the object
+/// defining it, or `None` for an undefined weak reference.
+fn find_reference(
+    objects: &[LinkedObject],
+    requester: usize,
+    index: usize,
+) -> Result<Option<(&LinkedObject, ElfSymbol<'static>)>, Error> {
+    let object = &objects[requester];
+    let sym = object
+        .symbols
+        .get(index)
+        .ok_or_else(|| Error::Message(format!("{}: invalid symbol {}", object.name, index)))?;
+    if sym.info >> 4 == STB_LOCAL {
+        return Ok(Some((object, sym)));
+    }
+
+    let version = object
+        .versions
+        .symbol_version(index)
+        .filter(|v| v.index > VER_NDX_GLOBAL)
+        .and_then(|v| object.versions.version_name(v.index));
+
+    let symbolic = object.is_symbolic().then_some(object);
+    for definer in symbolic.into_iter().chain(objects.iter()) {
+        if let Some((_, def)) = definer.find_definition(sym.name, version, false) {
+            return Ok(Some((definer, def)));
+        }
+    }
+
+    if sym.info >> 4 == STB_WEAK {
+        return Ok(None);
+    }
+    Err(Error::Message(format!(
+        "{}: undefined symbol {}{}",
+        object.name,
+        sym.name,
+        version.map(|v| format!("@{}", v)).unwrap_or_default()
+    )))
+}
+
+/// The address of a symbol defined by an object. Indirect functions are called to
+/// select their implementation.
+fn symbol_address(object: &LinkedObject, value: u64, st_type: u8) -> Result<usize, Error> {
+    let address = object.base + value as usize;
+    if st_type == STT_GNU_IFUNC {
+        return Ok(unsafe { call_ifunc_resolver(address, HOST_MACHINE, hwcap()) } as usize);
+    }
+    Ok(address)
+}
+
+/// The hardware capabilities passed to indirect function resolvers on AArch64.
+fn hwcap() -> u64 {
+    unsafe { libc::getauxval(libc::AT_HWCAP) }
+}
+
+/// Applies DT_RELR, the compact encoding of RELATIVE relocations: an even entry is
+/// the address of a relocation, and an odd entry is a bitmap of relocations in the
+/// 63 words following the last address.
+fn apply_relr(base: usize, addr: u64, size: u64) {
+    let entries = unsafe {
+        std::slice::from_raw_parts((base + addr as usize) as *const u64, size as usize / 8)
+    };
+    let relocate = |address: usize| unsafe {
+        let target = (base + address) as *mut u64;
+        target.write_unaligned(target.read_unaligned().wrapping_add(base as u64));
+    };
+
+    let mut next = 0;
+    for &entry in entries {
+        if entry & 1 == 0 {
+            relocate(entry as usize);
+            next = entry as usize + 8;
+        } else {
+            for bit in 1..64 {
+                if entry & (1 << bit) != 0 {
+                    relocate(next + (bit - 1) * 8);
+                }
+            }
+            next += 63 * 8;
+        }
+    }
+}
+
+/// The objects loaded by the system dynamic linker, except the main executable.
+fn host_objects() -> Result<Vec<LinkedObject>, Error> {
+    unsafe extern "C" fn callback(
+        info: *mut libc::dl_phdr_info,
+        _size: libc::size_t,
+        data: *mut c_void,
+    ) -> c_int {
+        let objects = &mut *(data as *mut Vec<HostObject>);
+        let info = &*info;
+        let name = if info.dlpi_name.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(info.dlpi_name)
+                .to_string_lossy()
+                .into_owned()
+        };
+        let phdrs = std::slice::from_raw_parts(
+            info.dlpi_phdr as *const ProgramHeader,
+            info.dlpi_phnum as usize,
+        );
+        // the TLS block of this thread is only allocated once used, for objects
+        // loaded after startup
+        let tls = (info.dlpi_tls_modid != 0).then(|| HostTls {
+            module_id: info.dlpi_tls_modid as u64,
+            offset: (!info.dlpi_tls_data.is_null())
+                .then(|| (info.dlpi_tls_data as usize).wrapping_sub(thread_pointer()) as i64),
+        });
+        objects.push((info.dlpi_addr as usize, phdrs, name, tls));
+        0
+    }
+
+    type HostObject = (usize, &'static [ProgramHeader], String, Option<HostTls>);
+    let mut found: Vec<HostObject> = vec![];
+    unsafe { libc::dl_iterate_phdr(Some(callback), &mut found as *mut _ as *mut c_void) };
+
+    let mut objects = vec![];
+    for (base, phdrs, name, tls) in found {
+        if name.is_empty() {
+            continue;
+        }
+        objects.push(unsafe { LinkedObject::from_host(base, phdrs, &name, tls)? });
+    }
+    Ok(objects)
+}
+
+/// The thread pointer of the calling thread, which static TLS blocks are at fixed
+/// offsets from.
+fn thread_pointer() -> usize {
+    let tp: usize;
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        // the thread control block starts with a pointer to itself
+        std::arch::asm!("mov {}, fs:0", out(reg) tp, options(nostack, readonly, preserves_flags));
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("mrs {}, tpidr_el0", out(reg) tp, options(nomem, nostack, preserves_flags));
+    }
+    tp
+}
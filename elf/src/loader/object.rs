@@ -0,0 +1,237 @@
+use std::path::{Path, PathBuf};
+
+use crate::raw::{
+    dynamic::{DynamicInfo, DynamicTable},
+    hash::HashTable,
+    header::ProgramHeader,
+    string::StringTable,
+    symbol::{ElfSymbol, Symbol, SymbolTable},
+    version::VersionTable,
+    Error, DF_SYMBOLIC, DT_STRSZ, DT_STRTAB, DT_SYMBOLIC, DT_SYMTAB, PT_DYNAMIC, SHN_UNDEF,
+    STB_GLOBAL, STB_GNU_UNIQUE, STB_WEAK, STT_COMMON, STT_FUNC, STT_GNU_IFUNC, STT_NOTYPE,
+    STT_OBJECT, STT_TLS, VER_NDX_GLOBAL,
+};
+
+use super::image::Image;
+
+/// An object in the scope of a `Program`: a shared object mapped by the linker, or
+/// an object already loaded into the process by the system dynamic linker.
+#[derive(Debug)]
+pub struct LinkedObject {
+    /// The DT_SONAME of the object, or its file name
+    pub name: String,
+    pub path: Option<PathBuf>,
+    /// The address the object is loaded at, added to its virtual addresses
+    pub base: usize,
+    /// The index of the object that first needed it, `None` for the root
+    pub parent: Option<usize>,
+    pub(crate) dynamic: DynamicTable<'static>,
+    pub(crate) info: DynamicInfo<'static>,
+    pub(crate) symbols: SymbolTable<'static>,
+    pub(crate) hash: HashTable<'static>,
+    pub(crate) versions: VersionTable<'static>,
+    /// `None` for objects loaded by the system dynamic linker, which are not relocated
+    pub(crate) image: Option<Image>,
+    /// The TLS block of an object loaded by the system dynamic linker
+    pub(crate) tls: Option<HostTls>,
+}
+
+/// The TLS block of an object loaded by the system dynamic linker, as reported by
+/// `dl_iterate_phdr`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HostTls {
+    /// The module id, which indexes the DTV
+    pub(crate) module_id: u64,
+    /// The offset of the block from the thread pointer, if allocated in this thread
+    pub(crate) offset: Option<i64>,
+}
+
+impl LinkedObject {
+    /// Reads the dynamic symbols of an object mapped by the linker.
+    pub(crate) fn from_image(
+        image: Image,
+        program_headers: &[ProgramHeader],
+        path: &Path,
+        parent: Option<usize>,
+    ) -> Result<LinkedObject, Error> {
+        let base = image.base();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_string();
+        LinkedObject::new(
+            base,
+            program_headers,
+            name,
+            Some(path.to_path_buf()),
+            parent,
+            Some(image),
+            false,
+        )
+    }
+
+    /// Reads the dynamic symbols of an object loaded by the system dynamic linker,
+    /// as reported by `dl_iterate_phdr`.
+    ///
+    /// # Safety
+    /// The program headers and segments of the object must stay mapped.
+    pub(crate) unsafe fn from_host(
+        base: usize,
+        program_headers: &[ProgramHeader],
+        path: &str,
+        tls: Option<HostTls>,
+    ) -> Result<LinkedObject, Error> {
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(path)
+            .to_string();
+        let path = (!path.is_empty()).then(|| PathBuf::from(path));
+        let mut object = LinkedObject::new(base, program_headers, name, path, None, None, true)?;
+        object.tls = tls;
+        Ok(object)
+    }
+
+    fn new(
+        base: usize,
+        program_headers: &[ProgramHeader],
+        name: String,
+        path: Option<PathBuf>,
+        parent: Option<usize>,
+        image: Option<Image>,
+        relocated_dynamic: bool,
+    ) -> Result<LinkedObject, Error> {
+        let hdr = program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+            .ok_or_else(|| Error::Message(format!("{}: no PT_DYNAMIC program header", name)))?;
+        let dynamic = DynamicTable::parse_segment(base, hdr)?;
+
+        // the system dynamic linker relocates the addresses of its objects' dynamic
+        // tables in place, and link-time addresses are below the base address
+        let read = |vaddr: u64, size: u64| -> Result<&'static [u8], Error> {
+            let address = if relocated_dynamic && base != 0 && vaddr >= base as u64 {
+                vaddr as usize
+            } else {
+                base + vaddr as usize
+            };
+            Ok(unsafe { std::slice::from_raw_parts(address as *const u8, size as usize) })
+        };
+
+        let info = DynamicInfo::resolve(&dynamic, read)?;
+        let hash = HashTable::resolve(&dynamic, read)?;
+        let symbol_count = hash.symbol_count();
+        let versions = VersionTable::resolve(&dynamic, symbol_count, read)?;
+
+        let strtab = match (dynamic.find_value(DT_STRTAB), dynamic.find_value(DT_STRSZ)) {
+            (Some(addr), Some(size)) => StringTable::from_bytes(read(addr, size)?)?,
+            _ => return Err(Error::Message(format!("{}: no dynamic string table", name))),
+        };
+        let symtab = dynamic
+            .find_value(DT_SYMTAB)
+            .ok_or_else(|| Error::Message(format!("{}: no dynamic symbol table", name)))?;
+        let symbols = SymbolTable::from_bytes(
+            read(
+                symtab,
+                (symbol_count * std::mem::size_of::<Symbol>()) as u64,
+            )?,
+            strtab,
+        )?;
+
+        let name = info.soname.map(|soname| soname.to_string()).unwrap_or(name);
+        Ok(LinkedObject {
+            name,
+            path,
+            base,
+            parent,
+            dynamic,
+            info,
+            symbols,
+            hash,
+            versions,
+            image,
+            tls: None,
+        })
+    }
+
+    /// Whether the object was loaded by the system dynamic linker.
+    pub fn is_host(&self) -> bool {
+        self.image.is_none()
+    }
+
+    /// Whether the object binds references to its own definitions first (DT_SYMBOLIC).
+    pub(crate) fn is_symbolic(&self) -> bool {
+        self.dynamic.find_value(DT_SYMBOLIC).is_some() || self.info.flags & DF_SYMBOLIC != 0
+    }
+
+    /// Finds the definition of a symbol in this object, with the matching rules of
+    /// the dynamic linker: a reference to a version binds the definition of that
+    /// version, or an unversioned definition. A reference without a version binds an
+    /// unversioned definition, or else the only non-hidden definition. Unless `newest`
+    /// is set, as for `dlsym`, it also binds the first version, which old unversioned
+    /// references were linked against.
+    pub(crate) fn find_definition(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        newest: bool,
+    ) -> Option<(usize, ElfSymbol<'static>)> {
+        let mut fallback = None;
+        let mut fallbacks = 0;
+
+        for index in self.hash.candidates(name.as_bytes()) {
+            let sym = match self.symbols.get(index) {
+                Some(sym) if sym.name == name => sym,
+                _ => continue,
+            };
+            if sym.shndx == SHN_UNDEF
+                || !matches!(sym.info >> 4, STB_GLOBAL | STB_WEAK | STB_GNU_UNIQUE)
+                || !matches!(
+                    sym.info & 0xf,
+                    STT_NOTYPE | STT_OBJECT | STT_FUNC | STT_COMMON | STT_TLS | STT_GNU_IFUNC
+                )
+            {
+                continue;
+            }
+
+            let sym_version = match self.versions.symbol_version(index) {
+                Some(v) => v,
+                None => return Some((index, sym)),
+            };
+            let defined = self
+                .versions
+                .definition(sym_version.index)
+                .filter(|_| sym_version.index > VER_NDX_GLOBAL);
+            match version {
+                Some(version) => {
+                    if defined.is_some_and(|d| d.name == version)
+                        || (defined.is_none() && !sym_version.hidden)
+                    {
+                        return Some((index, sym));
+                    }
+                }
+                None => {
+                    let first = if newest {
+                        VER_NDX_GLOBAL
+                    } else {
+                        VER_NDX_GLOBAL + 1
+                    };
+                    if sym_version.index <= first {
+                        return Some((index, sym));
+                    }
+                    if !sym_version.hidden {
+                        fallbacks += 1;
+                        fallback = Some((index, sym));
+                    }
+                }
+            }
+        }
+
+        if fallbacks == 1 {
+            fallback
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+/// The directories searched for DT_NEEDED libraries, besides those named by the
+/// objects themselves in DT_RPATH and DT_RUNPATH.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchPaths {
+    /// Searched after DT_RPATH and before DT_RUNPATH, like `LD_LIBRARY_PATH`
+    pub library_path: Vec<PathBuf>,
+    /// Searched last, in place of the `ld.so.cache` and the system directories
+    pub default_paths: Vec<PathBuf>,
+}
+
+impl Default for SearchPaths {
+    fn default() -> Self {
+        let multiarch = match std::env::consts::ARCH {
+            "x86_64" => Some("x86_64-linux-gnu"),
+            "aarch64" => Some("aarch64-linux-gnu"),
+            "riscv64" => Some("riscv64-linux-gnu"),
+            _ => None,
+        };
+
+        let mut default_paths = vec![];
+        if let Some(multiarch) = multiarch {
+            default_paths.push(Path::new("/lib").join(multiarch));
+            default_paths.push(Path::new("/usr/lib").join(multiarch));
+        }
+        for path in ["/lib64", "/usr/lib64", "/lib", "/usr/lib"] {
+            default_paths.push(PathBuf::from(path));
+        }
+
+        SearchPaths {
+            library_path: vec![],
+            default_paths,
+        }
+    }
+}
+
+impl SearchPaths {
+    /// The default paths, with the library path taken from `LD_LIBRARY_PATH`.
+    pub fn from_env() -> SearchPaths {
+        let library_path = std::env::var("LD_LIBRARY_PATH")
+            .map(|paths| split_paths(&paths, None))
+            .unwrap_or_default();
+        SearchPaths {
+            library_path,
+            ..Default::default()
+        }
+    }
+
+    /// The paths to try for a DT_NEEDED library, in the order of the dynamic linker:
+    /// a name with a slash is used as is, otherwise the `rpaths` of the requesting
+    /// object and its loaders are searched (only if the requesting object has no
+    /// DT_RUNPATH), then the library path, the `runpath` of the requesting object
+    /// and the default paths. Each path list comes with the directory of the object
+    /// naming it, which `$ORIGIN` expands to.
+    pub fn candidates(
+        &self,
+        name: &str,
+        rpaths: &[(&str, Option<&Path>)],
+        runpath: Option<(&str, Option<&Path>)>,
+    ) -> Vec<PathBuf> {
+        if name.contains('/') {
+            return vec![PathBuf::from(name)];
+        }
+
+        let mut directories = vec![];
+        if runpath.is_none() {
+            for (paths, origin) in rpaths {
+                directories.extend(split_paths(paths, *origin));
+            }
+        }
+        directories.extend(self.library_path.iter().cloned());
+        if let Some((paths, origin)) = runpath {
+            directories.extend(split_paths(paths, origin));
+        }
+        directories.extend(self.default_paths.iter().cloned());
+
+        directories.iter().map(|dir| dir.join(name)).collect()
+    }
+}
+
+/// Splits a colon-separated path list, expanding `$ORIGIN` and `${ORIGIN}`. Empty
+/// entries name the current directory. Entries with an `$ORIGIN` that cannot be
+/// expanded, or with other dynamic string tokens, are dropped.
+pub fn split_paths(paths: &str, origin: Option<&Path>) -> Vec<PathBuf> {
+    paths
+        .split([':', ';'])
+        .filter_map(|path| {
+            if path.is_empty() {
+                return Some(PathBuf::from("."));
+            }
+            if !path.contains('$') {
+                return Some(PathBuf::from(path));
+            }
+
+            let origin = origin?.to_str()?;
+            let path = path.replace("${ORIGIN}", origin).replace("$ORIGIN", origin);
+            if path.contains('$') {
+                return None;
+            }
+            Some(PathBuf::from(path))
+        })
+        .collect()
+}
@@ -1,11 +1,20 @@
+use std::fmt;
+
+use enumflags2::{bitflags, BitFlags};
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
+
+use crate::raw::{self, string::StringTable};
+
+use super::{Address, Error};
 
-#[derive(Debug, Clone, ToPrimitive, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToPrimitive, FromPrimitive)]
 pub enum DynamicTag {
     Null = 0,
     Needed = 1,
-    PtrRelSz = 2,
+    PltRelSz = 2,
     PltGot = 3,
+    Hash = 4,
     StrTab = 5,
     SymTab = 6,
     Rela = 7,
@@ -15,14 +24,385 @@ pub enum DynamicTag {
     SymEnt = 11,
     Init = 12,
     Fini = 13,
+    SoName = 14,
+    RPath = 15,
+    Symbolic = 16,
     Rel = 17,
     RelSz = 18,
     RelEnt = 19,
     PltRel = 20,
     Debug = 21,
+    TextRel = 22,
     JmpRel = 23,
+    BindNow = 24,
     InitArray = 25,
     FiniArray = 26,
     InitArraySz = 27,
     FiniArraySz = 28,
+    RunPath = 29,
+    Flags = 30,
+    PreinitArray = 32,
+    PreinitArraySz = 33,
+    SymTabShndx = 34,
+    RelrSz = 35,
+    Relr = 36,
+    RelrEnt = 37,
+    AndroidRel = 0x6000000f,
+    AndroidRelSz = 0x60000010,
+    AndroidRela = 0x60000011,
+    AndroidRelaSz = 0x60000012,
+    AndroidRelr = 0x6fffe000,
+    AndroidRelrSz = 0x6fffe001,
+    AndroidRelrEnt = 0x6fffe003,
+    GnuPrelinked = 0x6ffffdf5,
+    GnuConflictSz = 0x6ffffdf6,
+    GnuLiblistSz = 0x6ffffdf7,
+    Checksum = 0x6ffffdf8,
+    PltPadSz = 0x6ffffdf9,
+    MoveEnt = 0x6ffffdfa,
+    MoveSz = 0x6ffffdfb,
+    Feature1 = 0x6ffffdfc,
+    PosFlag1 = 0x6ffffdfd,
+    SymInSz = 0x6ffffdfe,
+    SymInEnt = 0x6ffffdff,
+    GnuHash = 0x6ffffef5,
+    TlsDescPlt = 0x6ffffef6,
+    TlsDescGot = 0x6ffffef7,
+    GnuConflict = 0x6ffffef8,
+    GnuLiblist = 0x6ffffef9,
+    Config = 0x6ffffefa,
+    DepAudit = 0x6ffffefb,
+    Audit = 0x6ffffefc,
+    PltPad = 0x6ffffefd,
+    MoveTab = 0x6ffffefe,
+    SymInfo = 0x6ffffeff,
+    VerSym = 0x6ffffff0,
+    RelaCount = 0x6ffffff9,
+    RelCount = 0x6ffffffa,
+    Flags1 = 0x6ffffffb,
+    VerDef = 0x6ffffffc,
+    VerDefNum = 0x6ffffffd,
+    VerNeed = 0x6ffffffe,
+    VerNeedNum = 0x6fffffff,
+    Auxiliary = 0x7ffffffd,
+    Filter = 0x7fffffff,
+}
+
+impl DynamicTag {
+    /// The `DT_` name of the tag without its prefix, as printed by `readelf`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynamicTag::Null => "NULL",
+            DynamicTag::Needed => "NEEDED",
+            DynamicTag::PltRelSz => "PLTRELSZ",
+            DynamicTag::PltGot => "PLTGOT",
+            DynamicTag::Hash => "HASH",
+            DynamicTag::StrTab => "STRTAB",
+            DynamicTag::SymTab => "SYMTAB",
+            DynamicTag::Rela => "RELA",
+            DynamicTag::RelaSz => "RELASZ",
+            DynamicTag::RelaEnt => "RELAENT",
+            DynamicTag::StrSz => "STRSZ",
+            DynamicTag::SymEnt => "SYMENT",
+            DynamicTag::Init => "INIT",
+            DynamicTag::Fini => "FINI",
+            DynamicTag::SoName => "SONAME",
+            DynamicTag::RPath => "RPATH",
+            DynamicTag::Symbolic => "SYMBOLIC",
+            DynamicTag::Rel => "REL",
+            DynamicTag::RelSz => "RELSZ",
+            DynamicTag::RelEnt => "RELENT",
+            DynamicTag::PltRel => "PLTREL",
+            DynamicTag::Debug => "DEBUG",
+            DynamicTag::TextRel => "TEXTREL",
+            DynamicTag::JmpRel => "JMPREL",
+            DynamicTag::BindNow => "BIND_NOW",
+            DynamicTag::InitArray => "INIT_ARRAY",
+            DynamicTag::FiniArray => "FINI_ARRAY",
+            DynamicTag::InitArraySz => "INIT_ARRAYSZ",
+            DynamicTag::FiniArraySz => "FINI_ARRAYSZ",
+            DynamicTag::RunPath => "RUNPATH",
+            DynamicTag::Flags => "FLAGS",
+            DynamicTag::PreinitArray => "PREINIT_ARRAY",
+            DynamicTag::PreinitArraySz => "PREINIT_ARRAYSZ",
+            DynamicTag::SymTabShndx => "SYMTAB_SHNDX",
+            DynamicTag::RelrSz => "RELRSZ",
+            DynamicTag::Relr => "RELR",
+            DynamicTag::RelrEnt => "RELRENT",
+            DynamicTag::AndroidRel => "ANDROID_REL",
+            DynamicTag::AndroidRelSz => "ANDROID_RELSZ",
+            DynamicTag::AndroidRela => "ANDROID_RELA",
+            DynamicTag::AndroidRelaSz => "ANDROID_RELASZ",
+            DynamicTag::AndroidRelr => "ANDROID_RELR",
+            DynamicTag::AndroidRelrSz => "ANDROID_RELRSZ",
+            DynamicTag::AndroidRelrEnt => "ANDROID_RELRENT",
+            DynamicTag::GnuPrelinked => "GNU_PRELINKED",
+            DynamicTag::GnuConflictSz => "GNU_CONFLICTSZ",
+            DynamicTag::GnuLiblistSz => "GNU_LIBLISTSZ",
+            DynamicTag::Checksum => "CHECKSUM",
+            DynamicTag::PltPadSz => "PLTPADSZ",
+            DynamicTag::MoveEnt => "MOVEENT",
+            DynamicTag::MoveSz => "MOVESZ",
+            DynamicTag::Feature1 => "FEATURE_1",
+            DynamicTag::PosFlag1 => "POSFLAG_1",
+            DynamicTag::SymInSz => "SYMINSZ",
+            DynamicTag::SymInEnt => "SYMINENT",
+            DynamicTag::GnuHash => "GNU_HASH",
+            DynamicTag::TlsDescPlt => "TLSDESC_PLT",
+            DynamicTag::TlsDescGot => "TLSDESC_GOT",
+            DynamicTag::GnuConflict => "GNU_CONFLICT",
+            DynamicTag::GnuLiblist => "GNU_LIBLIST",
+            DynamicTag::Config => "CONFIG",
+            DynamicTag::DepAudit => "DEPAUDIT",
+            DynamicTag::Audit => "AUDIT",
+            DynamicTag::PltPad => "PLTPAD",
+            DynamicTag::MoveTab => "MOVETAB",
+            DynamicTag::SymInfo => "SYMINFO",
+            DynamicTag::VerSym => "VERSYM",
+            DynamicTag::RelaCount => "RELACOUNT",
+            DynamicTag::RelCount => "RELCOUNT",
+            DynamicTag::Flags1 => "FLAGS_1",
+            DynamicTag::VerDef => "VERDEF",
+            DynamicTag::VerDefNum => "VERDEFNUM",
+            DynamicTag::VerNeed => "VERNEED",
+            DynamicTag::VerNeedNum => "VERNEEDNUM",
+            DynamicTag::Auxiliary => "AUXILIARY",
+            DynamicTag::Filter => "FILTER",
+        }
+    }
+}
+
+/// Interpretation of the `d_un` field of a dynamic entry, which depends on its tag.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DynamicValue {
+    /// The value is ignored, e.g. for DT_NULL, DT_SYMBOLIC or DT_BIND_NOW.
+    None,
+    /// An offset into the dynamic string table, resolved to the string.
+    String(String),
+    /// A virtual address in the object.
+    Address(Address),
+    /// A size in bytes.
+    Size(u64),
+    /// A number of entries.
+    Count(u64),
+    /// The type of relocation used by the PLT, either DT_REL or DT_RELA.
+    PltRel(DynamicTag),
+    Flags(BitFlags<DynamicFlag>),
+    Flags1(BitFlags<DynamicFlag1>),
+    Integer(u64),
+}
+
+impl DynamicValue {
+    /// Decodes the value of a dynamic entry with the given tag.
+    /// `strings` must be the dynamic string table (DT_STRTAB) of the object.
+    pub fn from_raw(
+        tag: DynamicTag,
+        value: u64,
+        strings: &StringTable,
+    ) -> Result<DynamicValue, Error> {
+        let value = match tag {
+            DynamicTag::Null | DynamicTag::Symbolic | DynamicTag::TextRel | DynamicTag::BindNow => {
+                DynamicValue::None
+            }
+            DynamicTag::Needed
+            | DynamicTag::SoName
+            | DynamicTag::RPath
+            | DynamicTag::RunPath
+            | DynamicTag::Config
+            | DynamicTag::DepAudit
+            | DynamicTag::Audit
+            | DynamicTag::Auxiliary
+            | DynamicTag::Filter => {
+                DynamicValue::String(strings.get_str(value as usize)?.to_string())
+            }
+            DynamicTag::PltRelSz
+            | DynamicTag::RelaSz
+            | DynamicTag::RelaEnt
+            | DynamicTag::StrSz
+            | DynamicTag::SymEnt
+            | DynamicTag::RelSz
+            | DynamicTag::RelEnt
+            | DynamicTag::InitArraySz
+            | DynamicTag::FiniArraySz
+            | DynamicTag::PreinitArraySz
+            | DynamicTag::RelrSz
+            | DynamicTag::RelrEnt
+            | DynamicTag::AndroidRelSz
+            | DynamicTag::AndroidRelaSz
+            | DynamicTag::AndroidRelrSz
+            | DynamicTag::AndroidRelrEnt
+            | DynamicTag::GnuConflictSz
+            | DynamicTag::GnuLiblistSz
+            | DynamicTag::PltPadSz
+            | DynamicTag::MoveEnt
+            | DynamicTag::MoveSz
+            | DynamicTag::SymInSz
+            | DynamicTag::SymInEnt => DynamicValue::Size(value),
+            DynamicTag::RelaCount
+            | DynamicTag::RelCount
+            | DynamicTag::VerDefNum
+            | DynamicTag::VerNeedNum => DynamicValue::Count(value),
+            DynamicTag::PltRel => match DynamicTag::from_u64(value) {
+                Some(t @ (DynamicTag::Rel | DynamicTag::Rela)) => DynamicValue::PltRel(t),
+                _ => return Err(Error::InvalidElf),
+            },
+            DynamicTag::Flags => DynamicValue::Flags(BitFlags::from_bits_truncate(value)),
+            DynamicTag::Flags1 => DynamicValue::Flags1(BitFlags::from_bits_truncate(value)),
+            DynamicTag::GnuPrelinked
+            | DynamicTag::Checksum
+            | DynamicTag::Feature1
+            | DynamicTag::PosFlag1 => DynamicValue::Integer(value),
+            DynamicTag::PltGot
+            | DynamicTag::Hash
+            | DynamicTag::StrTab
+            | DynamicTag::SymTab
+            | DynamicTag::Rela
+            | DynamicTag::Init
+            | DynamicTag::Fini
+            | DynamicTag::Rel
+            | DynamicTag::Debug
+            | DynamicTag::JmpRel
+            | DynamicTag::InitArray
+            | DynamicTag::FiniArray
+            | DynamicTag::PreinitArray
+            | DynamicTag::SymTabShndx
+            | DynamicTag::Relr
+            | DynamicTag::AndroidRel
+            | DynamicTag::AndroidRela
+            | DynamicTag::AndroidRelr
+            | DynamicTag::GnuHash
+            | DynamicTag::TlsDescPlt
+            | DynamicTag::TlsDescGot
+            | DynamicTag::GnuConflict
+            | DynamicTag::GnuLiblist
+            | DynamicTag::PltPad
+            | DynamicTag::MoveTab
+            | DynamicTag::SymInfo
+            | DynamicTag::VerSym
+            | DynamicTag::VerDef
+            | DynamicTag::VerNeed => DynamicValue::Address(value),
+        };
+        Ok(value)
+    }
+}
+
+impl fmt::Display for DynamicValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicValue::None => write!(f, "0x0"),
+            DynamicValue::String(s) => write!(f, "{s}"),
+            DynamicValue::Address(addr) => write!(f, "0x{addr:x}"),
+            DynamicValue::Size(size) => write!(f, "{size} (bytes)"),
+            DynamicValue::Count(count) | DynamicValue::Integer(count) => write!(f, "{count}"),
+            DynamicValue::PltRel(tag) => write!(f, "{}", tag.name()),
+            DynamicValue::Flags(flags) => {
+                let names = flags.iter().map(|f| f.name()).collect::<Vec<_>>();
+                write!(f, "{}", names.join(" "))
+            }
+            DynamicValue::Flags1(flags) => {
+                let names = flags.iter().map(|f| f.name()).collect::<Vec<_>>();
+                write!(f, "{}", names.join(" "))
+            }
+        }
+    }
+}
+
+/// Flags stored in DT_FLAGS.
+#[bitflags]
+#[repr(u64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DynamicFlag {
+    Origin = raw::DF_ORIGIN,
+    Symbolic = raw::DF_SYMBOLIC,
+    TextRel = raw::DF_TEXTREL,
+    BindNow = raw::DF_BIND_NOW,
+    StaticTls = raw::DF_STATIC_TLS,
+}
+
+impl DynamicFlag {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynamicFlag::Origin => "ORIGIN",
+            DynamicFlag::Symbolic => "SYMBOLIC",
+            DynamicFlag::TextRel => "TEXTREL",
+            DynamicFlag::BindNow => "BIND_NOW",
+            DynamicFlag::StaticTls => "STATIC_TLS",
+        }
+    }
+}
+
+/// Flags stored in DT_FLAGS_1.
+#[bitflags]
+#[repr(u64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DynamicFlag1 {
+    Now = 0x1,
+    Global = 0x2,
+    Group = 0x4,
+    NoDelete = 0x8,
+    LoadFltr = 0x10,
+    InitFirst = 0x20,
+    NoOpen = 0x40,
+    Origin = 0x80,
+    Direct = 0x100,
+    Trans = 0x200,
+    Interpose = 0x400,
+    NoDefLib = 0x800,
+    NoDump = 0x1000,
+    ConfAlt = 0x2000,
+    EndFiltee = 0x4000,
+    DispRelDne = 0x8000,
+    DispRelPnd = 0x10000,
+    NoDirect = 0x20000,
+    IgnMulDef = 0x40000,
+    NoKSyms = 0x80000,
+    NoHdr = 0x100000,
+    Edited = 0x200000,
+    NoReloc = 0x400000,
+    SymIntpose = 0x800000,
+    GlobAudit = 0x1000000,
+    Singleton = 0x2000000,
+    Stub = 0x4000000,
+    Pie = 0x8000000,
+    KMod = 0x10000000,
+    WeakFilter = 0x20000000,
+    NoCommon = 0x40000000,
+}
+
+impl DynamicFlag1 {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynamicFlag1::Now => "NOW",
+            DynamicFlag1::Global => "GLOBAL",
+            DynamicFlag1::Group => "GROUP",
+            DynamicFlag1::NoDelete => "NODELETE",
+            DynamicFlag1::LoadFltr => "LOADFLTR",
+            DynamicFlag1::InitFirst => "INITFIRST",
+            DynamicFlag1::NoOpen => "NOOPEN",
+            DynamicFlag1::Origin => "ORIGIN",
+            DynamicFlag1::Direct => "DIRECT",
+            DynamicFlag1::Trans => "TRANS",
+            DynamicFlag1::Interpose => "INTERPOSE",
+            DynamicFlag1::NoDefLib => "NODEFLIB",
+            DynamicFlag1::NoDump => "NODUMP",
+            DynamicFlag1::ConfAlt => "CONFALT",
+            DynamicFlag1::EndFiltee => "ENDFILTEE",
+            DynamicFlag1::DispRelDne => "DISPRELDNE",
+            DynamicFlag1::DispRelPnd => "DISPRELPND",
+            DynamicFlag1::NoDirect => "NODIRECT",
+            DynamicFlag1::IgnMulDef => "IGNMULDEF",
+            DynamicFlag1::NoKSyms => "NOKSYMS",
+            DynamicFlag1::NoHdr => "NOHDR",
+            DynamicFlag1::Edited => "EDITED",
+            DynamicFlag1::NoReloc => "NORELOC",
+            DynamicFlag1::SymIntpose => "SYMINTPOSE",
+            DynamicFlag1::GlobAudit => "GLOBAUDIT",
+            DynamicFlag1::Singleton => "SINGLETON",
+            DynamicFlag1::Stub => "STUB",
+            DynamicFlag1::Pie => "PIE",
+            DynamicFlag1::KMod => "KMOD",
+            DynamicFlag1::WeakFilter => "WEAKFILTER",
+            DynamicFlag1::NoCommon => "NOCOMMON",
+        }
+    }
 }
@@ -2,6 +2,7 @@ use self::{header::Header, section::SectionHeader, segment::ProgramHeader};
 
 pub mod dynamic;
 pub mod header;
+pub mod note;
 pub mod relocation;
 pub mod section;
 pub mod segment;
@@ -22,4 +23,6 @@ pub struct Elf {
 pub enum Error {
     #[error("invalid elf")]
     InvalidElf,
+    #[error(transparent)]
+    Raw(#[from] crate::raw::Error),
 }
@@ -0,0 +1,433 @@
+use std::fmt;
+
+use enumflags2::{bitflags, BitFlags};
+
+use crate::raw::{self, EM_AARCH64, EM_X86_64};
+
+use super::Error;
+
+/// A decoded note. Notes are identified by their owner name together with their type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Note {
+    GnuAbiTag(AbiTag),
+    GnuBuildId(Vec<u8>),
+    GnuGoldVersion(String),
+    GnuProperties(Vec<GnuProperty>),
+    /// JSON package metadata, see https://systemd.io/ELF_PACKAGE_METADATA/
+    FdoPackagingMetadata(String),
+    GoBuildId(String),
+    AndroidIdent {
+        api_level: u32,
+    },
+    Unknown {
+        name: String,
+        n_type: u32,
+        desc: Vec<u8>,
+    },
+}
+
+impl Note {
+    /// Decodes a note. The machine is needed to interpret processor-specific GNU properties.
+    pub fn from_raw(note: &raw::note::Note, machine: u16) -> Result<Self, Error> {
+        let desc = note.desc;
+        let parsed = match (note.name, note.n_type) {
+            (b"GNU", raw::NT_GNU_ABI_TAG) => Note::GnuAbiTag(AbiTag::parse(desc)?),
+            (b"GNU", raw::NT_GNU_BUILD_ID) => Note::GnuBuildId(desc.to_vec()),
+            (b"GNU", raw::NT_GNU_GOLD_VERSION) => Note::GnuGoldVersion(desc_string(desc)?),
+            (b"GNU", raw::NT_GNU_PROPERTY_TYPE_0) => {
+                Note::GnuProperties(GnuProperty::parse_all(desc, machine)?)
+            }
+            (b"FDO", raw::NT_FDO_PACKAGING_METADATA) => {
+                Note::FdoPackagingMetadata(desc_string(desc)?)
+            }
+            (b"Go", raw::NT_GO_BUILDID) => Note::GoBuildId(desc_string(desc)?),
+            (b"Android", raw::NT_ANDROID_TYPE_IDENT) => Note::AndroidIdent {
+                api_level: read_u32(desc, 0)?,
+            },
+            _ => Note::Unknown {
+                name: String::from_utf8_lossy(note.name).into_owned(),
+                n_type: note.n_type,
+                desc: desc.to_vec(),
+            },
+        };
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Note::GnuAbiTag(tag) => write!(
+                f,
+                "NT_GNU_ABI_TAG: OS: {:?}, ABI: {}.{}.{}",
+                tag.os, tag.major, tag.minor, tag.subminor
+            ),
+            Note::GnuBuildId(id) => write!(f, "NT_GNU_BUILD_ID: {}", hex(id)),
+            Note::GnuGoldVersion(version) => write!(f, "NT_GNU_GOLD_VERSION: {version}"),
+            Note::GnuProperties(props) => {
+                write!(f, "NT_GNU_PROPERTY_TYPE_0: ")?;
+                let props = props.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", props.join("; "))
+            }
+            Note::FdoPackagingMetadata(json) => write!(f, "NT_FDO_PACKAGING_METADATA: {json}"),
+            Note::GoBuildId(id) => write!(f, "GO BUILDID: {id}"),
+            Note::AndroidIdent { api_level } => write!(f, "Android API level: {api_level}"),
+            Note::Unknown { name, n_type, desc } => {
+                write!(f, "{name} note type 0x{n_type:08x} ({} bytes)", desc.len())
+            }
+        }
+    }
+}
+
+/// Contents of NT_GNU_ABI_TAG: the minimum kernel ABI required by the object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AbiTag {
+    pub os: AbiTagOs,
+    pub major: u32,
+    pub minor: u32,
+    pub subminor: u32,
+}
+
+impl AbiTag {
+    fn parse(desc: &[u8]) -> Result<Self, Error> {
+        let os = match read_u32(desc, 0)? {
+            0 => AbiTagOs::Linux,
+            1 => AbiTagOs::Hurd,
+            2 => AbiTagOs::Solaris,
+            3 => AbiTagOs::FreeBsd,
+            other => AbiTagOs::Other(other),
+        };
+
+        Ok(AbiTag {
+            os,
+            major: read_u32(desc, 4)?,
+            minor: read_u32(desc, 8)?,
+            subminor: read_u32(desc, 12)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiTagOs {
+    Linux,
+    Hurd,
+    Solaris,
+    FreeBsd,
+    Other(u32),
+}
+
+/// A property from NT_GNU_PROPERTY_TYPE_0, found in `.note.gnu.property`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GnuProperty {
+    StackSize(u64),
+    NoCopyOnProtected,
+    /// Features every input object was built with; the object is only
+    /// protected by IBT or SHSTK if all of its code supports them.
+    X86Feature1And(BitFlags<X86Feature1>),
+    X86Feature2Needed(BitFlags<X86Feature2>),
+    X86Feature2Used(BitFlags<X86Feature2>),
+    /// Micro-architecture levels the object requires to run.
+    X86Isa1Needed(BitFlags<X86Isa1>),
+    X86Isa1Used(BitFlags<X86Isa1>),
+    AArch64Feature1And(BitFlags<AArch64Feature1>),
+    Unknown {
+        pr_type: u32,
+        data: Vec<u8>,
+    },
+}
+
+impl GnuProperty {
+    /// Parses the array of properties in a note descriptor. Each property is
+    /// padded to 8 bytes in 64-bit objects.
+    fn parse_all(mut desc: &[u8], machine: u16) -> Result<Vec<GnuProperty>, Error> {
+        let mut props = vec![];
+        while !desc.is_empty() {
+            let pr_type = read_u32(desc, 0)?;
+            let pr_datasz = read_u32(desc, 4)? as usize;
+            let data = desc.get(8..(8 + pr_datasz)).ok_or(Error::InvalidElf)?;
+
+            let prop = match (machine, pr_type) {
+                (_, raw::GNU_PROPERTY_STACK_SIZE) => GnuProperty::StackSize(read_u64(data, 0)?),
+                (_, raw::GNU_PROPERTY_NO_COPY_ON_PROTECTED) => GnuProperty::NoCopyOnProtected,
+                (EM_X86_64, raw::GNU_PROPERTY_X86_FEATURE_1_AND) => {
+                    GnuProperty::X86Feature1And(BitFlags::from_bits_truncate(read_u32(data, 0)?))
+                }
+                (EM_X86_64, raw::GNU_PROPERTY_X86_FEATURE_2_NEEDED) => {
+                    GnuProperty::X86Feature2Needed(BitFlags::from_bits_truncate(read_u32(data, 0)?))
+                }
+                (EM_X86_64, raw::GNU_PROPERTY_X86_FEATURE_2_USED) => {
+                    GnuProperty::X86Feature2Used(BitFlags::from_bits_truncate(read_u32(data, 0)?))
+                }
+                (EM_X86_64, raw::GNU_PROPERTY_X86_ISA_1_NEEDED) => {
+                    GnuProperty::X86Isa1Needed(BitFlags::from_bits_truncate(read_u32(data, 0)?))
+                }
+                (EM_X86_64, raw::GNU_PROPERTY_X86_ISA_1_USED) => {
+                    GnuProperty::X86Isa1Used(BitFlags::from_bits_truncate(read_u32(data, 0)?))
+                }
+                (EM_AARCH64, raw::GNU_PROPERTY_AARCH64_FEATURE_1_AND) => {
+                    GnuProperty::AArch64Feature1And(BitFlags::from_bits_truncate(read_u32(
+                        data, 0,
+                    )?))
+                }
+                _ => GnuProperty::Unknown {
+                    pr_type,
+                    data: data.to_vec(),
+                },
+            };
+            props.push(prop);
+
+            let next = (8 + pr_datasz + 7) & !7;
+            desc = desc.get(next..).unwrap_or(&[]);
+        }
+        Ok(props)
+    }
+}
+
+/// Renders properties the way `readelf` does.
+impl fmt::Display for GnuProperty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn names<T: enumflags2::BitFlag>(
+            flags: &BitFlags<T>,
+            name: fn(&T) -> &'static str,
+        ) -> String {
+            if flags.is_empty() {
+                return "<None>".to_string();
+            }
+            flags
+                .iter()
+                .map(|f| name(&f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        match self {
+            GnuProperty::StackSize(size) => write!(f, "stack size: 0x{size:x}"),
+            GnuProperty::NoCopyOnProtected => write!(f, "no copy on protected"),
+            GnuProperty::X86Feature1And(flags) => {
+                write!(f, "x86 feature: {}", names(flags, X86Feature1::name))
+            }
+            GnuProperty::X86Feature2Needed(flags) => {
+                write!(f, "x86 feature needed: {}", names(flags, X86Feature2::name))
+            }
+            GnuProperty::X86Feature2Used(flags) => {
+                write!(f, "x86 feature used: {}", names(flags, X86Feature2::name))
+            }
+            GnuProperty::X86Isa1Needed(flags) => {
+                write!(f, "x86 ISA needed: {}", names(flags, X86Isa1::name))
+            }
+            GnuProperty::X86Isa1Used(flags) => {
+                write!(f, "x86 ISA used: {}", names(flags, X86Isa1::name))
+            }
+            GnuProperty::AArch64Feature1And(flags) => {
+                write!(
+                    f,
+                    "AArch64 feature: {}",
+                    names(flags, AArch64Feature1::name)
+                )
+            }
+            GnuProperty::Unknown { pr_type, data } => {
+                write!(f, "<unknown: 0x{pr_type:x}> ({} bytes)", data.len())
+            }
+        }
+    }
+}
+
+#[bitflags]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum X86Feature1 {
+    /// Indirect branch tracking
+    Ibt = 0x1,
+    /// Shadow stack
+    Shstk = 0x2,
+    LamU48 = 0x4,
+    LamU57 = 0x8,
+}
+
+impl X86Feature1 {
+    pub fn name(&self) -> &'static str {
+        match self {
+            X86Feature1::Ibt => "IBT",
+            X86Feature1::Shstk => "SHSTK",
+            X86Feature1::LamU48 => "LAM_U48",
+            X86Feature1::LamU57 => "LAM_U57",
+        }
+    }
+}
+
+#[bitflags]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum X86Feature2 {
+    X86 = 0x1,
+    X87 = 0x2,
+    Mmx = 0x4,
+    Xmm = 0x8,
+    Ymm = 0x10,
+    Zmm = 0x20,
+    Fxsr = 0x40,
+    Xsave = 0x80,
+    Xsaveopt = 0x100,
+    Xsavec = 0x200,
+    Tmm = 0x400,
+    Mask = 0x800,
+}
+
+impl X86Feature2 {
+    pub fn name(&self) -> &'static str {
+        match self {
+            X86Feature2::X86 => "x86",
+            X86Feature2::X87 => "x87",
+            X86Feature2::Mmx => "MMX",
+            X86Feature2::Xmm => "XMM",
+            X86Feature2::Ymm => "YMM",
+            X86Feature2::Zmm => "ZMM",
+            X86Feature2::Fxsr => "FXSR",
+            X86Feature2::Xsave => "XSAVE",
+            X86Feature2::Xsaveopt => "XSAVEOPT",
+            X86Feature2::Xsavec => "XSAVEC",
+            X86Feature2::Tmm => "TMM",
+            X86Feature2::Mask => "MASK",
+        }
+    }
+}
+
+/// x86-64 micro-architecture levels, as defined by the x86-64 psABI.
+#[bitflags]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub enum X86Isa1 {
+    Baseline = 0x1,
+    V2 = 0x2,
+    V3 = 0x4,
+    V4 = 0x8,
+}
+
+impl X86Isa1 {
+    pub fn name(&self) -> &'static str {
+        match self {
+            X86Isa1::Baseline => "x86-64-baseline",
+            X86Isa1::V2 => "x86-64-v2",
+            X86Isa1::V3 => "x86-64-v3",
+            X86Isa1::V4 => "x86-64-v4",
+        }
+    }
+}
+
+#[bitflags]
+#[repr(u32)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AArch64Feature1 {
+    /// Branch target identification
+    Bti = 0x1,
+    /// Pointer authentication
+    Pac = 0x2,
+    /// Guarded control stack
+    Gcs = 0x4,
+}
+
+impl AArch64Feature1 {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AArch64Feature1::Bti => "BTI",
+            AArch64Feature1::Pac => "PAC",
+            AArch64Feature1::Gcs => "GCS",
+        }
+    }
+}
+
+/// Hardening-relevant properties of an object, merged from its GNU property notes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GnuPropertySummary {
+    pub x86_features: BitFlags<X86Feature1>,
+    pub x86_isa_needed: BitFlags<X86Isa1>,
+    pub aarch64_features: BitFlags<AArch64Feature1>,
+}
+
+impl GnuPropertySummary {
+    /// Features recorded as AND properties are only present if every note that
+    /// records them has them, while needed ISA levels accumulate across notes.
+    pub fn from_notes<'a, I: IntoIterator<Item = &'a Note>>(notes: I) -> Self {
+        let mut x86_features: Option<BitFlags<X86Feature1>> = None;
+        let mut aarch64_features: Option<BitFlags<AArch64Feature1>> = None;
+        let mut x86_isa_needed = BitFlags::empty();
+        for note in notes {
+            if let Note::GnuProperties(props) = note {
+                for prop in props {
+                    match prop {
+                        GnuProperty::X86Feature1And(flags) => {
+                            x86_features = Some(x86_features.map_or(*flags, |f| f & *flags))
+                        }
+                        GnuProperty::X86Isa1Needed(flags) => x86_isa_needed |= *flags,
+                        GnuProperty::AArch64Feature1And(flags) => {
+                            aarch64_features = Some(aarch64_features.map_or(*flags, |f| f & *flags))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        GnuPropertySummary {
+            x86_features: x86_features.unwrap_or_default(),
+            x86_isa_needed,
+            aarch64_features: aarch64_features.unwrap_or_default(),
+        }
+    }
+
+    /// The highest x86-64 micro-architecture level the object needs, if recorded.
+    pub fn x86_isa_level(&self) -> Option<X86Isa1> {
+        self.x86_isa_needed.iter().last()
+    }
+
+    /// Whether the object was built with both indirect branch tracking and shadow stacks.
+    pub fn has_cet(&self) -> bool {
+        self.x86_features
+            .contains(X86Feature1::Ibt | X86Feature1::Shstk)
+    }
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Result<u32, Error> {
+    buf.get(offset..(offset + 4))
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .ok_or(Error::InvalidElf)
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> Result<u64, Error> {
+    buf.get(offset..(offset + 8))
+        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+        .ok_or(Error::InvalidElf)
+}
+
+/// Reads a string descriptor, which may or may not be NUL-terminated.
+fn desc_string(desc: &[u8]) -> Result<String, Error> {
+    let end = desc.iter().position(|&b| b == 0).unwrap_or(desc.len());
+    String::from_utf8(desc[..end].to_vec()).map_err(|_| Error::InvalidElf)
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_intersects_and_properties() {
+        let notes = [
+            Note::GnuProperties(vec![
+                GnuProperty::X86Feature1And(X86Feature1::Ibt | X86Feature1::Shstk),
+                GnuProperty::X86Isa1Needed(X86Isa1::Baseline.into()),
+            ]),
+            Note::GnuProperties(vec![
+                GnuProperty::X86Feature1And(X86Feature1::Ibt.into()),
+                GnuProperty::X86Isa1Needed(X86Isa1::V2.into()),
+            ]),
+        ];
+        let summary = GnuPropertySummary::from_notes(&notes);
+        assert_eq!(summary.x86_features, X86Feature1::Ibt);
+        assert!(!summary.has_cet());
+        assert_eq!(summary.x86_isa_needed, X86Isa1::Baseline | X86Isa1::V2);
+        assert_eq!(summary.x86_isa_level(), Some(X86Isa1::V2));
+    }
+}
@@ -3,5 +3,7 @@ use num_derive::{FromPrimitive, ToPrimitive};
 #[derive(Debug, Clone, ToPrimitive, FromPrimitive)]
 #[repr(u32)]
 pub enum RelocationType {
+    X86_64IRelative = 37,
     AArch64Relative = 0x403,
+    AArch64IRelative = 0x408,
 }
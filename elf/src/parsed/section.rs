@@ -1,25 +1,276 @@
-#[derive(Debug, Clone)]
-pub struct SectionHeader {}
+use std::fmt;
+
+use enumflags2::{bitflags, BitFlags};
+
+use crate::raw::{self, EM_AARCH64, EM_ARM, EM_RISCV, EM_X86_64};
+
+use super::Address;
 
 #[derive(Debug, Clone)]
+pub struct SectionHeader {
+    pub r#type: SectionType,
+    pub flags: BitFlags<SectionFlag>,
+    pub addr: Address,
+    pub offset: u64,
+    pub size: u64,
+    pub link: u32,
+    pub info: u32,
+    pub addralign: u64,
+    pub entsize: u64,
+}
+
+impl SectionHeader {
+    /// Section types in the processor-specific range overlap between architectures,
+    /// so the machine from the file header is needed to decode them.
+    pub fn from_raw(hdr: &raw::header::SectionHeader, machine: u16) -> Self {
+        let r#type = SectionType::from_u32(hdr.sh_type, machine);
+
+        SectionHeader {
+            r#type,
+            flags: BitFlags::from_bits_truncate(hdr.sh_flags),
+            addr: hdr.sh_addr,
+            offset: hdr.sh_offset,
+            size: hdr.sh_size,
+            link: hdr.sh_link,
+            info: hdr.sh_info,
+            addralign: hdr.sh_addralign,
+            entsize: hdr.sh_entsize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SectionType {
-    Null = 0x0,
-    Progbits = 0x1,
-    Symtab = 0x2,
-    Strtab = 0x3,
-    Rela = 0x4,
-    Hash = 0x5,
-    Dynamic = 0x6,
-    Note = 0x7,
-    Nobits = 0x8,
-    Rel = 0x9,
-    Shlib = 0xa,
-    Dynsym = 0xb,
-    InitArray = 0xe,
-    FiniArray = 0xf,
-    PreinitArray = 0x10,
-    Group = 0x11,
-    SymtabShndx = 0x12,
-    ShtNum = 0x13,
-    // Os(u32),
+    Null,
+    Progbits,
+    Symtab,
+    Strtab,
+    Rela,
+    Hash,
+    Dynamic,
+    Note,
+    Nobits,
+    Rel,
+    Shlib,
+    Dynsym,
+    InitArray,
+    FiniArray,
+    PreinitArray,
+    Group,
+    SymtabShndx,
+    Relr,
+    AndroidRel,
+    AndroidRela,
+    AndroidRelr,
+    LlvmOdrtab,
+    LlvmLinkerOptions,
+    LlvmAddrsig,
+    LlvmDependentLibraries,
+    LlvmSympart,
+    LlvmPartEhdr,
+    LlvmPartPhdr,
+    LlvmCallGraphProfile,
+    LlvmBbAddrMap,
+    LlvmOffloading,
+    LlvmLto,
+    GnuAttributes,
+    GnuHash,
+    GnuLiblist,
+    Checksum,
+    GnuVerdef,
+    GnuVerneed,
+    GnuVersym,
+    ArmExidx,
+    ArmPreemptmap,
+    ArmAttributes,
+    ArmDebugoverlay,
+    ArmOverlaysection,
+    X86_64Unwind,
+    AArch64Attributes,
+    RiscvAttributes,
+    Os(u32),
+    Proc(u32),
+    User(u32),
+    /// A reserved value outside the OS, processor and user ranges
+    Unknown(u32),
+}
+
+impl SectionType {
+    pub fn from_u32(value: u32, machine: u16) -> SectionType {
+        match value {
+            raw::SHT_NULL => SectionType::Null,
+            raw::SHT_PROGBITS => SectionType::Progbits,
+            raw::SHT_SYMTAB => SectionType::Symtab,
+            raw::SHT_STRTAB => SectionType::Strtab,
+            raw::SHT_RELA => SectionType::Rela,
+            raw::SHT_HASH => SectionType::Hash,
+            raw::SHT_DYNAMIC => SectionType::Dynamic,
+            raw::SHT_NOTE => SectionType::Note,
+            raw::SHT_NOBITS => SectionType::Nobits,
+            raw::SHT_REL => SectionType::Rel,
+            raw::SHT_SHLIB => SectionType::Shlib,
+            raw::SHT_DYNSYM => SectionType::Dynsym,
+            raw::SHT_INIT_ARRAY => SectionType::InitArray,
+            raw::SHT_FINI_ARRAY => SectionType::FiniArray,
+            raw::SHT_PREINIT_ARRAY => SectionType::PreinitArray,
+            raw::SHT_GROUP => SectionType::Group,
+            raw::SHT_SYMTAB_SHNDX => SectionType::SymtabShndx,
+            raw::SHT_RELR => SectionType::Relr,
+            raw::SHT_ANDROID_REL => SectionType::AndroidRel,
+            raw::SHT_ANDROID_RELA => SectionType::AndroidRela,
+            raw::SHT_ANDROID_RELR => SectionType::AndroidRelr,
+            raw::SHT_LLVM_ODRTAB => SectionType::LlvmOdrtab,
+            raw::SHT_LLVM_LINKER_OPTIONS => SectionType::LlvmLinkerOptions,
+            raw::SHT_LLVM_ADDRSIG => SectionType::LlvmAddrsig,
+            raw::SHT_LLVM_DEPENDENT_LIBRARIES => SectionType::LlvmDependentLibraries,
+            raw::SHT_LLVM_SYMPART => SectionType::LlvmSympart,
+            raw::SHT_LLVM_PART_EHDR => SectionType::LlvmPartEhdr,
+            raw::SHT_LLVM_PART_PHDR => SectionType::LlvmPartPhdr,
+            raw::SHT_LLVM_CALL_GRAPH_PROFILE => SectionType::LlvmCallGraphProfile,
+            raw::SHT_LLVM_BB_ADDR_MAP => SectionType::LlvmBbAddrMap,
+            raw::SHT_LLVM_OFFLOADING => SectionType::LlvmOffloading,
+            raw::SHT_LLVM_LTO => SectionType::LlvmLto,
+            raw::SHT_GNU_ATTRIBUTES => SectionType::GnuAttributes,
+            raw::SHT_GNU_HASH => SectionType::GnuHash,
+            raw::SHT_GNU_LIBLIST => SectionType::GnuLiblist,
+            raw::SHT_CHECKSUM => SectionType::Checksum,
+            raw::SHT_GNU_VERDEF => SectionType::GnuVerdef,
+            raw::SHT_GNU_VERNEED => SectionType::GnuVerneed,
+            raw::SHT_GNU_VERSYM => SectionType::GnuVersym,
+            raw::SHT_LOOS..=raw::SHT_HIOS => SectionType::Os(value),
+            raw::SHT_LOPROC..=raw::SHT_HIPROC => match (machine, value) {
+                (EM_ARM, raw::SHT_ARM_EXIDX) => SectionType::ArmExidx,
+                (EM_ARM, raw::SHT_ARM_PREEMPTMAP) => SectionType::ArmPreemptmap,
+                (EM_ARM, raw::SHT_ARM_ATTRIBUTES) => SectionType::ArmAttributes,
+                (EM_ARM, raw::SHT_ARM_DEBUGOVERLAY) => SectionType::ArmDebugoverlay,
+                (EM_ARM, raw::SHT_ARM_OVERLAYSECTION) => SectionType::ArmOverlaysection,
+                (EM_X86_64, raw::SHT_X86_64_UNWIND) => SectionType::X86_64Unwind,
+                (EM_AARCH64, raw::SHT_AARCH64_ATTRIBUTES) => SectionType::AArch64Attributes,
+                (EM_RISCV, raw::SHT_RISCV_ATTRIBUTES) => SectionType::RiscvAttributes,
+                _ => SectionType::Proc(value),
+            },
+            raw::SHT_LOUSER..=raw::SHT_HIUSER => SectionType::User(value),
+            _ => SectionType::Unknown(value),
+        }
+    }
+}
+
+/// Renders section types the way `readelf` does.
+impl fmt::Display for SectionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SectionType::Null => "NULL",
+            SectionType::Progbits => "PROGBITS",
+            SectionType::Symtab => "SYMTAB",
+            SectionType::Strtab => "STRTAB",
+            SectionType::Rela => "RELA",
+            SectionType::Hash => "HASH",
+            SectionType::Dynamic => "DYNAMIC",
+            SectionType::Note => "NOTE",
+            SectionType::Nobits => "NOBITS",
+            SectionType::Rel => "REL",
+            SectionType::Shlib => "SHLIB",
+            SectionType::Dynsym => "DYNSYM",
+            SectionType::InitArray => "INIT_ARRAY",
+            SectionType::FiniArray => "FINI_ARRAY",
+            SectionType::PreinitArray => "PREINIT_ARRAY",
+            SectionType::Group => "GROUP",
+            SectionType::SymtabShndx => "SYMTAB SECTION INDICES",
+            SectionType::Relr => "RELR",
+            SectionType::AndroidRel => "ANDROID_REL",
+            SectionType::AndroidRela => "ANDROID_RELA",
+            SectionType::AndroidRelr => "ANDROID_RELR",
+            SectionType::LlvmOdrtab => "LLVM_ODRTAB",
+            SectionType::LlvmLinkerOptions => "LLVM_LINKER_OPTIONS",
+            SectionType::LlvmAddrsig => "LLVM_ADDRSIG",
+            SectionType::LlvmDependentLibraries => "LLVM_DEPENDENT_LIBRARIES",
+            SectionType::LlvmSympart => "LLVM_SYMPART",
+            SectionType::LlvmPartEhdr => "LLVM_PART_EHDR",
+            SectionType::LlvmPartPhdr => "LLVM_PART_PHDR",
+            SectionType::LlvmCallGraphProfile => "LLVM_CALL_GRAPH_PROFILE",
+            SectionType::LlvmBbAddrMap => "LLVM_BB_ADDR_MAP",
+            SectionType::LlvmOffloading => "LLVM_OFFLOADING",
+            SectionType::LlvmLto => "LLVM_LTO",
+            SectionType::GnuAttributes => "GNU_ATTRIBUTES",
+            SectionType::GnuHash => "GNU_HASH",
+            SectionType::GnuLiblist => "GNU_LIBLIST",
+            SectionType::Checksum => "CHECKSUM",
+            SectionType::GnuVerdef => "VERDEF",
+            SectionType::GnuVerneed => "VERNEED",
+            SectionType::GnuVersym => "VERSYM",
+            SectionType::ArmExidx => "ARM_EXIDX",
+            SectionType::ArmPreemptmap => "ARM_PREEMPTMAP",
+            SectionType::ArmAttributes => "ARM_ATTRIBUTES",
+            SectionType::ArmDebugoverlay => "ARM_DEBUGOVERLAY",
+            SectionType::ArmOverlaysection => "ARM_OVERLAYSECTION",
+            SectionType::X86_64Unwind => "X86_64_UNWIND",
+            SectionType::AArch64Attributes => "AARCH64_ATTRIBUTES",
+            SectionType::RiscvAttributes => "RISCV_ATTRIBUTES",
+            SectionType::Os(value) => return write!(f, "LOOS+0x{:x}", value - raw::SHT_LOOS),
+            SectionType::Proc(value) => return write!(f, "LOPROC+0x{:x}", value - raw::SHT_LOPROC),
+            SectionType::User(value) => return write!(f, "LOUSER+0x{:x}", value - raw::SHT_LOUSER),
+            SectionType::Unknown(value) => return write!(f, "<unknown>: {:x}", value),
+        };
+        f.write_str(name)
+    }
+}
+
+#[bitflags]
+#[repr(u64)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SectionFlag {
+    Write = 0x1,
+    Alloc = 0x2,
+    ExecInstr = 0x4,
+    Merge = 0x10,
+    Strings = 0x20,
+    InfoLink = 0x40,
+    LinkOrder = 0x80,
+    OsNonconforming = 0x100,
+    Group = 0x200,
+    Tls = 0x400,
+    Compressed = 0x800,
+    GnuRetain = 0x200000,
+    Exclude = 0x80000000,
+}
+
+impl SectionFlag {
+    /// The key letter used by `readelf` for this flag.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SectionFlag::Write => "W",
+            SectionFlag::Alloc => "A",
+            SectionFlag::ExecInstr => "X",
+            SectionFlag::Merge => "M",
+            SectionFlag::Strings => "S",
+            SectionFlag::InfoLink => "I",
+            SectionFlag::LinkOrder => "L",
+            SectionFlag::OsNonconforming => "O",
+            SectionFlag::Group => "G",
+            SectionFlag::Tls => "T",
+            SectionFlag::Compressed => "C",
+            SectionFlag::GnuRetain => "R",
+            SectionFlag::Exclude => "E",
+        }
+    }
+}
+
+/// Renders the raw `sh_flags` of a section as `readelf` key letters.
+/// Bits in the OS and processor ranges that have no dedicated flag are shown
+/// as `o` and `p` respectively, and any other unknown bits as `x`.
+pub fn flag_letters(sh_flags: u64) -> String {
+    let flags = BitFlags::<SectionFlag>::from_bits_truncate(sh_flags);
+    let mut letters: String = flags.iter().map(|f| f.name()).collect();
+
+    let rest = sh_flags & !flags.bits();
+    if rest & raw::SHF_MASKOS != 0 {
+        letters.push('o');
+    }
+    if rest & raw::SHF_MASKPROC != 0 {
+        letters.push('p');
+    }
+    if rest & !(raw::SHF_MASKOS | raw::SHF_MASKPROC) != 0 {
+        letters.push('x');
+    }
+    letters
 }
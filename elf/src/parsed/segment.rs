@@ -1,5 +1,8 @@
+use std::fmt;
+
 use enumflags2::{bitflags, BitFlags};
-use num_derive::{FromPrimitive, ToPrimitive};
+
+use crate::raw::{self, EM_AARCH64, EM_ARM, EM_MIPS, EM_RISCV};
 
 use super::Address;
 
@@ -7,6 +10,10 @@ use super::Address;
 pub struct ProgramHeader {
     pub r#type: SegmentType,
     pub flags: BitFlags<SegmentFlag>,
+    /// OS-specific bits of `p_flags` (`PF_MASKOS`), which have no generic meaning.
+    pub os_flags: u32,
+    /// Processor-specific bits of `p_flags` (`PF_MASKPROC`), which have no generic meaning.
+    pub proc_flags: u32,
     pub offset: u64,
     pub vaddr: Address,
     pub paddr: Address,
@@ -16,20 +23,143 @@ pub struct ProgramHeader {
     // TODO: how do we refer to the contents? byte array, parsed format, etc? redundancy in tagging
 }
 
-#[derive(Debug, Clone, ToPrimitive, FromPrimitive)]
+impl ProgramHeader {
+    /// Segment types in the processor-specific range overlap between architectures,
+    /// so the machine from the file header is needed to decode them.
+    pub fn from_raw(hdr: &raw::header::ProgramHeader, machine: u16) -> Self {
+        let r#type = SegmentType::from_u32(hdr.get_type(), machine);
+        let p_flags = hdr.get_flags();
+
+        ProgramHeader {
+            r#type,
+            flags: BitFlags::from_bits_truncate(p_flags),
+            os_flags: p_flags & raw::PF_MASKOS,
+            proc_flags: p_flags & raw::PF_MASKPROC,
+            offset: hdr.get_offset(),
+            vaddr: hdr.get_vaddr(),
+            paddr: hdr.get_paddr(),
+            filesz: hdr.get_filesz(),
+            memsz: hdr.get_memsz(),
+            align: hdr.get_align(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SegmentType {
-    Null = 0x0,
-    Load = 0x1,
-    Dynamic = 0x2,
-    Interp = 0x3,
-    Note = 0x4,
-    Shlib = 0x5,
-    Phdr = 0x6,
-    Tls = 0x7,
+    Null,
+    Load,
+    Dynamic,
+    Interp,
+    Note,
+    Shlib,
+    Phdr,
+    Tls,
+    GnuEhFrame,
+    GnuStack,
+    GnuRelro,
+    GnuProperty,
+    GnuSframe,
+    OpenBsdMutable,
+    OpenBsdRandomize,
+    OpenBsdWxNeeded,
+    OpenBsdNoBtCfi,
+    OpenBsdSyscalls,
+    OpenBsdBootData,
+    ArmArchExt,
+    ArmExidx,
+    AArch64MemtagMte,
+    MipsRegInfo,
+    MipsRtProc,
+    MipsOptions,
+    MipsAbiFlags,
+    RiscvAttributes,
+    Os(u32),
+    Proc(u32),
+    /// A reserved value outside the OS and processor ranges
+    Unknown(u32),
+}
+
+impl SegmentType {
+    pub fn from_u32(value: u32, machine: u16) -> SegmentType {
+        match value {
+            raw::PT_NULL => SegmentType::Null,
+            raw::PT_LOAD => SegmentType::Load,
+            raw::PT_DYNAMIC => SegmentType::Dynamic,
+            raw::PT_INTERP => SegmentType::Interp,
+            raw::PT_NOTE => SegmentType::Note,
+            raw::PT_SHLIB => SegmentType::Shlib,
+            raw::PT_PHDR => SegmentType::Phdr,
+            raw::PT_TLS => SegmentType::Tls,
+            raw::PT_GNU_EH_FRAME => SegmentType::GnuEhFrame,
+            raw::PT_GNU_STACK => SegmentType::GnuStack,
+            raw::PT_GNU_RELRO => SegmentType::GnuRelro,
+            raw::PT_GNU_PROPERTY => SegmentType::GnuProperty,
+            raw::PT_GNU_SFRAME => SegmentType::GnuSframe,
+            raw::PT_OPENBSD_MUTABLE => SegmentType::OpenBsdMutable,
+            raw::PT_OPENBSD_RANDOMIZE => SegmentType::OpenBsdRandomize,
+            raw::PT_OPENBSD_WXNEEDED => SegmentType::OpenBsdWxNeeded,
+            raw::PT_OPENBSD_NOBTCFI => SegmentType::OpenBsdNoBtCfi,
+            raw::PT_OPENBSD_SYSCALLS => SegmentType::OpenBsdSyscalls,
+            raw::PT_OPENBSD_BOOTDATA => SegmentType::OpenBsdBootData,
+            raw::PT_LOOS..=raw::PT_HIOS => SegmentType::Os(value),
+            raw::PT_LOPROC..=raw::PT_HIPROC => match (machine, value) {
+                (EM_ARM, raw::PT_ARM_ARCHEXT) => SegmentType::ArmArchExt,
+                (EM_ARM, raw::PT_ARM_EXIDX) => SegmentType::ArmExidx,
+                (EM_AARCH64, raw::PT_AARCH64_MEMTAG_MTE) => SegmentType::AArch64MemtagMte,
+                (EM_MIPS, raw::PT_MIPS_REGINFO) => SegmentType::MipsRegInfo,
+                (EM_MIPS, raw::PT_MIPS_RTPROC) => SegmentType::MipsRtProc,
+                (EM_MIPS, raw::PT_MIPS_OPTIONS) => SegmentType::MipsOptions,
+                (EM_MIPS, raw::PT_MIPS_ABIFLAGS) => SegmentType::MipsAbiFlags,
+                (EM_RISCV, raw::PT_RISCV_ATTRIBUTES) => SegmentType::RiscvAttributes,
+                _ => SegmentType::Proc(value),
+            },
+            _ => SegmentType::Unknown(value),
+        }
+    }
+}
+
+/// Renders segment types the way `readelf` does.
+impl fmt::Display for SegmentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SegmentType::Null => "NULL",
+            SegmentType::Load => "LOAD",
+            SegmentType::Dynamic => "DYNAMIC",
+            SegmentType::Interp => "INTERP",
+            SegmentType::Note => "NOTE",
+            SegmentType::Shlib => "SHLIB",
+            SegmentType::Phdr => "PHDR",
+            SegmentType::Tls => "TLS",
+            SegmentType::GnuEhFrame => "GNU_EH_FRAME",
+            SegmentType::GnuStack => "GNU_STACK",
+            SegmentType::GnuRelro => "GNU_RELRO",
+            SegmentType::GnuProperty => "GNU_PROPERTY",
+            SegmentType::GnuSframe => "GNU_SFRAME",
+            SegmentType::OpenBsdMutable => "OPENBSD_MUTABLE",
+            SegmentType::OpenBsdRandomize => "OPENBSD_RANDOMIZE",
+            SegmentType::OpenBsdWxNeeded => "OPENBSD_WXNEEDED",
+            SegmentType::OpenBsdNoBtCfi => "OPENBSD_NOBTCFI",
+            SegmentType::OpenBsdSyscalls => "OPENBSD_SYSCALLS",
+            SegmentType::OpenBsdBootData => "OPENBSD_BOOTDATA",
+            SegmentType::ArmArchExt => "ARM_ARCHEXT",
+            SegmentType::ArmExidx => "ARM_EXIDX",
+            SegmentType::AArch64MemtagMte => "AARCH64_MEMTAG_MTE",
+            SegmentType::MipsRegInfo => "MIPS_REGINFO",
+            SegmentType::MipsRtProc => "MIPS_RTPROC",
+            SegmentType::MipsOptions => "MIPS_OPTIONS",
+            SegmentType::MipsAbiFlags => "MIPS_ABIFLAGS",
+            SegmentType::RiscvAttributes => "RISCV_ATTRIBUTES",
+            SegmentType::Os(value) => return write!(f, "LOOS+0x{:x}", value - raw::PT_LOOS),
+            SegmentType::Proc(value) => return write!(f, "LOPROC+0x{:x}", value - raw::PT_LOPROC),
+            SegmentType::Unknown(value) => return write!(f, "<unknown>: {:x}", value),
+        };
+        f.write_str(name)
+    }
 }
 
 #[bitflags]
-#[repr(u8)]
+#[repr(u32)]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum SegmentFlag {
     Execute = 0b001,
@@ -9,7 +9,9 @@ pub enum SymbolType {
     Section = 3,
     File = 4,
     Common = 5,
-    Loos = 10,
+    Tls = 6,
+    /// An indirect function, whose value is a resolver returning the implementation
+    GnuIfunc = 10,
     Hios = 12,
     Loproc = 13,
     Hiproc = 15,
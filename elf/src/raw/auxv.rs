@@ -0,0 +1,194 @@
+use std::{fs::File, io::Read};
+
+use super::{
+    dynamic::DynamicTable, header::ProgramHeader, Error, AT_BASE, AT_ENTRY, AT_HWCAP, AT_HWCAP2,
+    AT_NULL, AT_PAGESZ, AT_PHDR, AT_PHENT, AT_PHNUM, AT_RANDOM, AT_SYSINFO_EHDR, PT_DYNAMIC,
+    PT_PHDR,
+};
+
+/// The auxiliary vector the kernel passes to a new program above its environment,
+/// describing the executable image, the interpreter and the machine.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuxVector {
+    /// The (type, value) pairs, without the terminating AT_NULL entry
+    pub entries: Vec<(u64, u64)>,
+}
+
+impl AuxVector {
+    /// Reads the entries from their in-memory layout, e.g. the contents of an NT_AUXV
+    /// note or of `/proc/<pid>/auxv`, up to the AT_NULL entry.
+    pub fn from_bytes(buf: &[u8]) -> AuxVector {
+        let entries = buf
+            .chunks_exact(16)
+            .map(|c| {
+                (
+                    u64::from_le_bytes(c[..8].try_into().unwrap()),
+                    u64::from_le_bytes(c[8..].try_into().unwrap()),
+                )
+            })
+            .take_while(|&(a_type, _)| a_type != AT_NULL)
+            .collect();
+        AuxVector { entries }
+    }
+
+    /// Reads the auxiliary vector of the running process from `/proc/self/auxv`.
+    pub fn from_self() -> Result<AuxVector, Error> {
+        Self::read_file("/proc/self/auxv")
+    }
+
+    /// Reads the auxiliary vector of a process from `/proc/<pid>/auxv`.
+    pub fn from_proc(pid: i32) -> Result<AuxVector, Error> {
+        Self::read_file(&format!("/proc/{pid}/auxv"))
+    }
+
+    fn read_file(path: &str) -> Result<AuxVector, Error> {
+        let mut buf = vec![];
+        File::open(path)
+            .map_err(Error::Open)?
+            .read_to_end(&mut buf)
+            .map_err(Error::Read)?;
+        Ok(Self::from_bytes(&buf))
+    }
+
+    /// Reads the auxiliary vector from the initial stack of a program, as found by
+    /// `_start`: the argument count, the argument and environment pointers, each
+    /// terminated by a null pointer, then the auxiliary vector.
+    ///
+    /// # Safety
+    /// `sp` must point to the argument count on the initial stack.
+    pub unsafe fn from_stack(sp: *const u64) -> AuxVector {
+        let mut entries = vec![];
+        let mut entry = stack_entries(sp);
+        while *entry != AT_NULL {
+            entries.push((*entry, *entry.add(1)));
+            entry = entry.add(2);
+        }
+        AuxVector { entries }
+    }
+
+    /// Finds an entry on the initial stack without allocating, for self-relocating
+    /// code that runs before its relocations are applied.
+    ///
+    /// # Safety
+    /// `sp` must point to the argument count on the initial stack.
+    pub unsafe fn find_on_stack(sp: *const u64, a_type: u64) -> Option<u64> {
+        let mut entry = stack_entries(sp);
+        while *entry != AT_NULL {
+            if *entry == a_type {
+                return Some(*entry.add(1));
+            }
+            entry = entry.add(2);
+        }
+        None
+    }
+
+    /// The value of the first entry of a type.
+    pub fn get(&self, a_type: u64) -> Option<u64> {
+        self.entries
+            .iter()
+            .find(|&&(t, _)| t == a_type)
+            .map(|&(_, value)| value)
+    }
+
+    /// The address of the program headers of the executable.
+    pub fn phdr(&self) -> Option<u64> {
+        self.get(AT_PHDR)
+    }
+
+    /// The number of program headers of the executable.
+    pub fn phnum(&self) -> Option<u64> {
+        self.get(AT_PHNUM)
+    }
+
+    /// The address the interpreter is loaded at, 0 for programs without one.
+    pub fn base(&self) -> Option<u64> {
+        self.get(AT_BASE)
+    }
+
+    /// The entry point of the executable.
+    pub fn entry(&self) -> Option<u64> {
+        self.get(AT_ENTRY)
+    }
+
+    /// The hardware capabilities of the machine, passed to AArch64 IFUNC resolvers.
+    pub fn hwcap(&self) -> Option<u64> {
+        self.get(AT_HWCAP)
+    }
+
+    pub fn hwcap2(&self) -> Option<u64> {
+        self.get(AT_HWCAP2)
+    }
+
+    /// The address of 16 random bytes, used to seed stack protectors.
+    pub fn random(&self) -> Option<u64> {
+        self.get(AT_RANDOM)
+    }
+
+    /// The address of the ELF header of the vDSO.
+    pub fn sysinfo_ehdr(&self) -> Option<u64> {
+        self.get(AT_SYSINFO_EHDR)
+    }
+
+    pub fn page_size(&self) -> Option<u64> {
+        self.get(AT_PAGESZ)
+    }
+
+    /// The program headers of the executable, mapped in the running process.
+    ///
+    /// # Safety
+    /// The auxiliary vector must be the one of the running process.
+    pub unsafe fn program_headers(&self) -> Result<&'static [ProgramHeader], Error> {
+        let (phdr, phnum) = match (self.phdr(), self.phnum()) {
+            (Some(phdr), Some(phnum)) => (phdr, phnum),
+            _ => return Err(Error::Message("no AT_PHDR or AT_PHNUM entry".to_string())),
+        };
+        if self
+            .get(AT_PHENT)
+            .is_some_and(|size| size as usize != std::mem::size_of::<ProgramHeader>())
+        {
+            return Err(Error::Message("invalid program header size".to_string()));
+        }
+        Ok(std::slice::from_raw_parts(
+            phdr as *const ProgramHeader,
+            phnum as usize,
+        ))
+    }
+
+    /// The address the executable is loaded at, added to its virtual addresses: the
+    /// address of its program headers less the virtual address in PT_PHDR.
+    ///
+    /// # Safety
+    /// The auxiliary vector must be the one of the running process.
+    pub unsafe fn load_bias(&self) -> Result<usize, Error> {
+        let phdr = self
+            .program_headers()?
+            .iter()
+            .find(|ph| ph.get_type() == PT_PHDR)
+            .ok_or_else(|| Error::Message("no PT_PHDR program header".to_string()))?;
+        Ok((self.phdr().unwrap_or_default() - phdr.get_vaddr()) as usize)
+    }
+
+    /// The dynamic table of the executable, mapped in the running process.
+    ///
+    /// # Safety
+    /// The auxiliary vector must be the one of the running process.
+    pub unsafe fn dynamic_table(&self) -> Result<DynamicTable<'static>, Error> {
+        let hdr = self
+            .program_headers()?
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+            .ok_or_else(|| Error::Message("no PT_DYNAMIC program header".to_string()))?;
+        DynamicTable::parse_segment(self.load_bias()?, hdr)
+    }
+}
+
+/// The first auxiliary vector entry on the initial stack.
+unsafe fn stack_entries(sp: *const u64) -> *const u64 {
+    let argc = *sp as usize;
+    // skip the argument count, the arguments and their null terminator
+    let mut ptr = sp.add(argc + 2);
+    while *ptr != 0 {
+        ptr = ptr.add(1);
+    }
+    ptr.add(1)
+}
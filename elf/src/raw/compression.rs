@@ -0,0 +1,268 @@
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    io::{Read, Write},
+};
+
+use super::{Error, ELFCOMPRESS_ZLIB, ELFCOMPRESS_ZSTD};
+
+static_assertions::const_assert!(std::mem::size_of::<CompressionHeader>() == 24);
+
+/// Magic of the legacy `.zdebug_*` sections, followed by the big-endian uncompressed size.
+pub const ZDEBUG_MAGIC: &[u8; 4] = b"ZLIB";
+
+/// `Elf64_Chdr`, at the start of the contents of a section with SHF_COMPRESSED.
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct CompressionHeader {
+    pub ch_type: u32,
+    pub ch_reserved: u32,
+    /// The size of the uncompressed data
+    pub ch_size: u64,
+    /// The alignment of the uncompressed data
+    pub ch_addralign: u64,
+}
+
+impl CompressionHeader {
+    pub fn parse(buf: &[u8]) -> Result<&CompressionHeader, Error> {
+        if buf.len() < std::mem::size_of::<CompressionHeader>() {
+            return Err(Error::Message(
+                "invalid compression header length".to_string(),
+            ));
+        }
+
+        let ptr = buf.as_ptr() as *const CompressionHeader;
+        Ok(unsafe { &*ptr })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        let ptr = self as *const CompressionHeader as *const u8;
+        unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<CompressionHeader>()) }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionType {
+    Zlib,
+    Zstd,
+}
+
+impl CompressionType {
+    pub fn from_u32(ch_type: u32) -> Option<CompressionType> {
+        match ch_type {
+            ELFCOMPRESS_ZLIB => Some(CompressionType::Zlib),
+            ELFCOMPRESS_ZSTD => Some(CompressionType::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        match self {
+            CompressionType::Zlib => ELFCOMPRESS_ZLIB,
+            CompressionType::Zstd => ELFCOMPRESS_ZSTD,
+        }
+    }
+}
+
+/// Decompresses the contents of a section with SHF_COMPRESSED.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let chdr = CompressionHeader::parse(data)?;
+    let ch_type = chdr.ch_type;
+    let compression = CompressionType::from_u32(ch_type)
+        .ok_or_else(|| Error::Message(format!("unknown compression type {}", ch_type)))?;
+    let payload = &data[std::mem::size_of::<CompressionHeader>()..];
+    decompress_payload(payload, compression, chdr.ch_size)
+}
+
+/// Decompresses the contents of a legacy `.zdebug_*` section.
+pub fn decompress_zdebug(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 12 || &data[..4] != ZDEBUG_MAGIC {
+        return Err(Error::Message("invalid .zdebug section header".to_string()));
+    }
+    let size = u64::from_be_bytes(data[4..12].try_into().unwrap());
+    decompress_payload(&data[12..], CompressionType::Zlib, size)
+}
+
+fn decompress_payload(
+    payload: &[u8],
+    compression: CompressionType,
+    size: u64,
+) -> Result<Vec<u8>, Error> {
+    // the size comes from the file, so it only serves as a capacity hint, and output
+    // is read at most one byte past it so that a payload expanding beyond it fails early
+    let mut out = Vec::with_capacity(size.min(1 << 30) as usize);
+    let limit = size.saturating_add(1);
+    let too_large = || Error::Message(format!("decompressed more than {} bytes", size));
+    match compression {
+        CompressionType::Zlib => {
+            flate2::read::ZlibDecoder::new(payload)
+                .take(limit)
+                .read_to_end(&mut out)
+                .map_err(Error::Read)?;
+        }
+        CompressionType::Zstd => {
+            let mut input = payload;
+            while !input.is_empty() {
+                let mut decoder = ruzstd::decoding::StreamingDecoder::new(&mut input)
+                    .map_err(|e| Error::Message(format!("invalid zstd frame: {}", e)))?;
+                (&mut decoder)
+                    .take(limit - out.len() as u64)
+                    .read_to_end(&mut out)
+                    .map_err(Error::Read)?;
+                if out.len() as u64 > size {
+                    return Err(too_large());
+                }
+            }
+        }
+    }
+
+    if out.len() as u64 > size {
+        return Err(too_large());
+    }
+    if out.len() as u64 != size {
+        return Err(Error::Message(format!(
+            "decompressed {} bytes, expected {}",
+            out.len(),
+            size
+        )));
+    }
+    Ok(out)
+}
+
+/// Compresses section contents for SHF_COMPRESSED, returning the compression header
+/// followed by the compressed data. `addralign` is the alignment of the uncompressed
+/// section; the compressed section should be aligned to 8.
+pub fn compress(data: &[u8], compression: CompressionType, addralign: u64) -> Vec<u8> {
+    let chdr = CompressionHeader {
+        ch_type: compression.to_u32(),
+        ch_reserved: 0,
+        ch_size: data.len() as u64,
+        ch_addralign: addralign,
+    };
+    let mut out = chdr.as_bytes().to_vec();
+    compress_payload(data, compression, &mut out);
+    out
+}
+
+/// Compresses section contents in the legacy `.zdebug_*` format.
+pub fn compress_zdebug(data: &[u8]) -> Vec<u8> {
+    let mut out = ZDEBUG_MAGIC.to_vec();
+    out.extend((data.len() as u64).to_be_bytes());
+    compress_payload(data, CompressionType::Zlib, &mut out);
+    out
+}
+
+fn compress_payload(data: &[u8], compression: CompressionType, out: &mut Vec<u8>) {
+    match compression {
+        CompressionType::Zlib => {
+            let mut encoder = flate2::write::ZlibEncoder::new(out, flate2::Compression::default());
+            // writing to a Vec cannot fail
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap();
+        }
+        CompressionType::Zstd => {
+            ruzstd::encoding::compress(data, out, ruzstd::encoding::CompressionLevel::Fastest)
+        }
+    }
+}
+
+/// Keeps decompressed section contents alive for as long as the storage, so that they
+/// can be borrowed like the contents of the file, e.g. by `DwarfSections`.
+#[derive(Debug, Default)]
+pub struct SectionStorage {
+    buffers: RefCell<Vec<Vec<u8>>>,
+}
+
+impl SectionStorage {
+    pub fn new() -> SectionStorage {
+        SectionStorage::default()
+    }
+
+    pub fn store<'a>(&'a self, data: Cow<'a, [u8]>) -> &'a [u8] {
+        match data {
+            Cow::Borrowed(data) => data,
+            Cow::Owned(data) => {
+                let (ptr, len) = (data.as_ptr(), data.len());
+                // the heap buffer does not move when the Vec is moved, and buffers are
+                // never removed or modified while the storage is borrowed
+                self.buffers.borrow_mut().push(data);
+                unsafe { std::slice::from_raw_parts(ptr, len) }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents() -> Vec<u8> {
+        (0..10_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect()
+    }
+
+    /// Compressed contents whose header claims a different uncompressed size.
+    fn with_size(mut compressed: Vec<u8>, size: u64) -> Vec<u8> {
+        compressed[8..16].copy_from_slice(&size.to_le_bytes());
+        compressed
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = contents();
+        for compression in [CompressionType::Zlib, CompressionType::Zstd] {
+            let compressed = compress(&data, compression, 16);
+            let chdr = CompressionHeader::parse(&compressed).unwrap();
+            let (ch_type, ch_size, ch_addralign) = (chdr.ch_type, chdr.ch_size, chdr.ch_addralign);
+            assert_eq!(ch_type, compression.to_u32());
+            assert_eq!(ch_size, data.len() as u64);
+            assert_eq!(ch_addralign, 16);
+            assert!(compressed.len() < data.len());
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn zdebug_round_trip() {
+        let data = contents();
+        let compressed = compress_zdebug(&data);
+        assert_eq!(&compressed[..4], ZDEBUG_MAGIC);
+        assert_eq!(decompress_zdebug(&compressed).unwrap(), data);
+        assert!(decompress_zdebug(&compressed[..8]).is_err());
+    }
+
+    #[test]
+    fn payload_larger_than_declared_size() {
+        let data = vec![0; 1 << 20];
+        for compression in [CompressionType::Zlib, CompressionType::Zstd] {
+            let compressed = with_size(compress(&data, compression, 1), 100);
+            match decompress(&compressed) {
+                Err(Error::Message(message)) => {
+                    assert_eq!(message, "decompressed more than 100 bytes")
+                }
+                result => panic!("unexpected result {:?}", result.map(|out| out.len())),
+            }
+        }
+
+        let mut compressed = compress_zdebug(&data);
+        compressed[4..12].copy_from_slice(&100u64.to_be_bytes());
+        assert!(decompress_zdebug(&compressed).is_err());
+    }
+
+    #[test]
+    fn payload_smaller_than_declared_size() {
+        let data = contents();
+        for compression in [CompressionType::Zlib, CompressionType::Zstd] {
+            let compressed = with_size(compress(&data, compression, 1), data.len() as u64 + 1);
+            assert!(decompress(&compressed).is_err());
+        }
+    }
+
+    #[test]
+    fn unknown_compression_type() {
+        let mut compressed = compress(&contents(), CompressionType::Zlib, 1);
+        compressed[..4].copy_from_slice(&3u32.to_le_bytes());
+        assert!(decompress(&compressed).is_err());
+    }
+}
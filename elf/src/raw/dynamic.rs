@@ -1,8 +1,14 @@
-use crate::raw::SHT_DYNAMIC;
+use crate::raw::{
+    DF_1_NOW, DF_BIND_NOW, DT_BIND_NOW, DT_FINI, DT_FINI_ARRAY, DT_FINI_ARRAYSZ, DT_FLAGS,
+    DT_FLAGS_1, DT_INIT, DT_INIT_ARRAY, DT_INIT_ARRAYSZ, DT_NEEDED, DT_NULL, DT_PREINIT_ARRAY,
+    DT_PREINIT_ARRAYSZ, DT_RPATH, DT_RUNPATH, DT_SONAME, DT_STRSZ, DT_STRTAB, SHT_DYNAMIC,
+};
 
 use super::{
-    header::{ProgramHeader, SectionHeader},
-    Error, DT_REL, DT_RELA, PT_DYNAMIC,
+    header::{Headers, ProgramHeader, SectionHeader},
+    mapping::AddressMap,
+    string::StringTable,
+    Error, DT_RELA, PT_DYNAMIC,
 };
 
 #[derive(Debug, Clone)]
@@ -33,6 +39,29 @@ impl<'a> DynamicTable<'a> {
         })
     }
 
+    /// Reads dynamic table from the file contents of the PT_DYNAMIC program header.
+    pub fn parse_file_segment<A: AsRef<[u8]>>(
+        buf: &'a A,
+        hdr: &ProgramHeader,
+    ) -> Result<DynamicTable<'a>, Error> {
+        if hdr.get_type() != PT_DYNAMIC {
+            return Err(Error::Message("header not PT_DYNAMIC".to_string()));
+        }
+
+        let offset = hdr.get_offset() as usize;
+        let size = hdr.get_filesz() as usize;
+        let phbuf = offset
+            .checked_add(size)
+            .and_then(|end| buf.as_ref().get(offset..end))
+            .ok_or_else(|| Error::Message("invalid segment offset and size".to_string()))?;
+
+        let ptr = phbuf.as_ptr() as *const Dynamic;
+        let entries = size / std::mem::size_of::<Dynamic>();
+        let entries: &'a [Dynamic] = unsafe { std::slice::from_raw_parts(ptr, entries) };
+
+        Ok(DynamicTable { entries })
+    }
+
     /// Reads dynamic table from the ELF program header in virtual memory.
     /// Precondition: all loadable segments have been mapped into virtual memory already.
     pub fn parse_segment(base_addr: usize, hdr: &ProgramHeader) -> Result<DynamicTable<'a>, Error> {
@@ -66,6 +95,117 @@ impl<'a> DynamicTable<'a> {
     pub fn iter(&'a self) -> impl Iterator<Item = &'a Dynamic> {
         self.entries.iter()
     }
+
+    /// Iterates the entries up to, but not including, the terminating DT_NULL.
+    fn live_entries(&self) -> impl Iterator<Item = &Dynamic> {
+        self.entries.iter().take_while(|d| d.get_tag() != DT_NULL)
+    }
+
+    pub(crate) fn find_value(&self, tag: u64) -> Option<u64> {
+        self.live_entries()
+            .find(|d| d.get_tag() == tag)
+            .map(|d| d.get_value())
+    }
+}
+
+/// High-level view of the dynamic linking information of an object, with
+/// string table offsets resolved and init/fini arrays read.
+#[derive(Debug, Clone, Default)]
+pub struct DynamicInfo<'a> {
+    pub needed: Vec<&'a str>,
+    pub soname: Option<&'a str>,
+    pub rpath: Option<&'a str>,
+    pub runpath: Option<&'a str>,
+    pub init: Option<u64>,
+    pub fini: Option<u64>,
+    /// Whether the legacy DT_BIND_NOW entry is present.
+    pub bind_now: bool,
+    pub preinit_array: Vec<u64>,
+    pub init_array: Vec<u64>,
+    pub fini_array: Vec<u64>,
+    /// Value of DT_FLAGS, see `parsed::dynamic::DynamicFlag`.
+    pub flags: u64,
+    /// Value of DT_FLAGS_1, see `parsed::dynamic::DynamicFlag1`.
+    pub flags_1: u64,
+}
+
+impl<'a> DynamicInfo<'a> {
+    /// Reads dynamic linking information from an ELF file buffer.
+    /// Addresses found in the dynamic table are translated to file offsets
+    /// through the PT_LOAD program headers.
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<DynamicInfo<'a>, Error> {
+        let hdr = elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+            .ok_or_else(|| Error::Message("no PT_DYNAMIC program header".to_string()))?;
+        let dynamic = DynamicTable::parse_file_segment(buf, hdr)?;
+
+        let map = AddressMap::new(buf, elf);
+        Self::resolve(&dynamic, |vaddr, size| map.read_file_backed(vaddr, size))
+    }
+
+    /// Reads dynamic linking information from an image mapped at `base_addr`.
+    /// Precondition: all loadable segments have been mapped into virtual memory already,
+    /// and the dynamic table has not been relocated in place.
+    pub fn from_image(base_addr: usize, dynamic: &DynamicTable) -> Result<DynamicInfo<'a>, Error> {
+        Self::resolve(dynamic, |vaddr, size| {
+            let ptr = (base_addr + vaddr as usize) as *const u8;
+            Ok(unsafe { std::slice::from_raw_parts(ptr, size as usize) })
+        })
+    }
+
+    /// Resolves the entries of the dynamic table, reading `size` bytes at
+    /// a virtual address with `read`.
+    pub(crate) fn resolve<F>(dynamic: &DynamicTable, read: F) -> Result<DynamicInfo<'a>, Error>
+    where
+        F: Fn(u64, u64) -> Result<&'a [u8], Error>,
+    {
+        let strtab = match (dynamic.find_value(DT_STRTAB), dynamic.find_value(DT_STRSZ)) {
+            (Some(addr), Some(size)) => Some(StringTable::from_bytes(read(addr, size)?)?),
+            _ => None,
+        };
+        let get_str = |offset: u64| match &strtab {
+            Some(strtab) => strtab.get_str(offset as usize),
+            None => Err(Error::Message("no dynamic string table".to_string())),
+        };
+        let get_array = |addr_tag: u64, size_tag: u64| -> Result<Vec<u64>, Error> {
+            match (dynamic.find_value(addr_tag), dynamic.find_value(size_tag)) {
+                (Some(addr), Some(size)) if size > 0 => Ok(read(addr, size)?
+                    .chunks_exact(8)
+                    .map(|c| u64::from_le_bytes(c.try_into().unwrap()))
+                    .collect()),
+                _ => Ok(vec![]),
+            }
+        };
+
+        let mut info = DynamicInfo::default();
+        for entry in dynamic.live_entries() {
+            let value = entry.get_value();
+            match entry.get_tag() {
+                DT_NEEDED => info.needed.push(get_str(value)?),
+                DT_SONAME => info.soname = Some(get_str(value)?),
+                DT_RPATH => info.rpath = Some(get_str(value)?),
+                DT_RUNPATH => info.runpath = Some(get_str(value)?),
+                DT_INIT => info.init = Some(value),
+                DT_FINI => info.fini = Some(value),
+                DT_FLAGS => info.flags = value,
+                DT_FLAGS_1 => info.flags_1 = value,
+                DT_BIND_NOW => info.bind_now = true,
+                _ => {}
+            }
+        }
+        info.preinit_array = get_array(DT_PREINIT_ARRAY, DT_PREINIT_ARRAYSZ)?;
+        info.init_array = get_array(DT_INIT_ARRAY, DT_INIT_ARRAYSZ)?;
+        info.fini_array = get_array(DT_FINI_ARRAY, DT_FINI_ARRAYSZ)?;
+
+        Ok(info)
+    }
+
+    /// Whether all symbols must be bound at load time (DT_BIND_NOW, DF_BIND_NOW or DF_1_NOW).
+    pub fn binds_now(&self) -> bool {
+        self.bind_now || self.flags & DF_BIND_NOW != 0 || self.flags_1 & DF_1_NOW != 0
+    }
 }
 
 static_assertions::const_assert!(std::mem::size_of::<Dynamic>() == 16);
@@ -0,0 +1,98 @@
+use super::{
+    header::{Headers, SectionHeader},
+    symbol::SymbolTable,
+    Error, GRP_COMDAT, SHT_GROUP, STT_SECTION,
+};
+
+/// A section group (SHT_GROUP): sections of a relocatable object that the linker
+/// keeps or discards together. C++ compilers put inline functions and template
+/// instantiations in COMDAT groups, of which the linker keeps one copy per signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionGroup<'a> {
+    /// Index of the SHT_GROUP section
+    pub index: usize,
+    /// GRP_* flags
+    pub flags: u32,
+    /// The name of the symbol identifying the group
+    pub signature: &'a str,
+    /// Indices of the member sections
+    pub members: Vec<u32>,
+}
+
+impl<'a> SectionGroup<'a> {
+    pub fn parse<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers<'a>,
+        index: usize,
+    ) -> Result<SectionGroup<'a>, Error> {
+        let hdr = elf
+            .get_section_header_by_index(index)
+            .ok_or_else(|| Error::Message(format!("invalid section index {}", index)))?;
+        if hdr.sh_type != SHT_GROUP {
+            return Err(Error::Message("section not a section group".to_string()));
+        }
+
+        let data = hdr.get_section_buffer(buf)?;
+        if data.len() < 4 || data.len() % 4 != 0 {
+            return Err(Error::Message("invalid section group size".to_string()));
+        }
+        let mut words = data
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes(word.try_into().unwrap()));
+        let flags = words.next().unwrap();
+        let members = words.collect();
+
+        Ok(SectionGroup {
+            index,
+            flags,
+            signature: signature(buf, elf, hdr)?,
+            members,
+        })
+    }
+
+    /// Parses every section group of an object, in section order.
+    pub fn parse_all<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers<'a>,
+    ) -> Result<Vec<SectionGroup<'a>>, Error> {
+        elf.section_headers
+            .iter()
+            .enumerate()
+            .filter(|(_, hdr)| hdr.sh_type == SHT_GROUP)
+            .map(|(index, _)| SectionGroup::parse(buf, elf, index))
+            .collect()
+    }
+
+    pub fn is_comdat(&self) -> bool {
+        self.flags & GRP_COMDAT != 0
+    }
+}
+
+/// The group signature is the name of the symbol at index `sh_info` of the symbol table
+/// `sh_link`. Section symbols have no name, so the name of their section is used instead.
+fn signature<'a, A: AsRef<[u8]>>(
+    buf: &'a A,
+    elf: &Headers<'a>,
+    hdr: &SectionHeader,
+) -> Result<&'a str, Error> {
+    let symtab_hdr = elf
+        .get_section_header_by_index(hdr.sh_link as usize)
+        .ok_or_else(|| Error::Message("invalid section group symbol table".to_string()))?;
+    let symtab = SymbolTable::parse(buf, elf, symtab_hdr)?;
+
+    let index = hdr.sh_info as usize;
+    if index >= symtab.len() {
+        return Err(Error::Message(format!(
+            "invalid section group signature symbol {}",
+            index
+        )));
+    }
+    let symbol = symtab.get_elf_symbol(index);
+    if symbol.info & 0xf == STT_SECTION {
+        let section = elf
+            .get_section_header_by_index(symbol.shndx as usize)
+            .ok_or_else(|| Error::Message("invalid section symbol".to_string()))?;
+        return elf.sh_names.get_str(section.sh_name as usize);
+    }
+    Ok(symbol.name)
+}
@@ -0,0 +1,196 @@
+use super::{
+    dynamic::DynamicTable, header::Headers, mapping::AddressMap, Error, DT_GNU_HASH, DT_HASH,
+    PT_DYNAMIC,
+};
+
+/// The GNU hash function, used by DT_GNU_HASH.
+pub fn gnu_hash(name: &[u8]) -> u32 {
+    name.iter()
+        .fold(5381u32, |h, &c| h.wrapping_mul(33).wrapping_add(c as u32))
+}
+
+/// The System V hash function, used by DT_HASH and to identify symbol versions.
+pub fn sysv_hash(name: &[u8]) -> u32 {
+    let mut h = 0u32;
+    for &c in name {
+        h = (h << 4).wrapping_add(c as u32);
+        let g = h & 0xf0000000;
+        if g != 0 {
+            h ^= g >> 24;
+        }
+        h &= !g;
+    }
+    h
+}
+
+/// A symbol hash table of the dynamic symbol table, DT_GNU_HASH or DT_HASH, used to
+/// look up symbols by name and to count the dynamic symbols.
+#[derive(Debug, Clone)]
+pub enum HashTable<'a> {
+    Gnu {
+        /// The index of the first symbol in the table; earlier symbols are not hashed
+        symoffset: u32,
+        bloom_shift: u32,
+        bloom: &'a [u8],
+        buckets: &'a [u8],
+        chains: &'a [u8],
+    },
+    Sysv {
+        buckets: &'a [u8],
+        chains: &'a [u8],
+    },
+}
+
+impl<'a> HashTable<'a> {
+    /// Reads the hash table of an ELF file through its dynamic table. DT_GNU_HASH
+    /// is preferred over DT_HASH.
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<HashTable<'a>, Error> {
+        let hdr = elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+            .ok_or_else(|| Error::Message("no PT_DYNAMIC program header".to_string()))?;
+        let dynamic = DynamicTable::parse_file_segment(buf, hdr)?;
+
+        let map = AddressMap::new(buf, elf);
+        Self::resolve(&dynamic, |vaddr, size| map.read_file_backed(vaddr, size))
+    }
+
+    /// Reads the hash table of an image mapped at `base_addr`.
+    /// Precondition: all loadable segments have been mapped into virtual memory already,
+    /// and the dynamic table has not been relocated in place.
+    pub fn from_image(base_addr: usize, dynamic: &DynamicTable) -> Result<HashTable<'a>, Error> {
+        Self::resolve(dynamic, |vaddr, size| {
+            let ptr = (base_addr + vaddr as usize) as *const u8;
+            Ok(unsafe { std::slice::from_raw_parts(ptr, size as usize) })
+        })
+    }
+
+    /// Reads the hash table at a virtual address with `read`, returning `size` bytes.
+    pub(crate) fn resolve<F>(dynamic: &DynamicTable, read: F) -> Result<HashTable<'a>, Error>
+    where
+        F: Fn(u64, u64) -> Result<&'a [u8], Error>,
+    {
+        if let Some(addr) = dynamic.find_value(DT_GNU_HASH) {
+            let header = read(addr, 16)?;
+            let nbuckets = read_u32(header, 0);
+            let symoffset = read_u32(header, 1);
+            let bloom_size = read_u32(header, 2) as u64;
+            let bloom_shift = read_u32(header, 3);
+
+            let bloom = read(addr + 16, bloom_size * 8)?;
+            let buckets_addr = addr + 16 + bloom_size * 8;
+            let buckets = read(buckets_addr, nbuckets as u64 * 4)?;
+
+            // the chain of the last bucket ends with the last symbol, marked by its low bit
+            let chains_addr = buckets_addr + nbuckets as u64 * 4;
+            let last = (0..nbuckets as usize)
+                .map(|i| read_u32(buckets, i))
+                .max()
+                .unwrap_or(0);
+            let mut count = 0;
+            if last >= symoffset {
+                count = (last - symoffset) as u64;
+                while read_u32(read(chains_addr + count * 4, 4)?, 0) & 1 == 0 {
+                    count += 1;
+                }
+                count += 1;
+            }
+
+            return Ok(HashTable::Gnu {
+                symoffset,
+                bloom_shift,
+                bloom,
+                buckets,
+                chains: read(chains_addr, count * 4)?,
+            });
+        }
+
+        if let Some(addr) = dynamic.find_value(DT_HASH) {
+            let header = read(addr, 8)?;
+            let nbucket = read_u32(header, 0) as u64;
+            let nchain = read_u32(header, 1) as u64;
+            return Ok(HashTable::Sysv {
+                buckets: read(addr + 8, nbucket * 4)?,
+                chains: read(addr + 8 + nbucket * 4, nchain * 4)?,
+            });
+        }
+
+        Err(Error::Message("no symbol hash table".to_string()))
+    }
+
+    /// The number of symbols in the dynamic symbol table.
+    pub fn symbol_count(&self) -> usize {
+        match self {
+            HashTable::Gnu {
+                symoffset, chains, ..
+            } => *symoffset as usize + chains.len() / 4,
+            HashTable::Sysv { chains, .. } => chains.len() / 4,
+        }
+    }
+
+    /// The indices of the symbols that may be named `name`. Their names must still be
+    /// compared, and a name may have several definitions with different versions.
+    pub fn candidates(&self, name: &[u8]) -> Vec<usize> {
+        let mut candidates = vec![];
+        match self {
+            HashTable::Gnu {
+                symoffset,
+                bloom_shift,
+                bloom,
+                buckets,
+                chains,
+            } => {
+                let hash = gnu_hash(name);
+                let words = bloom.len() / 8;
+                if words == 0 || buckets.is_empty() {
+                    return candidates;
+                }
+                let word = read_u64(bloom, (hash as usize / 64) % words);
+                let mask = (1u64 << (hash % 64)) | (1u64 << ((hash >> bloom_shift) % 64));
+                if word & mask != mask {
+                    return candidates;
+                }
+
+                let mut index = read_u32(buckets, hash as usize % (buckets.len() / 4)) as usize;
+                if index < *symoffset as usize {
+                    return candidates;
+                }
+                while let Some(chain) = chains
+                    .get((index - *symoffset as usize) * 4..)
+                    .filter(|c| c.len() >= 4)
+                    .map(|c| read_u32(c, 0))
+                {
+                    if chain | 1 == hash | 1 {
+                        candidates.push(index);
+                    }
+                    if chain & 1 != 0 {
+                        break;
+                    }
+                    index += 1;
+                }
+            }
+            HashTable::Sysv { buckets, chains } => {
+                if buckets.is_empty() {
+                    return candidates;
+                }
+                let hash = sysv_hash(name) as usize;
+                let mut index = read_u32(buckets, hash % (buckets.len() / 4)) as usize;
+                // index 0 is the undefined symbol, which terminates the chain
+                while index != 0 && index < chains.len() / 4 && candidates.len() < chains.len() {
+                    candidates.push(index);
+                    index = read_u32(chains, index) as usize;
+                }
+            }
+        }
+        candidates
+    }
+}
+
+fn read_u32(buf: &[u8], index: usize) -> u32 {
+    u32::from_le_bytes(buf[index * 4..index * 4 + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], index: usize) -> u64 {
+    u64::from_le_bytes(buf[index * 8..index * 8 + 8].try_into().unwrap())
+}
@@ -1,6 +1,8 @@
-use crate::raw::{ELF_CLASS_64, ELF_DATA_LITTLE, ELF_MAGIC};
+use std::borrow::Cow;
 
-use super::{string::StringTable, Error};
+use crate::raw::{ELF_CLASS_64, ELF_DATA_LITTLE, ELF_MAGIC, SHF_COMPRESSED};
+
+use super::{compression, string::StringTable, Error};
 
 /// A raw representation of the headers in an ELF file.
 /// This includes the ELF headers, the program headers, and
@@ -35,8 +37,13 @@ impl<'a> Headers<'a> {
         let section_headers: &[SectionHeader] = SectionHeader::parse_headers(buf, header)?;
 
         // TODO: validate
-        let sh_names_header = &section_headers[header.e_shstrndx as usize];
-        let sh_names = StringTable::parse(buf, sh_names_header)?;
+        // core files and stripped objects may have no section headers at all
+        let sh_names = match section_headers.get(header.e_shstrndx as usize) {
+            Some(sh_names_header) if header.e_shstrndx != 0 => {
+                StringTable::parse(buf, sh_names_header)?
+            }
+            _ => StringTable::from_bytes(&[])?,
+        };
 
         Ok(Self {
             header,
@@ -59,6 +66,61 @@ impl<'a> Headers<'a> {
             .iter()
             .find(|&hdr| hdr.sh_type == sh_type)
     }
+
+    pub fn find_section_header_by_name(&self, name: &str) -> Option<&SectionHeader> {
+        self.section_headers.iter().find(|&hdr| {
+            self.sh_names
+                .get_str(hdr.sh_name as usize)
+                .is_ok_and(|n| n == name)
+        })
+    }
+
+    /// The contents of a section, decompressed if it has SHF_COMPRESSED or is a
+    /// legacy `.zdebug_*` section. Other sections are borrowed from the file.
+    pub fn get_section_data<'b, A: AsRef<[u8]>>(
+        &self,
+        buf: &'b A,
+        hdr: &SectionHeader,
+    ) -> Result<Cow<'b, [u8]>, Error> {
+        let data = hdr.get_section_buffer(buf)?;
+        if hdr.sh_flags & SHF_COMPRESSED != 0 {
+            return compression::decompress(data).map(Cow::Owned);
+        }
+        let name = self.sh_names.get_str(hdr.sh_name as usize)?;
+        if name.starts_with(".zdebug") {
+            return compression::decompress_zdebug(data).map(Cow::Owned);
+        }
+        Ok(Cow::Borrowed(data))
+    }
+
+    /// Finds a section by name and returns its decompressed contents. A `.debug_*`
+    /// section is also found under its legacy compressed name `.zdebug_*`.
+    pub fn find_section_data_by_name<'b, A: AsRef<[u8]>>(
+        &self,
+        buf: &'b A,
+        name: &str,
+    ) -> Result<Option<Cow<'b, [u8]>>, Error> {
+        let hdr = self.find_section_header_by_name(name).or_else(|| {
+            let zname = format!(
+                ".z{}",
+                name.strip_prefix('.').filter(|n| n.starts_with("debug"))?
+            );
+            self.find_section_header_by_name(&zname)
+        });
+        match hdr {
+            Some(hdr) => self.get_section_data(buf, hdr).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether a section's contents must be decompressed before use.
+    pub fn is_section_compressed(&self, hdr: &SectionHeader) -> bool {
+        hdr.sh_flags & SHF_COMPRESSED != 0
+            || self
+                .sh_names
+                .get_str(hdr.sh_name as usize)
+                .is_ok_and(|name| name.starts_with(".zdebug"))
+    }
 }
 
 static_assertions::const_assert!(std::mem::size_of::<FileHeader>() == 64);
@@ -86,6 +148,11 @@ pub struct FileHeader {
 }
 
 impl FileHeader {
+    pub fn as_bytes(&self) -> &[u8] {
+        let ptr = self as *const FileHeader as *const u8;
+        unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<FileHeader>()) }
+    }
+
     pub fn parse<'a, A: AsRef<[u8]>>(buf: &'a A) -> Result<&'a FileHeader, Error> {
         let buf = buf.as_ref();
         if buf.len() < std::mem::size_of::<FileHeader>() {
@@ -113,14 +180,14 @@ pub struct Ident {
 #[derive(Debug, Clone)]
 #[repr(C, packed)]
 pub struct ProgramHeader {
-    p_type: u32,
-    p_flags: u32,
-    p_offset: u64,
-    p_vaddr: u64,
-    p_paddr: u64,
-    p_filesz: u64,
-    p_memsz: u64,
-    p_align: u64,
+    pub p_type: u32,
+    pub p_flags: u32,
+    pub p_offset: u64,
+    pub p_vaddr: u64,
+    pub p_paddr: u64,
+    pub p_filesz: u64,
+    pub p_memsz: u64,
+    pub p_align: u64,
 }
 
 impl ProgramHeader {
@@ -128,6 +195,10 @@ impl ProgramHeader {
         self.p_vaddr
     }
 
+    pub fn get_paddr(&self) -> u64 {
+        self.p_paddr
+    }
+
     pub fn get_memsz(&self) -> u64 {
         self.p_memsz
     }
@@ -147,6 +218,20 @@ impl ProgramHeader {
     pub fn get_type(&self) -> u32 {
         self.p_type
     }
+
+    pub fn get_align(&self) -> u64 {
+        self.p_align
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        let ptr = self as *const ProgramHeader as *const u8;
+        unsafe { std::slice::from_raw_parts(ptr, std::mem::size_of::<ProgramHeader>()) }
+    }
+
+    /// Whether the memory image of the segment contains the virtual address.
+    pub fn contains_address(&self, vaddr: u64) -> bool {
+        vaddr >= self.p_vaddr && vaddr - self.p_vaddr < self.p_memsz
+    }
 }
 
 impl ProgramHeader {
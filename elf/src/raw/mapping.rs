@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use super::{
+    header::{Headers, ProgramHeader},
+    Error, PT_LOAD,
+};
+
+/// Translates between virtual addresses and file offsets of an ELF file
+/// using its loadable segments, as the program loader would map them.
+#[derive(Debug, Clone)]
+pub struct AddressMap<'a> {
+    buf: &'a [u8],
+    segments: Vec<&'a ProgramHeader>,
+}
+
+impl<'a> AddressMap<'a> {
+    pub fn new<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> AddressMap<'a> {
+        Self::from_program_headers(buf, elf.program_headers)
+    }
+
+    pub fn from_program_headers<A: AsRef<[u8]>>(
+        buf: &'a A,
+        program_headers: &'a [ProgramHeader],
+    ) -> AddressMap<'a> {
+        let mut segments: Vec<&'a ProgramHeader> = program_headers
+            .iter()
+            .filter(|ph| ph.get_type() == PT_LOAD)
+            .collect();
+        segments.sort_by_key(|ph| ph.get_vaddr());
+
+        AddressMap {
+            buf: buf.as_ref(),
+            segments,
+        }
+    }
+
+    /// Finds the loadable segment whose memory image contains `vaddr`.
+    pub fn find_segment(&self, vaddr: u64) -> Option<&'a ProgramHeader> {
+        self.segments
+            .iter()
+            .find(|ph| ph.contains_address(vaddr))
+            .copied()
+    }
+
+    /// Translates a virtual address to the file offset backing it.
+    /// Fails for addresses outside any segment, or in the zero-filled part
+    /// of a segment (such as `.bss`) that has no file contents.
+    pub fn vaddr_to_offset(&self, vaddr: u64) -> Result<u64, Error> {
+        let ph = self
+            .find_segment(vaddr)
+            .ok_or(Error::UnmappedAddress(vaddr))?;
+        let delta = vaddr - ph.get_vaddr();
+        if delta >= ph.get_filesz() {
+            return Err(Error::Message(format!(
+                "address 0x{vaddr:x} has no file contents"
+            )));
+        }
+
+        Ok(ph.get_offset() + delta)
+    }
+
+    /// Translates a file offset to the virtual address it is loaded at.
+    pub fn offset_to_vaddr(&self, offset: u64) -> Result<u64, Error> {
+        self.segments
+            .iter()
+            .find(|ph| offset >= ph.get_offset() && offset - ph.get_offset() < ph.get_filesz())
+            .map(|ph| ph.get_vaddr() + (offset - ph.get_offset()))
+            .ok_or_else(|| Error::Message(format!("offset 0x{offset:x} is not loaded")))
+    }
+
+    /// Reads `size` bytes at a virtual address. Memory beyond the file size of
+    /// a segment reads as zeros, in which case the returned buffer is owned.
+    /// The range must lie within a single segment.
+    pub fn read(&self, vaddr: u64, size: u64) -> Result<Cow<'a, [u8]>, Error> {
+        let ph = self.segment_for_range(vaddr, size)?;
+        let delta = vaddr - ph.get_vaddr();
+        let filesz = ph.get_filesz();
+
+        if delta + size <= filesz {
+            return self
+                .file_bytes(ph.get_offset() + delta, size)
+                .map(Cow::Borrowed);
+        }
+
+        let mut bytes = vec![0; size as usize];
+        if delta < filesz {
+            let backed = filesz - delta;
+            let file = self.file_bytes(ph.get_offset() + delta, backed)?;
+            bytes[..backed as usize].copy_from_slice(file);
+        }
+        Ok(Cow::Owned(bytes))
+    }
+
+    /// Reads `size` bytes at a virtual address, which must be entirely backed by file contents.
+    pub fn read_file_backed(&self, vaddr: u64, size: u64) -> Result<&'a [u8], Error> {
+        let ph = self.segment_for_range(vaddr, size)?;
+        let delta = vaddr - ph.get_vaddr();
+        if delta + size > ph.get_filesz() {
+            return Err(Error::Message(format!(
+                "address 0x{vaddr:x} has no file contents"
+            )));
+        }
+
+        self.file_bytes(ph.get_offset() + delta, size)
+    }
+
+    pub fn read_u64(&self, vaddr: u64) -> Result<u64, Error> {
+        let bytes = self.read(vaddr, 8)?;
+        Ok(u64::from_le_bytes(bytes.as_ref().try_into().unwrap()))
+    }
+
+    fn segment_for_range(&self, vaddr: u64, size: u64) -> Result<&'a ProgramHeader, Error> {
+        let ph = self
+            .find_segment(vaddr)
+            .ok_or(Error::UnmappedAddress(vaddr))?;
+        let end = vaddr
+            .checked_add(size)
+            .ok_or(Error::UnmappedAddress(vaddr))?;
+        // find_segment guarantees vaddr >= p_vaddr, so this cannot underflow
+        if end - ph.get_vaddr() > ph.get_memsz() {
+            return Err(Error::UnmappedAddress(
+                ph.get_vaddr().saturating_add(ph.get_memsz()),
+            ));
+        }
+        Ok(ph)
+    }
+
+    fn file_bytes(&self, offset: u64, size: u64) -> Result<&'a [u8], Error> {
+        let offset = offset as usize;
+        offset
+            .checked_add(size as usize)
+            .and_then(|end| self.buf.get(offset..end))
+            .ok_or_else(|| Error::Message("invalid segment offset and size".to_string()))
+    }
+}
@@ -1,8 +1,15 @@
+pub mod auxv;
+pub mod compression;
 pub mod dynamic;
+pub mod group;
+pub mod hash;
 pub mod header;
+pub mod mapping;
+pub mod note;
 pub mod relocation;
 pub mod string;
 pub mod symbol;
+pub mod version;
 
 pub const ELF_MAGIC: [u8; 4] = [0x7f, 0x45, 0x4c, 0x46];
 
@@ -12,31 +19,328 @@ pub const ELF_CLASS_64: u8 = 0x02;
 pub const ELF_DATA_LITTLE: u8 = 0x01;
 pub const ELF_DATA_BIG: u8 = 0x02;
 
+pub const ET_NONE: u16 = 0;
+pub const ET_REL: u16 = 1;
+pub const ET_EXEC: u16 = 2;
+pub const ET_DYN: u16 = 3;
+pub const ET_CORE: u16 = 4;
+
+pub const EM_NONE: u16 = 0;
+pub const EM_386: u16 = 3;
+pub const EM_MIPS: u16 = 8;
+pub const EM_ARM: u16 = 40;
+pub const EM_X86_64: u16 = 62;
+pub const EM_AARCH64: u16 = 183;
+pub const EM_RISCV: u16 = 243;
+
 pub const SHT_NULL: u32 = 0x00;
 pub const SHT_PROGBITS: u32 = 0x01;
 pub const SHT_SYMTAB: u32 = 0x02;
 pub const SHT_STRTAB: u32 = 0x03;
 pub const SHT_RELA: u32 = 0x04;
+pub const SHT_HASH: u32 = 0x05;
 pub const SHT_DYNAMIC: u32 = 0x06;
+pub const SHT_NOTE: u32 = 0x07;
+pub const SHT_NOBITS: u32 = 0x08;
+pub const SHT_REL: u32 = 0x09;
+pub const SHT_SHLIB: u32 = 0x0A;
 pub const SHT_DYNSYM: u32 = 0x0B;
+pub const SHT_INIT_ARRAY: u32 = 0x0E;
+pub const SHT_FINI_ARRAY: u32 = 0x0F;
+pub const SHT_PREINIT_ARRAY: u32 = 0x10;
+pub const SHT_GROUP: u32 = 0x11;
+pub const SHT_SYMTAB_SHNDX: u32 = 0x12;
+pub const SHT_RELR: u32 = 0x13;
+pub const SHT_LOOS: u32 = 0x60000000;
+pub const SHT_ANDROID_REL: u32 = 0x60000001;
+pub const SHT_ANDROID_RELA: u32 = 0x60000002;
+pub const SHT_LLVM_ODRTAB: u32 = 0x6fff4c00;
+pub const SHT_LLVM_LINKER_OPTIONS: u32 = 0x6fff4c01;
+pub const SHT_LLVM_ADDRSIG: u32 = 0x6fff4c03;
+pub const SHT_LLVM_DEPENDENT_LIBRARIES: u32 = 0x6fff4c04;
+pub const SHT_LLVM_SYMPART: u32 = 0x6fff4c05;
+pub const SHT_LLVM_PART_EHDR: u32 = 0x6fff4c06;
+pub const SHT_LLVM_PART_PHDR: u32 = 0x6fff4c07;
+pub const SHT_LLVM_CALL_GRAPH_PROFILE: u32 = 0x6fff4c09;
+pub const SHT_LLVM_BB_ADDR_MAP: u32 = 0x6fff4c0a;
+pub const SHT_LLVM_OFFLOADING: u32 = 0x6fff4c0b;
+pub const SHT_LLVM_LTO: u32 = 0x6fff4c0c;
+pub const SHT_ANDROID_RELR: u32 = 0x6fffff00;
+pub const SHT_GNU_ATTRIBUTES: u32 = 0x6ffffff5;
+pub const SHT_GNU_HASH: u32 = 0x6ffffff6;
+pub const SHT_GNU_LIBLIST: u32 = 0x6ffffff7;
+pub const SHT_CHECKSUM: u32 = 0x6ffffff8;
+pub const SHT_GNU_VERDEF: u32 = 0x6ffffffd;
+pub const SHT_GNU_VERNEED: u32 = 0x6ffffffe;
+pub const SHT_GNU_VERSYM: u32 = 0x6fffffff;
+pub const SHT_HIOS: u32 = 0x6fffffff;
+pub const SHT_LOPROC: u32 = 0x70000000;
+pub const SHT_ARM_EXIDX: u32 = 0x70000001;
+pub const SHT_ARM_PREEMPTMAP: u32 = 0x70000002;
+pub const SHT_ARM_ATTRIBUTES: u32 = 0x70000003;
+pub const SHT_ARM_DEBUGOVERLAY: u32 = 0x70000004;
+pub const SHT_ARM_OVERLAYSECTION: u32 = 0x70000005;
+pub const SHT_X86_64_UNWIND: u32 = 0x70000001;
+pub const SHT_AARCH64_ATTRIBUTES: u32 = 0x70000003;
+pub const SHT_RISCV_ATTRIBUTES: u32 = 0x70000003;
+pub const SHT_HIPROC: u32 = 0x7fffffff;
+pub const SHT_LOUSER: u32 = 0x80000000;
+pub const SHT_HIUSER: u32 = 0xffffffff;
+
+pub const SHF_WRITE: u64 = 0x1;
+pub const SHF_ALLOC: u64 = 0x2;
+pub const SHF_EXECINSTR: u64 = 0x4;
+pub const SHF_MERGE: u64 = 0x10;
+pub const SHF_STRINGS: u64 = 0x20;
+pub const SHF_INFO_LINK: u64 = 0x40;
+pub const SHF_LINK_ORDER: u64 = 0x80;
+pub const SHF_OS_NONCONFORMING: u64 = 0x100;
+pub const SHF_GROUP: u64 = 0x200;
+pub const SHF_TLS: u64 = 0x400;
+pub const SHF_COMPRESSED: u64 = 0x800;
+pub const SHF_GNU_RETAIN: u64 = 0x200000;
+pub const SHF_MASKOS: u64 = 0x0ff00000;
+pub const SHF_MASKPROC: u64 = 0xf0000000;
+pub const SHF_EXCLUDE: u64 = 0x80000000;
+
+pub const GRP_COMDAT: u32 = 0x1;
+pub const GRP_MASKOS: u32 = 0x0ff00000;
+pub const GRP_MASKPROC: u32 = 0xf0000000;
+
+pub const ELFCOMPRESS_ZLIB: u32 = 1;
+pub const ELFCOMPRESS_ZSTD: u32 = 2;
 
+pub const SHN_UNDEF: u16 = 0;
+pub const SHN_ABS: u16 = 0xfff1;
+pub const SHN_COMMON: u16 = 0xfff2;
+
+pub const STB_LOCAL: u8 = 0;
+pub const STB_GLOBAL: u8 = 1;
+pub const STB_WEAK: u8 = 2;
+pub const STB_GNU_UNIQUE: u8 = 10;
+
+pub const STT_NOTYPE: u8 = 0;
+pub const STT_OBJECT: u8 = 1;
+pub const STT_FUNC: u8 = 2;
+pub const STT_SECTION: u8 = 3;
+pub const STT_FILE: u8 = 4;
+pub const STT_COMMON: u8 = 5;
+pub const STT_TLS: u8 = 6;
+pub const STT_GNU_IFUNC: u8 = 10;
+
+pub const VER_NDX_LOCAL: u16 = 0;
+pub const VER_NDX_GLOBAL: u16 = 1;
+pub const VERSYM_HIDDEN: u16 = 0x8000;
+pub const VERSYM_VERSION: u16 = 0x7fff;
+pub const VER_FLG_BASE: u16 = 0x1;
+pub const VER_FLG_WEAK: u16 = 0x2;
+
+pub const PT_NULL: u32 = 0x00;
+pub const PT_LOAD: u32 = 0x01;
 pub const PT_DYNAMIC: u32 = 0x02;
+pub const PT_INTERP: u32 = 0x03;
+pub const PT_NOTE: u32 = 0x04;
+pub const PT_SHLIB: u32 = 0x05;
+pub const PT_PHDR: u32 = 0x06;
+pub const PT_TLS: u32 = 0x07;
+pub const PT_LOOS: u32 = 0x60000000;
+pub const PT_GNU_EH_FRAME: u32 = 0x6474e550;
+pub const PT_GNU_STACK: u32 = 0x6474e551;
+pub const PT_GNU_RELRO: u32 = 0x6474e552;
+pub const PT_GNU_PROPERTY: u32 = 0x6474e553;
+pub const PT_GNU_SFRAME: u32 = 0x6474e554;
+pub const PT_OPENBSD_MUTABLE: u32 = 0x65a3dbe5;
+pub const PT_OPENBSD_RANDOMIZE: u32 = 0x65a3dbe6;
+pub const PT_OPENBSD_WXNEEDED: u32 = 0x65a3dbe7;
+pub const PT_OPENBSD_NOBTCFI: u32 = 0x65a3dbe8;
+pub const PT_OPENBSD_SYSCALLS: u32 = 0x65a3dbe9;
+pub const PT_OPENBSD_BOOTDATA: u32 = 0x65a41be6;
+pub const PT_HIOS: u32 = 0x6fffffff;
+pub const PT_LOPROC: u32 = 0x70000000;
+pub const PT_ARM_ARCHEXT: u32 = 0x70000000;
+pub const PT_ARM_EXIDX: u32 = 0x70000001;
+pub const PT_AARCH64_MEMTAG_MTE: u32 = 0x70000002;
+pub const PT_MIPS_REGINFO: u32 = 0x70000000;
+pub const PT_MIPS_RTPROC: u32 = 0x70000001;
+pub const PT_MIPS_OPTIONS: u32 = 0x70000002;
+pub const PT_MIPS_ABIFLAGS: u32 = 0x70000003;
+pub const PT_RISCV_ATTRIBUTES: u32 = 0x70000003;
+pub const PT_HIPROC: u32 = 0x7fffffff;
+
+pub const PF_X: u32 = 0x1;
+pub const PF_W: u32 = 0x2;
+pub const PF_R: u32 = 0x4;
+pub const PF_MASKOS: u32 = 0x0ff00000;
+pub const PF_MASKPROC: u32 = 0xf0000000;
 
 pub const DT_NULL: u64 = 0;
 pub const DT_NEEDED: u64 = 1;
-pub const DT_PTRRELSZ: u64 = 2;
+pub const DT_PLTRELSZ: u64 = 2;
 pub const DT_PLTGOT: u64 = 3;
+pub const DT_HASH: u64 = 4;
 pub const DT_STRTAB: u64 = 5;
 pub const DT_SYMTAB: u64 = 6;
 pub const DT_RELA: u64 = 7;
 pub const DT_RELASZ: u64 = 8;
 pub const DT_RELAENT: u64 = 9;
+pub const DT_STRSZ: u64 = 10;
+pub const DT_SYMENT: u64 = 11;
 pub const DT_INIT: u64 = 12;
+pub const DT_FINI: u64 = 13;
+pub const DT_SONAME: u64 = 14;
+pub const DT_RPATH: u64 = 15;
+pub const DT_SYMBOLIC: u64 = 16;
 pub const DT_REL: u64 = 17;
 pub const DT_RELSZ: u64 = 18;
 pub const DT_RELENT: u64 = 19;
+pub const DT_PLTREL: u64 = 20;
+pub const DT_DEBUG: u64 = 21;
+pub const DT_TEXTREL: u64 = 22;
+pub const DT_JMPREL: u64 = 23;
+pub const DT_BIND_NOW: u64 = 24;
+pub const DT_INIT_ARRAY: u64 = 25;
+pub const DT_FINI_ARRAY: u64 = 26;
+pub const DT_INIT_ARRAYSZ: u64 = 27;
+pub const DT_FINI_ARRAYSZ: u64 = 28;
+pub const DT_RUNPATH: u64 = 29;
+pub const DT_FLAGS: u64 = 30;
+pub const DT_PREINIT_ARRAY: u64 = 32;
+pub const DT_PREINIT_ARRAYSZ: u64 = 33;
+pub const DT_SYMTAB_SHNDX: u64 = 34;
+pub const DT_RELRSZ: u64 = 35;
+pub const DT_RELR: u64 = 36;
+pub const DT_RELRENT: u64 = 37;
+pub const DT_ANDROID_REL: u64 = 0x6000000f;
+pub const DT_ANDROID_RELSZ: u64 = 0x60000010;
+pub const DT_ANDROID_RELA: u64 = 0x60000011;
+pub const DT_ANDROID_RELASZ: u64 = 0x60000012;
+pub const DT_ANDROID_RELR: u64 = 0x6fffe000;
+pub const DT_ANDROID_RELRSZ: u64 = 0x6fffe001;
+pub const DT_ANDROID_RELRENT: u64 = 0x6fffe003;
+pub const DT_GNU_PRELINKED: u64 = 0x6ffffdf5;
+pub const DT_GNU_CONFLICTSZ: u64 = 0x6ffffdf6;
+pub const DT_GNU_LIBLISTSZ: u64 = 0x6ffffdf7;
+pub const DT_CHECKSUM: u64 = 0x6ffffdf8;
+pub const DT_PLTPADSZ: u64 = 0x6ffffdf9;
+pub const DT_MOVEENT: u64 = 0x6ffffdfa;
+pub const DT_MOVESZ: u64 = 0x6ffffdfb;
+pub const DT_FEATURE_1: u64 = 0x6ffffdfc;
+pub const DT_POSFLAG_1: u64 = 0x6ffffdfd;
+pub const DT_SYMINSZ: u64 = 0x6ffffdfe;
+pub const DT_SYMINENT: u64 = 0x6ffffdff;
+pub const DT_GNU_HASH: u64 = 0x6ffffef5;
+pub const DT_TLSDESC_PLT: u64 = 0x6ffffef6;
+pub const DT_TLSDESC_GOT: u64 = 0x6ffffef7;
+pub const DT_GNU_CONFLICT: u64 = 0x6ffffef8;
+pub const DT_GNU_LIBLIST: u64 = 0x6ffffef9;
+pub const DT_CONFIG: u64 = 0x6ffffefa;
+pub const DT_DEPAUDIT: u64 = 0x6ffffefb;
+pub const DT_AUDIT: u64 = 0x6ffffefc;
+pub const DT_PLTPAD: u64 = 0x6ffffefd;
+pub const DT_MOVETAB: u64 = 0x6ffffefe;
+pub const DT_SYMINFO: u64 = 0x6ffffeff;
+pub const DT_VERSYM: u64 = 0x6ffffff0;
+pub const DT_RELACOUNT: u64 = 0x6ffffff9;
+pub const DT_RELCOUNT: u64 = 0x6ffffffa;
+pub const DT_FLAGS_1: u64 = 0x6ffffffb;
+pub const DT_VERDEF: u64 = 0x6ffffffc;
+pub const DT_VERDEFNUM: u64 = 0x6ffffffd;
+pub const DT_VERNEED: u64 = 0x6ffffffe;
+pub const DT_VERNEEDNUM: u64 = 0x6fffffff;
+pub const DT_AUXILIARY: u64 = 0x7ffffffd;
+pub const DT_FILTER: u64 = 0x7fffffff;
 
+pub const DF_ORIGIN: u64 = 0x1;
+pub const DF_SYMBOLIC: u64 = 0x2;
+pub const DF_TEXTREL: u64 = 0x4;
+pub const DF_BIND_NOW: u64 = 0x8;
+pub const DF_STATIC_TLS: u64 = 0x10;
+
+pub const DF_1_NOW: u64 = 0x1;
+pub const DF_1_GLOBAL: u64 = 0x2;
+pub const DF_1_NODELETE: u64 = 0x8;
+pub const DF_1_INITFIRST: u64 = 0x20;
+pub const DF_1_NOOPEN: u64 = 0x40;
+pub const DF_1_ORIGIN: u64 = 0x80;
+pub const DF_1_PIE: u64 = 0x08000000;
+
+pub const NT_GNU_ABI_TAG: u32 = 1;
+pub const NT_GNU_HWCAP: u32 = 2;
+pub const NT_GNU_BUILD_ID: u32 = 3;
+pub const NT_GNU_GOLD_VERSION: u32 = 4;
+pub const NT_GNU_PROPERTY_TYPE_0: u32 = 5;
+pub const NT_FDO_PACKAGING_METADATA: u32 = 0xcafe1a7e;
+
+pub const NT_PRSTATUS: u32 = 1;
+pub const NT_FPREGSET: u32 = 2;
+pub const NT_PRPSINFO: u32 = 3;
+pub const NT_TASKSTRUCT: u32 = 4;
+pub const NT_AUXV: u32 = 6;
+pub const NT_SIGINFO: u32 = 0x53494749;
+pub const NT_FILE: u32 = 0x46494c45;
+pub const NT_X86_XSTATE: u32 = 0x202;
+pub const NT_ARM_TLS: u32 = 0x401;
+pub const NT_ARM_HW_BREAK: u32 = 0x402;
+pub const NT_ARM_HW_WATCH: u32 = 0x403;
+pub const NT_ARM_SYSTEM_CALL: u32 = 0x404;
+pub const NT_ARM_SVE: u32 = 0x405;
+pub const NT_ARM_PAC_MASK: u32 = 0x406;
+
+pub const NT_GO_PKGLIST: u32 = 1;
+pub const NT_GO_ABIHASH: u32 = 2;
+pub const NT_GO_DEPS: u32 = 3;
+pub const NT_GO_BUILDID: u32 = 4;
+
+pub const NT_ANDROID_TYPE_IDENT: u32 = 1;
+pub const NT_ANDROID_TYPE_KUSER: u32 = 3;
+pub const NT_ANDROID_TYPE_MEMTAG: u32 = 4;
+
+pub const GNU_PROPERTY_STACK_SIZE: u32 = 1;
+pub const GNU_PROPERTY_NO_COPY_ON_PROTECTED: u32 = 2;
+pub const GNU_PROPERTY_AARCH64_FEATURE_1_AND: u32 = 0xc0000000;
+pub const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc0000002;
+pub const GNU_PROPERTY_X86_FEATURE_2_NEEDED: u32 = 0xc0008001;
+pub const GNU_PROPERTY_X86_ISA_1_NEEDED: u32 = 0xc0008002;
+pub const GNU_PROPERTY_X86_FEATURE_2_USED: u32 = 0xc0010001;
+pub const GNU_PROPERTY_X86_ISA_1_USED: u32 = 0xc0010002;
+
+pub const R_X86_64_NONE: u32 = 0;
+pub const R_X86_64_64: u32 = 1;
+pub const R_X86_64_COPY: u32 = 5;
+pub const R_X86_64_GLOB_DAT: u32 = 6;
+pub const R_X86_64_JUMP_SLOT: u32 = 7;
+pub const R_X86_64_RELATIVE: u32 = 8;
+pub const R_X86_64_DTPMOD64: u32 = 16;
+pub const R_X86_64_DTPOFF64: u32 = 17;
+pub const R_X86_64_TPOFF64: u32 = 18;
+pub const R_X86_64_IRELATIVE: u32 = 37;
+
+pub const R_AARCH64_NONE: u32 = 0;
+pub const R_AARCH64_ABS64: u32 = 0x101;
+pub const R_AARCH64_COPY: u32 = 0x400;
+pub const R_AARCH64_GLOB_DAT: u32 = 0x401;
+pub const R_AARCH64_JUMP_SLOT: u32 = 0x402;
 pub const R_AARCH64_RELATIV: u32 = 0x403;
+pub const R_AARCH64_TLS_DTPMOD64: u32 = 0x404;
+pub const R_AARCH64_TLS_DTPREL64: u32 = 0x405;
+pub const R_AARCH64_TLS_TPREL64: u32 = 0x406;
+pub const R_AARCH64_IRELATIVE: u32 = 0x408;
+
+pub const R_RISCV_TLS_DTPMOD64: u32 = 7;
+pub const R_RISCV_TLS_DTPREL64: u32 = 9;
+pub const R_RISCV_TLS_TPREL64: u32 = 11;
+
+pub const AT_NULL: u64 = 0;
+pub const AT_PHDR: u64 = 3;
+pub const AT_PHENT: u64 = 4;
+pub const AT_PHNUM: u64 = 5;
+pub const AT_PAGESZ: u64 = 6;
+pub const AT_BASE: u64 = 7;
+pub const AT_ENTRY: u64 = 9;
+pub const AT_HWCAP: u64 = 16;
+pub const AT_RANDOM: u64 = 25;
+pub const AT_HWCAP2: u64 = 26;
+pub const AT_SYSINFO_EHDR: u64 = 33;
 
 pub type SymbolTableIndex = u32;
 
@@ -46,6 +350,8 @@ pub enum Error {
     Open(std::io::Error),
     #[error("read error: {0}")]
     Read(std::io::Error),
+    #[error("write error: {0}")]
+    Write(std::io::Error),
     #[error("invalid magic number")]
     InvalidMagicNumber,
     #[error("unexpected class")]
@@ -54,6 +360,8 @@ pub enum Error {
     InvalidClass,
     #[error("invalid endianness")]
     InvalidEndianness,
+    #[error("address 0x{0:x} is not mapped")]
+    UnmappedAddress(u64),
     #[error("error: {0}")]
     Message(String),
 }
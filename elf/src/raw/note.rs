@@ -0,0 +1,158 @@
+use super::{
+    header::{Headers, ProgramHeader, SectionHeader},
+    Error, NT_GNU_BUILD_ID, PT_NOTE, SHT_NOTE,
+};
+
+/// Iterator over the entries of a note section or segment.
+/// Notes are laid out back to back, each padded to the alignment of the
+/// containing section or segment (4 bytes for most notes, 8 for GNU properties).
+#[derive(Debug, Clone)]
+pub struct NoteIterator<'a> {
+    buf: &'a [u8],
+    align: usize,
+}
+
+impl<'a> NoteIterator<'a> {
+    pub fn new(buf: &'a [u8], align: u64) -> Result<NoteIterator<'a>, Error> {
+        let align = match align {
+            0..=4 => 4,
+            8 => 8,
+            _ => return Err(Error::Message(format!("invalid note alignment {align}"))),
+        };
+
+        Ok(NoteIterator { buf, align })
+    }
+
+    pub fn parse_section<A: AsRef<[u8]>>(
+        buf: &'a A,
+        hdr: &SectionHeader,
+    ) -> Result<NoteIterator<'a>, Error> {
+        if hdr.sh_type != SHT_NOTE {
+            return Err(Error::Message("section not a note section".to_string()));
+        }
+
+        let shbuf = hdr.get_section_buffer(buf)?;
+        Self::new(shbuf, hdr.sh_addralign)
+    }
+
+    /// Reads notes from the file contents of a PT_NOTE program header.
+    pub fn parse_file_segment<A: AsRef<[u8]>>(
+        buf: &'a A,
+        hdr: &ProgramHeader,
+    ) -> Result<NoteIterator<'a>, Error> {
+        if hdr.get_type() != PT_NOTE {
+            return Err(Error::Message("header not PT_NOTE".to_string()));
+        }
+
+        let offset = hdr.get_offset() as usize;
+        let size = hdr.get_filesz() as usize;
+        let phbuf = offset
+            .checked_add(size)
+            .and_then(|end| buf.as_ref().get(offset..end))
+            .ok_or_else(|| Error::Message("invalid segment offset and size".to_string()))?;
+        Self::new(phbuf, hdr.get_align())
+    }
+
+    fn read_word(&self, offset: usize) -> u32 {
+        u32::from_le_bytes(self.buf[offset..(offset + 4)].try_into().unwrap())
+    }
+}
+
+impl<'a> Iterator for NoteIterator<'a> {
+    type Item = Result<Note<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        if self.buf.len() < NOTE_HEADER_SIZE {
+            self.buf = &[];
+            return Some(Err(Error::Message("truncated note header".to_string())));
+        }
+
+        let namesz = self.read_word(0) as usize;
+        let descsz = self.read_word(4) as usize;
+        let n_type = self.read_word(8);
+
+        let desc_offset = align_up(NOTE_HEADER_SIZE + namesz, self.align);
+        let desc_end = desc_offset + descsz;
+        if desc_end > self.buf.len() {
+            self.buf = &[];
+            return Some(Err(Error::Message("truncated note".to_string())));
+        }
+
+        // the name is NUL-terminated, and namesz includes the terminator
+        let name = &self.buf[NOTE_HEADER_SIZE..(NOTE_HEADER_SIZE + namesz)];
+        let name = name.strip_suffix(&[0]).unwrap_or(name);
+        let desc = &self.buf[desc_offset..desc_end];
+
+        let next = align_up(desc_end, self.align).min(self.buf.len());
+        self.buf = &self.buf[next..];
+
+        Some(Ok(Note { name, n_type, desc }))
+    }
+}
+
+const NOTE_HEADER_SIZE: usize = 12;
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+/// A single note, borrowing its name and descriptor from the file.
+#[derive(Debug, Clone, Copy)]
+pub struct Note<'a> {
+    /// The owner of the note, without the NUL terminator.
+    pub name: &'a [u8],
+    pub n_type: u32,
+    pub desc: &'a [u8],
+}
+
+impl<'a> Note<'a> {
+    pub fn name_str(&self) -> Option<&'a str> {
+        std::str::from_utf8(self.name).ok()
+    }
+
+    pub fn is_gnu(&self) -> bool {
+        self.name == b"GNU"
+    }
+}
+
+/// Iterates over the notes of every PT_NOTE segment, or of every SHT_NOTE
+/// section if the object has no note segments (e.g. relocatable objects).
+pub fn note_iterators<'a, A: AsRef<[u8]>>(
+    buf: &'a A,
+    elf: &Headers<'a>,
+) -> Result<Vec<NoteIterator<'a>>, Error> {
+    let segments = elf
+        .program_headers
+        .iter()
+        .filter(|ph| ph.get_type() == PT_NOTE)
+        .map(|ph| NoteIterator::parse_file_segment(buf, ph))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !segments.is_empty() {
+        return Ok(segments);
+    }
+
+    elf.section_headers
+        .iter()
+        .filter(|sh| sh.sh_type == SHT_NOTE)
+        .map(|sh| NoteIterator::parse_section(buf, sh))
+        .collect()
+}
+
+/// Finds the GNU build ID of an object, as written by `ld --build-id`.
+pub fn find_build_id<'a, A: AsRef<[u8]>>(
+    buf: &'a A,
+    elf: &Headers<'a>,
+) -> Result<Option<&'a [u8]>, Error> {
+    for notes in note_iterators(buf, elf)? {
+        for note in notes {
+            let note = note?;
+            if note.is_gnu() && note.n_type == NT_GNU_BUILD_ID {
+                return Ok(Some(note.desc));
+            }
+        }
+    }
+    Ok(None)
+}
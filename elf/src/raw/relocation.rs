@@ -1,4 +1,7 @@
-use crate::raw::{DT_RELA, DT_RELAENT, DT_RELASZ, SHT_RELA};
+use crate::raw::{
+    DT_JMPREL, DT_PLTREL, DT_PLTRELSZ, DT_RELA, DT_RELAENT, DT_RELASZ, EM_AARCH64, EM_X86_64,
+    R_AARCH64_IRELATIVE, R_X86_64_IRELATIVE, SHT_RELA,
+};
 
 use super::{dynamic::DynamicTable, header::SectionHeader, Error};
 
@@ -49,6 +52,51 @@ impl<'a, R: Relocation> RelocationTable<'a, R> {
         Ok(RelocationTable { relocs })
     }
 
+    /// Reads the PLT relocation table, DT_JMPREL, from the dynamic table, if present.
+    pub fn parse_jmprel_dynamic(
+        base: usize,
+        dynamic: &DynamicTable,
+    ) -> Result<Option<RelocationTable<'a, Rela>>, Error> {
+        let (addr, size) = match (
+            dynamic.find_value(DT_JMPREL),
+            dynamic.find_value(DT_PLTRELSZ),
+        ) {
+            (Some(addr), Some(size)) => (addr, size),
+            _ => return Ok(None),
+        };
+        if dynamic.find_value(DT_PLTREL) != Some(DT_RELA) {
+            return Err(Error::Message("PLT relocations are not RELA".to_string()));
+        }
+
+        let entry_count = size as usize / std::mem::size_of::<Rela>();
+        let ptr = (base + addr as usize) as *const Rela;
+        let relocs: &'a [Rela] = unsafe { std::slice::from_raw_parts(ptr, entry_count) };
+
+        Ok(Some(RelocationTable { relocs }))
+    }
+
+    /// Reads a relocation table from its contents, e.g. read through the dynamic table
+    /// of a file.
+    pub fn from_bytes(buf: &'a [u8]) -> Result<RelocationTable<'a, R>, Error> {
+        let entry_size = std::mem::size_of::<R>();
+        if !buf.len().is_multiple_of(entry_size) {
+            return Err(Error::Message("invalid relocation table size".to_string()));
+        }
+
+        let ptr = buf.as_ptr() as *const R;
+        let relocs: &'a [R] = unsafe { std::slice::from_raw_parts(ptr, buf.len() / entry_size) };
+
+        Ok(RelocationTable { relocs })
+    }
+
+    pub fn len(&self) -> usize {
+        self.relocs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.relocs.is_empty()
+    }
+
     pub fn get_relocation(&'a self, index: usize) -> &'a R {
         if index >= self.relocs.len() {
             panic!("invalid symbol index");
@@ -62,6 +110,58 @@ impl<'a, R: Relocation> RelocationTable<'a, R> {
     }
 }
 
+impl RelocationTable<'_, Rela> {
+    /// Applies the IRELATIVE relocations of the table to an image mapped at
+    /// `base_addr`: the resolver at the base address plus the addend is called to
+    /// select the implementation of an indirect function, and its address is stored
+    /// at the relocation offset. On AArch64 resolvers are passed `hwcap`, the AT_HWCAP
+    /// value of the process. Other relocations are skipped; resolvers may read
+    /// relocated data, so RELATIVE relocations should be applied first.
+    /// Returns the number of relocations applied.
+    ///
+    /// # Safety
+    /// The image must be mapped for the machine of the running process, with its
+    /// resolvers executable and the relocation offsets writable.
+    pub unsafe fn apply_irelative(
+        &self,
+        base_addr: usize,
+        machine: u16,
+        hwcap: u64,
+    ) -> Result<usize, Error> {
+        let irelative = match machine {
+            EM_X86_64 => R_X86_64_IRELATIVE,
+            EM_AARCH64 => R_AARCH64_IRELATIVE,
+            _ => {
+                return Err(Error::Message(format!(
+                    "IRELATIVE relocations are not supported for machine {}",
+                    machine
+                )))
+            }
+        };
+
+        let mut count = 0;
+        for reloc in self.relocs.iter().filter(|r| r.get_type() == irelative) {
+            let resolver = base_addr.wrapping_add(reloc.get_addend() as usize);
+            let target = (base_addr + reloc.get_offset() as usize) as *mut u64;
+            target.write_unaligned(call_ifunc_resolver(resolver, machine, hwcap));
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+/// Calls the resolver of an indirect function, returning the address of the
+/// implementation it selects. AArch64 resolvers are passed `hwcap`, the AT_HWCAP value
+/// of the process; x86_64 resolvers take no arguments and are passed 0.
+///
+/// # Safety
+/// `resolver` must be the address of a mapped, executable resolver for the machine of
+/// the running process.
+pub(crate) unsafe fn call_ifunc_resolver(resolver: usize, machine: u16, hwcap: u64) -> u64 {
+    let resolver: extern "C" fn(u64) -> u64 = std::mem::transmute(resolver);
+    resolver(if machine == EM_AARCH64 { hwcap } else { 0 })
+}
+
 static_assertions::const_assert!(std::mem::size_of::<Rel>() == 16);
 static_assertions::const_assert!(std::mem::size_of::<Rela>() == 24);
 
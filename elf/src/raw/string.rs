@@ -25,6 +25,16 @@ impl<'a> StringTable<'a> {
         Ok(StringTable { buf })
     }
 
+    /// Wraps string table contents located without a section header,
+    /// e.g. through DT_STRTAB and DT_STRSZ.
+    pub fn from_bytes(buf: &'a [u8]) -> Result<StringTable<'a>, Error> {
+        if !buf.is_empty() && buf[0] != 0x00 {
+            return Err(Error::Message("invalid string table".to_string()));
+        }
+
+        Ok(StringTable { buf })
+    }
+
     pub fn get_string(&self, offset: usize) -> &'a CStr {
         if offset >= self.buf.len() {
             panic!("invalid string access");
@@ -33,6 +43,19 @@ impl<'a> StringTable<'a> {
         unsafe { CStr::from_ptr(ptr.add(offset)) }
     }
 
+    /// Checked variant of `get_string` for offsets read from untrusted data,
+    /// such as dynamic table entries.
+    pub fn get_str(&self, offset: usize) -> Result<&'a str, Error> {
+        let bytes = self
+            .buf
+            .get(offset..)
+            .ok_or_else(|| Error::Message("string table offset out of range".to_string()))?;
+        let cstr = CStr::from_bytes_until_nul(bytes)
+            .map_err(|_| Error::Message("unterminated string".to_string()))?;
+        cstr.to_str()
+            .map_err(|_| Error::Message("invalid utf-8 string".to_string()))
+    }
+
     pub fn get_all_strings(&self) -> Vec<&'a CStr> {
         let mut strings = vec![];
         let mut start = 0;
@@ -41,6 +41,35 @@ impl<'a> SymbolTable<'a> {
         })
     }
 
+    /// Wraps symbol table contents located without a section header, e.g. through
+    /// DT_SYMTAB and the symbol count of the hash table.
+    pub fn from_bytes(
+        buf: &'a [u8],
+        string_table: StringTable<'a>,
+    ) -> Result<SymbolTable<'a>, Error> {
+        let entry_size = std::mem::size_of::<Symbol>();
+        if !buf.len().is_multiple_of(entry_size) {
+            return Err(Error::Message("invalid symbol table size".to_string()));
+        }
+
+        let ptr = buf.as_ptr() as *const Symbol;
+        let symbols: &'a [Symbol] =
+            unsafe { std::slice::from_raw_parts(ptr, buf.len() / entry_size) };
+
+        Ok(SymbolTable {
+            string_table,
+            symbols,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
     pub fn get_symbol(&'a self, index: usize) -> &'a Symbol {
         if index >= self.symbols.len() {
             panic!("invalid symbol index");
@@ -49,11 +78,17 @@ impl<'a> SymbolTable<'a> {
         &self.symbols[index]
     }
 
-    pub fn get_elf_symbol(&'a self, index: usize) -> ElfSymbol<'a> {
+    pub fn get_elf_symbol(&self, index: usize) -> ElfSymbol<'a> {
         self.convert_symbol(self.get_symbol(index))
     }
 
-    fn convert_symbol(&'a self, symbol: &Symbol) -> ElfSymbol<'a> {
+    /// Checked variant of `get_elf_symbol` for indices read from untrusted data,
+    /// such as relocations.
+    pub fn get(&self, index: usize) -> Option<ElfSymbol<'a>> {
+        self.symbols.get(index).map(|sym| self.convert_symbol(sym))
+    }
+
+    fn convert_symbol(&self, symbol: &Symbol) -> ElfSymbol<'a> {
         let name_index = symbol.st_name;
 
         let name = if name_index == 0 {
@@ -84,7 +119,7 @@ impl<'a> SymbolTable<'a> {
         self.symbols.iter()
     }
 
-    pub fn symbols_iter(&'a self) -> impl Iterator<Item = ElfSymbol<'a>> {
+    pub fn symbols_iter(&self) -> impl Iterator<Item = ElfSymbol<'a>> + '_ {
         self.symbols.iter().map(|sym| self.convert_symbol(sym))
     }
 }
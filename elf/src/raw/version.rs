@@ -0,0 +1,216 @@
+use super::{
+    dynamic::DynamicTable, hash::HashTable, header::Headers, mapping::AddressMap,
+    string::StringTable, Error, DT_STRSZ, DT_STRTAB, DT_VERDEF, DT_VERDEFNUM, DT_VERNEED,
+    DT_VERNEEDNUM, DT_VERSYM, PT_DYNAMIC, SHT_GNU_VERSYM, VERSYM_HIDDEN, VERSYM_VERSION,
+};
+
+/// A version defined by an object (`Elf64_Verdef`), e.g. `GLIBC_2.34` in libc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionDefinition<'a> {
+    /// The index referenced by the version symbol table
+    pub index: u16,
+    /// VER_FLG_* flags. The VER_FLG_BASE definition names the object itself.
+    pub flags: u16,
+    pub hash: u32,
+    pub name: &'a str,
+    /// The versions this version inherits from
+    pub parents: Vec<&'a str>,
+}
+
+/// A version required from a dependency (`Elf64_Vernaux`), e.g. `GLIBC_2.34`
+/// from `libc.so.6`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRequirement<'a> {
+    /// The dependency expected to define the version
+    pub file: &'a str,
+    /// The index referenced by the version symbol table
+    pub index: u16,
+    /// VER_FLG_* flags. A VER_FLG_WEAK requirement need not be satisfied.
+    pub flags: u16,
+    pub hash: u32,
+    pub name: &'a str,
+}
+
+/// The version of a symbol, from the version symbol table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolVersion {
+    /// VER_NDX_LOCAL, VER_NDX_GLOBAL, or the index of a definition or requirement
+    pub index: u16,
+    /// Hidden definitions can only be bound by references that request their version.
+    pub hidden: bool,
+}
+
+/// The symbol versioning information of an object: DT_VERSYM, DT_VERDEF and DT_VERNEED.
+#[derive(Debug, Clone, Default)]
+pub struct VersionTable<'a> {
+    versym: &'a [u8],
+    pub definitions: Vec<VersionDefinition<'a>>,
+    pub requirements: Vec<VersionRequirement<'a>>,
+}
+
+impl<'a> VersionTable<'a> {
+    /// Reads the versioning information of an ELF file through its dynamic table.
+    pub fn parse<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<VersionTable<'a>, Error> {
+        let hdr = elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_DYNAMIC)
+            .ok_or_else(|| Error::Message("no PT_DYNAMIC program header".to_string()))?;
+        let dynamic = DynamicTable::parse_file_segment(buf, hdr)?;
+
+        // without section headers, the hash table gives the number of symbols
+        let symbol_count = match elf.find_section_header(SHT_GNU_VERSYM) {
+            Some(hdr) => hdr.sh_size as usize / 2,
+            None if dynamic.find_value(DT_VERSYM).is_some() => {
+                HashTable::parse(buf, elf)?.symbol_count()
+            }
+            None => 0,
+        };
+
+        let map = AddressMap::new(buf, elf);
+        Self::resolve(&dynamic, symbol_count, |vaddr, size| {
+            map.read_file_backed(vaddr, size)
+        })
+    }
+
+    /// Reads the versioning information of an image mapped at `base_addr`, with
+    /// `symbol_count` dynamic symbols.
+    /// Precondition: all loadable segments have been mapped into virtual memory already,
+    /// and the dynamic table has not been relocated in place.
+    pub fn from_image(
+        base_addr: usize,
+        dynamic: &DynamicTable,
+        symbol_count: usize,
+    ) -> Result<VersionTable<'a>, Error> {
+        Self::resolve(dynamic, symbol_count, |vaddr, size| {
+            let ptr = (base_addr + vaddr as usize) as *const u8;
+            Ok(unsafe { std::slice::from_raw_parts(ptr, size as usize) })
+        })
+    }
+
+    /// Reads the tables at virtual addresses with `read`, returning `size` bytes.
+    pub(crate) fn resolve<F>(
+        dynamic: &DynamicTable,
+        symbol_count: usize,
+        read: F,
+    ) -> Result<VersionTable<'a>, Error>
+    where
+        F: Fn(u64, u64) -> Result<&'a [u8], Error>,
+    {
+        let mut table = VersionTable::default();
+        let versym = match dynamic.find_value(DT_VERSYM) {
+            Some(addr) => addr,
+            None => return Ok(table),
+        };
+        table.versym = read(versym, symbol_count as u64 * 2)?;
+
+        let strtab = match (dynamic.find_value(DT_STRTAB), dynamic.find_value(DT_STRSZ)) {
+            (Some(addr), Some(size)) => StringTable::from_bytes(read(addr, size)?)?,
+            _ => return Err(Error::Message("no dynamic string table".to_string())),
+        };
+
+        if let (Some(mut addr), Some(count)) = (
+            dynamic.find_value(DT_VERDEF),
+            dynamic.find_value(DT_VERDEFNUM),
+        ) {
+            for _ in 0..count {
+                let verdef = read(addr, 20)?;
+                let aux_count = read_u16(verdef, 6);
+                let mut aux_addr = addr + read_u32(verdef, 12) as u64;
+
+                let mut names = vec![];
+                for _ in 0..aux_count {
+                    let verdaux = read(aux_addr, 8)?;
+                    names.push(strtab.get_str(read_u32(verdaux, 0) as usize)?);
+                    aux_addr += read_u32(verdaux, 4) as u64;
+                }
+                if names.is_empty() {
+                    return Err(Error::Message(
+                        "version definition without name".to_string(),
+                    ));
+                }
+
+                table.definitions.push(VersionDefinition {
+                    index: read_u16(verdef, 4),
+                    flags: read_u16(verdef, 2),
+                    hash: read_u32(verdef, 8),
+                    name: names.remove(0),
+                    parents: names,
+                });
+
+                let next = read_u32(verdef, 16) as u64;
+                if next == 0 {
+                    break;
+                }
+                addr += next;
+            }
+        }
+
+        if let (Some(mut addr), Some(count)) = (
+            dynamic.find_value(DT_VERNEED),
+            dynamic.find_value(DT_VERNEEDNUM),
+        ) {
+            for _ in 0..count {
+                let verneed = read(addr, 16)?;
+                let file = strtab.get_str(read_u32(verneed, 4) as usize)?;
+                let mut aux_addr = addr + read_u32(verneed, 8) as u64;
+                for _ in 0..read_u16(verneed, 2) {
+                    let vernaux = read(aux_addr, 16)?;
+                    table.requirements.push(VersionRequirement {
+                        file,
+                        index: read_u16(vernaux, 6),
+                        flags: read_u16(vernaux, 4),
+                        hash: read_u32(vernaux, 0),
+                        name: strtab.get_str(read_u32(vernaux, 8) as usize)?,
+                    });
+                    aux_addr += read_u32(vernaux, 12) as u64;
+                }
+
+                let next = read_u32(verneed, 12) as u64;
+                if next == 0 {
+                    break;
+                }
+                addr += next;
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Whether the object has a version symbol table.
+    pub fn is_versioned(&self) -> bool {
+        !self.versym.is_empty()
+    }
+
+    /// The version of the dynamic symbol at an index.
+    pub fn symbol_version(&self, index: usize) -> Option<SymbolVersion> {
+        let value = read_u16(self.versym.get(index * 2..index * 2 + 2)?, 0);
+        Some(SymbolVersion {
+            index: value & VERSYM_VERSION,
+            hidden: value & VERSYM_HIDDEN != 0,
+        })
+    }
+
+    pub fn definition(&self, index: u16) -> Option<&VersionDefinition<'a>> {
+        self.definitions.iter().find(|d| d.index == index)
+    }
+
+    pub fn requirement(&self, index: u16) -> Option<&VersionRequirement<'a>> {
+        self.requirements.iter().find(|r| r.index == index)
+    }
+
+    /// The name of the version at an index, whether defined or required.
+    pub fn version_name(&self, index: u16) -> Option<&'a str> {
+        self.definition(index)
+            .map(|d| d.name)
+            .or_else(|| self.requirement(index).map(|r| r.name))
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
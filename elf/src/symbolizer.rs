@@ -0,0 +1,310 @@
+use std::cell::OnceCell;
+
+use crate::{
+    dwarf::{
+        info::{Die, Unit, UnitIterator},
+        line::LineProgram,
+        lists::Range,
+        DwarfSections, DW_AT_ABSTRACT_ORIGIN, DW_AT_CALL_COLUMN, DW_AT_CALL_FILE, DW_AT_CALL_LINE,
+        DW_AT_COMP_DIR, DW_AT_LINKAGE_NAME, DW_AT_MIPS_LINKAGE_NAME, DW_AT_NAME,
+        DW_AT_SPECIFICATION, DW_TAG_INLINED_SUBROUTINE, DW_TAG_SUBPROGRAM,
+    },
+    raw::{
+        compression::SectionStorage, header::Headers, symbol::SymbolTable, Error, SHN_UNDEF,
+        SHT_DYNSYM, SHT_SYMTAB, STT_FUNC, STT_GNU_IFUNC, STT_NOTYPE, STT_OBJECT,
+    },
+};
+
+/// Abstract origins and specifications can chain, e.g. an inlined instance of a
+/// C++ method refers to its abstract instance, which refers to its declaration.
+const MAX_ORIGIN_DEPTH: usize = 8;
+
+/// Maps addresses to functions and source locations, like binutils `addr2line -f -i`.
+///
+/// DWARF debug information is used when present, including the chain of inlined calls
+/// at an address. Otherwise the function is looked up in `.symtab` and `.dynsym`.
+pub struct Symbolizer<'a> {
+    units: Vec<UnitEntry<'a>>,
+    /// Function and object symbols, sorted by address
+    symbols: Vec<SymbolEntry<'a>>,
+}
+
+struct UnitEntry<'a> {
+    unit: Unit<'a>,
+    ranges: Vec<Range>,
+    comp_dir: Option<&'a str>,
+    /// Parsed on the first lookup that needs it
+    lines: OnceCell<Option<LineProgram<'a>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SymbolEntry<'a> {
+    address: u64,
+    size: u64,
+    name: &'a str,
+}
+
+/// A function in the call chain at an address, and the source location within it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub function: Option<&'a str>,
+    pub file: Option<String>,
+    pub line: u64,
+    pub column: u64,
+}
+
+impl<'a> Symbolizer<'a> {
+    /// Fails if the debug sections are compressed, see `from_sections`.
+    pub fn new<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers) -> Result<Symbolizer<'a>, Error> {
+        Symbolizer::from_sections(buf, elf, &DwarfSections::load(buf, elf)?)
+    }
+
+    /// Uses debug sections loaded separately, e.g. decompressed by `DwarfSections::load_with`.
+    pub fn from_sections<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+        sections: &DwarfSections<'a>,
+    ) -> Result<Symbolizer<'a>, Error> {
+        Symbolizer::from_parts(sections, read_symbols(buf, elf)?)
+    }
+
+    /// Combines a stripped object with its separate debug file, as located through
+    /// `debuglink`. Debug sections and symbols of the debug file take precedence over
+    /// those left in the object. `supplementary` is the file named by the debug file's
+    /// `.gnu_debugaltlink`, if any. Debug files are often compressed, so sections are
+    /// decompressed into `storage`.
+    pub fn with_debug_file<A: AsRef<[u8]>, D: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+        debug: &'a D,
+        supplementary: Option<&'a D>,
+        storage: &'a SectionStorage,
+    ) -> Result<Symbolizer<'a>, Error> {
+        let debug_elf = Headers::parse(debug)?;
+        let mut sections = DwarfSections::load_with(debug, &debug_elf, storage)?
+            .merge(&DwarfSections::load_with(buf, elf, storage)?);
+        if let Some(supplementary) = supplementary {
+            let sup_elf = Headers::parse(supplementary)?;
+            sections.debug_str_sup =
+                DwarfSections::load_with(supplementary, &sup_elf, storage)?.debug_str;
+        }
+
+        let mut symbols = read_symbols(debug, &debug_elf)?;
+        symbols.extend(read_symbols(buf, elf)?);
+        Symbolizer::from_parts(&sections, symbols)
+    }
+
+    fn from_parts(
+        sections: &DwarfSections<'a>,
+        mut symbols: Vec<SymbolEntry<'a>>,
+    ) -> Result<Symbolizer<'a>, Error> {
+        let mut units = vec![];
+        for unit in UnitIterator::new(sections) {
+            let unit = unit?;
+            let root = unit.root()?;
+            let comp_dir = match root.attr(DW_AT_COMP_DIR) {
+                Some(value) => unit.string(value)?,
+                None => None,
+            };
+            units.push(UnitEntry {
+                ranges: unit.ranges(&root)?,
+                unit,
+                comp_dir,
+                lines: OnceCell::new(),
+            });
+        }
+
+        // earlier tables win over aliases at the same address, e.g. .symtab over .dynsym
+        symbols.sort_by_key(|sym| sym.address);
+        symbols.dedup_by_key(|sym| sym.address);
+
+        Ok(Symbolizer { units, symbols })
+    }
+
+    /// Returns the call chain at an address, innermost (inlined) function first.
+    /// The chain is empty if nothing is known about the address.
+    pub fn symbolize(&self, address: u64) -> Result<Vec<Frame<'a>>, Error> {
+        let entry = match self
+            .units
+            .iter()
+            .find(|entry| entry.ranges.iter().any(|r| r.contains(address)))
+        {
+            Some(entry) => entry,
+            None => return Ok(self.symbol_frame(address).into_iter().collect()),
+        };
+        let unit = &entry.unit;
+
+        let chain = self.inline_chain(unit, address)?;
+
+        // the innermost location comes from the line table
+        let lines = entry
+            .lines
+            .get_or_init(|| unit.line_program().ok().flatten());
+        let mut location = lines.as_ref().and_then(|lines| {
+            lines
+                .find_row(address)
+                .map(|row| (row.file, row.line, row.column))
+        });
+
+        let mut frames = vec![];
+        for die in chain.iter().rev() {
+            let (file, line, column) = location.unwrap_or_default();
+            frames.push(Frame {
+                function: self.function_name(unit, die)?,
+                file: location.and_then(|_| self.file_path(entry, file)),
+                line,
+                column,
+            });
+
+            // an inlined subroutine's call site is the location in its caller
+            location = if die.tag == DW_TAG_INLINED_SUBROUTINE {
+                Some((
+                    attr_u64(die, DW_AT_CALL_FILE),
+                    attr_u64(die, DW_AT_CALL_LINE),
+                    attr_u64(die, DW_AT_CALL_COLUMN),
+                ))
+            } else {
+                None
+            };
+        }
+
+        if frames.is_empty() {
+            let (file, line, column) = location.unwrap_or_default();
+            frames.push(Frame {
+                function: self.symbol_frame(address).and_then(|f| f.function),
+                file: location.and_then(|_| self.file_path(entry, file)),
+                line,
+                column,
+            });
+        } else if frames[frames.len() - 1].function.is_none() {
+            let outermost = frames.len() - 1;
+            frames[outermost].function = self.symbol_frame(address).and_then(|f| f.function);
+        }
+
+        Ok(frames)
+    }
+
+    /// The subprogram containing an address, followed by the inlined subroutines
+    /// nested within it that also contain it.
+    fn inline_chain(&self, unit: &Unit<'a>, address: u64) -> Result<Vec<Die<'a>>, Error> {
+        let mut chain: Vec<Die> = vec![];
+        for die in unit.entries() {
+            let die = die?;
+            // once iteration leaves the subtree of the innermost match, the chain is complete
+            if let Some(last) = chain.last() {
+                if die.depth <= last.depth {
+                    break;
+                }
+            }
+            if die.tag != DW_TAG_SUBPROGRAM && die.tag != DW_TAG_INLINED_SUBROUTINE {
+                continue;
+            }
+            if unit.ranges(&die)?.iter().any(|r| r.contains(address)) {
+                chain.push(die);
+            }
+        }
+        Ok(chain)
+    }
+
+    /// The linkage name or name of a function, following abstract origins and
+    /// specifications to the DIE that declares it.
+    fn function_name(&self, unit: &Unit<'a>, die: &Die<'a>) -> Result<Option<&'a str>, Error> {
+        let mut unit = unit;
+        let mut die = die.clone();
+        for _ in 0..MAX_ORIGIN_DEPTH {
+            for name in [DW_AT_LINKAGE_NAME, DW_AT_MIPS_LINKAGE_NAME, DW_AT_NAME] {
+                if let Some(value) = die.attr(name) {
+                    if let Some(name) = unit.string(value)? {
+                        return Ok(Some(name));
+                    }
+                }
+            }
+
+            let origin = die
+                .attr(DW_AT_ABSTRACT_ORIGIN)
+                .or_else(|| die.attr(DW_AT_SPECIFICATION))
+                .and_then(|value| value.as_u64());
+            let offset = match origin {
+                Some(offset) => offset,
+                None => break,
+            };
+            // references made with DW_FORM_ref_addr may point into another unit
+            unit = match self.units.iter().find(|entry| entry.unit.contains(offset)) {
+                Some(entry) => &entry.unit,
+                None => break,
+            };
+            die = unit.entry_at(offset)?;
+        }
+        Ok(None)
+    }
+
+    fn file_path(&self, entry: &UnitEntry<'a>, index: u64) -> Option<String> {
+        let lines = entry.lines.get()?.as_ref()?;
+        let (directory, file) = lines.file(index)?;
+
+        let mut path = String::new();
+        for part in [entry.comp_dir, directory, Some(file)]
+            .into_iter()
+            .flatten()
+        {
+            if part.starts_with('/') {
+                path.clear();
+            } else if !path.is_empty() && !path.ends_with('/') {
+                path.push('/');
+            }
+            path.push_str(part);
+        }
+        Some(path)
+    }
+
+    /// Looks up the symbol containing an address, for objects without debug information.
+    fn symbol_frame(&self, address: u64) -> Option<Frame<'a>> {
+        let index = self.symbols.partition_point(|sym| sym.address <= address);
+        let sym = self.symbols.get(index.checked_sub(1)?)?;
+        if sym.size != 0 && address >= sym.address + sym.size {
+            return None;
+        }
+        Some(Frame {
+            function: Some(sym.name),
+            file: None,
+            line: 0,
+            column: 0,
+        })
+    }
+}
+
+fn attr_u64(die: &Die, name: u64) -> u64 {
+    die.attr(name).and_then(|value| value.as_u64()).unwrap_or(0)
+}
+
+/// Reads the function and object symbols of `.symtab` and `.dynsym`, in that order.
+fn read_symbols<'a, A: AsRef<[u8]>>(
+    buf: &'a A,
+    elf: &Headers,
+) -> Result<Vec<SymbolEntry<'a>>, Error> {
+    let mut symbols = vec![];
+    for sh_type in [SHT_SYMTAB, SHT_DYNSYM] {
+        let hdr = match elf.find_section_header(sh_type) {
+            Some(hdr) => hdr,
+            None => continue,
+        };
+        let symtab = SymbolTable::parse(buf, elf, hdr)?;
+        symbols.extend(
+            symtab
+                .symbols_iter()
+                .filter(|sym| {
+                    matches!(
+                        sym.info & 0xf,
+                        STT_NOTYPE | STT_OBJECT | STT_FUNC | STT_GNU_IFUNC
+                    ) && sym.shndx != SHN_UNDEF
+                        && !sym.name.is_empty()
+                })
+                .map(|sym| SymbolEntry {
+                    address: sym.value,
+                    size: sym.size,
+                    name: sym.name,
+                }),
+        );
+    }
+    Ok(symbols)
+}
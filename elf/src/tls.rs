@@ -0,0 +1,381 @@
+use crate::raw::{
+    header::{Headers, ProgramHeader},
+    Error, EM_AARCH64, EM_RISCV, EM_X86_64, PT_TLS, R_AARCH64_TLS_DTPMOD64, R_AARCH64_TLS_DTPREL64,
+    R_AARCH64_TLS_TPREL64, R_RISCV_TLS_DTPMOD64, R_RISCV_TLS_DTPREL64, R_RISCV_TLS_TPREL64,
+    R_X86_64_DTPMOD64, R_X86_64_DTPOFF64, R_X86_64_TPOFF64,
+};
+
+/// RISC-V biases DTPREL values by this much, so that a signed 12-bit offset from
+/// the DTV pointer reaches more of the block.
+const RISCV_DTV_OFFSET: u64 = 0x800;
+
+/// The initialization image of the thread-local storage of a module, described by
+/// its PT_TLS segment. Every thread gets a block of `memsz` bytes, starting with a
+/// copy of `tdata` and followed by the zero-filled `.tbss`.
+#[derive(Debug, Clone, Copy)]
+pub struct TlsTemplate<'a> {
+    /// The link-time address of the template. TLS symbol values are relative to it.
+    pub vaddr: u64,
+    /// The initialized data, `.tdata`
+    pub tdata: &'a [u8],
+    pub memsz: u64,
+    pub align: u64,
+}
+
+impl<'a> TlsTemplate<'a> {
+    /// Reads the template from the PT_TLS segment of an ELF file, if it has one.
+    pub fn parse<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers<'a>,
+    ) -> Result<Option<TlsTemplate<'a>>, Error> {
+        let hdr = match elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_TLS)
+        {
+            Some(hdr) => hdr,
+            None => return Ok(None),
+        };
+
+        let start = hdr.get_offset() as usize;
+        let tdata = start
+            .checked_add(hdr.get_filesz() as usize)
+            .and_then(|end| buf.as_ref().get(start..end))
+            .ok_or_else(|| Error::Message("invalid PT_TLS segment".to_string()))?;
+        Ok(Some(TlsTemplate::new(hdr, tdata)))
+    }
+
+    /// Reads the template of an image mapped at `base_addr`.
+    /// Precondition: all loadable segments have been mapped into virtual memory already.
+    pub fn from_image(base_addr: usize, hdr: &ProgramHeader) -> Result<TlsTemplate<'a>, Error> {
+        if hdr.get_type() != PT_TLS {
+            return Err(Error::Message("header not PT_TLS".to_string()));
+        }
+
+        let ptr = (base_addr + hdr.get_vaddr() as usize) as *const u8;
+        let tdata = unsafe { std::slice::from_raw_parts(ptr, hdr.get_filesz() as usize) };
+        Ok(TlsTemplate::new(hdr, tdata))
+    }
+
+    fn new(hdr: &ProgramHeader, tdata: &'a [u8]) -> TlsTemplate<'a> {
+        TlsTemplate {
+            vaddr: hdr.get_vaddr(),
+            tdata,
+            memsz: hdr.get_memsz(),
+            align: hdr.get_align().max(1),
+        }
+    }
+
+    /// The size of the zero-initialized data, `.tbss`.
+    pub fn tbss_size(&self) -> u64 {
+        self.memsz.saturating_sub(self.tdata.len() as u64)
+    }
+
+    /// Initializes the TLS block of a thread from the template. Fails if the block
+    /// cannot hold the initialized data, `.tdata`.
+    pub fn initialize(&self, block: &mut [u8]) -> Result<(), Error> {
+        if block.len() < self.tdata.len() {
+            return Err(Error::Message("TLS block is too small".to_string()));
+        }
+        let (tdata, tbss) = block.split_at_mut(self.tdata.len());
+        tdata.copy_from_slice(self.tdata);
+        tbss.fill(0);
+        Ok(())
+    }
+}
+
+/// Where the TLS blocks are placed relative to the thread pointer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsVariant {
+    /// The thread pointer points to the thread control block, which is followed by
+    /// the TLS blocks (AArch64, RISC-V).
+    I,
+    /// The TLS blocks precede the thread pointer, which points to the thread
+    /// control block (x86_64).
+    II,
+}
+
+/// A TLS relocation, resolved by `StaticTls::resolve_relocation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsRelocation {
+    /// The module id of the symbol, used as an index into the DTV
+    DtpMod,
+    /// The offset of the symbol within its module's TLS block
+    DtpOff,
+    /// The offset of the symbol from the thread pointer
+    TpOff,
+}
+
+impl TlsRelocation {
+    pub fn from_type(machine: u16, r_type: u32) -> Option<TlsRelocation> {
+        match (machine, r_type) {
+            (EM_X86_64, R_X86_64_DTPMOD64)
+            | (EM_AARCH64, R_AARCH64_TLS_DTPMOD64)
+            | (EM_RISCV, R_RISCV_TLS_DTPMOD64) => Some(TlsRelocation::DtpMod),
+            (EM_X86_64, R_X86_64_DTPOFF64)
+            | (EM_AARCH64, R_AARCH64_TLS_DTPREL64)
+            | (EM_RISCV, R_RISCV_TLS_DTPREL64) => Some(TlsRelocation::DtpOff),
+            (EM_X86_64, R_X86_64_TPOFF64)
+            | (EM_AARCH64, R_AARCH64_TLS_TPREL64)
+            | (EM_RISCV, R_RISCV_TLS_TPREL64) => Some(TlsRelocation::TpOff),
+            _ => None,
+        }
+    }
+}
+
+/// A TLS block placed in the static TLS area.
+#[derive(Debug, Clone, Copy)]
+pub struct TlsModule<'a> {
+    /// The module id, starting from 1 for the executable
+    pub id: u64,
+    pub template: TlsTemplate<'a>,
+    /// The offset of the block from the thread pointer
+    pub offset: i64,
+}
+
+/// The static TLS area of a thread: the TLS blocks of the executable and of the
+/// libraries loaded with it, at fixed offsets from the thread pointer. Blocks are
+/// laid out as in "ELF Handling For Thread-Local Storage", assuming each template
+/// starts at an address aligned to its alignment.
+#[derive(Debug, Clone)]
+pub struct StaticTls<'a> {
+    machine: u16,
+    variant: TlsVariant,
+    /// The size of the thread control block at the thread pointer in variant I
+    tcb_size: u64,
+    modules: Vec<TlsModule<'a>>,
+    /// The size of the TLS blocks, excluding the thread control block
+    size: u64,
+    align: u64,
+}
+
+impl<'a> StaticTls<'a> {
+    pub fn new(machine: u16) -> Result<StaticTls<'a>, Error> {
+        let (variant, tcb_size) = match machine {
+            EM_X86_64 => (TlsVariant::II, 0),
+            // the TCB holds the DTV pointer and a reserved word
+            EM_AARCH64 => (TlsVariant::I, 16),
+            // the thread pointer points past the TCB
+            EM_RISCV => (TlsVariant::I, 0),
+            _ => {
+                return Err(Error::Message(format!(
+                    "unsupported machine {} for TLS",
+                    machine
+                )))
+            }
+        };
+
+        Ok(StaticTls {
+            machine,
+            variant,
+            tcb_size,
+            modules: vec![],
+            size: 0,
+            align: 1,
+        })
+    }
+
+    pub fn variant(&self) -> TlsVariant {
+        self.variant
+    }
+
+    /// Places the next TLS block, returning its module id. The executable must be
+    /// added first, followed by its libraries in load order.
+    pub fn add(&mut self, template: TlsTemplate<'a>) -> u64 {
+        let offset = match self.variant {
+            TlsVariant::I => {
+                let start = align_up(self.tcb_size + self.size, template.align);
+                self.size = start + template.memsz - self.tcb_size;
+                start as i64
+            }
+            TlsVariant::II => {
+                self.size = align_up(self.size + template.memsz, template.align);
+                -(self.size as i64)
+            }
+        };
+        self.align = self.align.max(template.align);
+
+        let id = self.modules.len() as u64 + 1;
+        self.modules.push(TlsModule {
+            id,
+            template,
+            offset,
+        });
+        id
+    }
+
+    pub fn modules(&self) -> &[TlsModule<'a>] {
+        &self.modules
+    }
+
+    pub fn module(&self, id: u64) -> Option<&TlsModule<'a>> {
+        self.modules.get(id.checked_sub(1)? as usize)
+    }
+
+    /// The alignment of the static TLS area, and of the thread pointer.
+    pub fn align(&self) -> u64 {
+        self.align
+    }
+
+    /// The size of the static TLS area: the TLS blocks and, in variant I, the thread
+    /// control block. In variant II the thread control block follows the area.
+    pub fn area_size(&self) -> u64 {
+        align_up(self.tcb_size + self.size, self.align)
+    }
+
+    /// The offset of the thread pointer within the static TLS area.
+    pub fn thread_pointer_offset(&self) -> u64 {
+        match self.variant {
+            TlsVariant::I => 0,
+            TlsVariant::II => self.area_size(),
+        }
+    }
+
+    /// Initializes the TLS blocks of a thread in its static TLS area, which must be
+    /// `area_size` bytes aligned to `align`. The thread control block is left to the
+    /// caller.
+    pub fn initialize(&self, area: &mut [u8]) -> Result<(), Error> {
+        if (area.len() as u64) < self.area_size() {
+            return Err(Error::Message("static TLS area is too small".to_string()));
+        }
+
+        let tp = self.thread_pointer_offset() as i64;
+        for module in self.modules.iter() {
+            let start = (tp + module.offset) as usize;
+            module
+                .template
+                .initialize(&mut area[start..start + module.template.memsz as usize])?;
+        }
+        Ok(())
+    }
+
+    /// Computes the value of a TLS relocation against a symbol of module `id`, with
+    /// `value` the symbol value, an offset within the module's TLS template. Symbol
+    /// values are zero for relocations without a symbol.
+    pub fn resolve_relocation(
+        &self,
+        relocation: TlsRelocation,
+        id: u64,
+        value: u64,
+        addend: i64,
+    ) -> Result<u64, Error> {
+        let module = self
+            .module(id)
+            .ok_or_else(|| Error::Message(format!("unknown TLS module {}", id)))?;
+        let offset = value.wrapping_add(addend as u64);
+
+        Ok(match relocation {
+            TlsRelocation::DtpMod => module.id,
+            TlsRelocation::DtpOff if self.machine == EM_RISCV => {
+                offset.wrapping_sub(RISCV_DTV_OFFSET)
+            }
+            TlsRelocation::DtpOff => offset,
+            TlsRelocation::TpOff => (module.offset as u64).wrapping_add(offset),
+        })
+    }
+}
+
+fn align_up(value: u64, align: u64) -> u64 {
+    value.div_ceil(align) * align
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::EM_ARM;
+
+    const TDATA: &[u8] = &[1, 2, 3, 4];
+
+    /// Blocks with mixed sizes and alignments, the first with initialized data.
+    fn layout(machine: u16) -> StaticTls<'static> {
+        let mut tls = StaticTls::new(machine).unwrap();
+        for (tdata, memsz, align) in [(TDATA, 0x14, 8), (&[][..], 0x30, 32), (&[][..], 5, 1)] {
+            tls.add(TlsTemplate {
+                vaddr: 0,
+                tdata,
+                memsz,
+                align,
+            });
+        }
+        tls
+    }
+
+    fn offsets(tls: &StaticTls) -> Vec<i64> {
+        tls.modules().iter().map(|module| module.offset).collect()
+    }
+
+    #[test]
+    fn variant_ii_x86_64() {
+        let tls = layout(EM_X86_64);
+        assert_eq!(tls.variant(), TlsVariant::II);
+        assert_eq!(offsets(&tls), [-0x18, -0x60, -0x65]);
+        assert_eq!(tls.align(), 32);
+        assert_eq!(tls.area_size(), 0x80);
+        assert_eq!(tls.thread_pointer_offset(), 0x80);
+
+        let resolve = |kind, id, value, addend| tls.resolve_relocation(kind, id, value, addend);
+        assert_eq!(resolve(TlsRelocation::DtpMod, 2, 8, 4).unwrap(), 2);
+        assert_eq!(resolve(TlsRelocation::DtpOff, 2, 8, 4).unwrap(), 12);
+        assert_eq!(
+            resolve(TlsRelocation::TpOff, 2, 8, 4).unwrap(),
+            -0x54i64 as u64
+        );
+        assert!(resolve(TlsRelocation::TpOff, 0, 0, 0).is_err());
+        assert!(resolve(TlsRelocation::TpOff, 4, 0, 0).is_err());
+
+        let mut area = vec![0xff; 0x80];
+        tls.initialize(&mut area).unwrap();
+        assert_eq!(&area[0x68..0x6c], TDATA);
+        assert!(area[0x6c..0x7c].iter().all(|&b| b == 0));
+        assert!(area[0x7c..].iter().all(|&b| b == 0xff));
+        assert!(tls.initialize(&mut area[..0x7f]).is_err());
+    }
+
+    #[test]
+    fn variant_i_aarch64() {
+        let tls = layout(EM_AARCH64);
+        assert_eq!(tls.variant(), TlsVariant::I);
+        // the blocks follow the 16-byte thread control block
+        assert_eq!(offsets(&tls), [0x10, 0x40, 0x70]);
+        assert_eq!(tls.area_size(), 0x80);
+        assert_eq!(tls.thread_pointer_offset(), 0);
+        assert_eq!(
+            tls.resolve_relocation(TlsRelocation::TpOff, 3, 2, 0)
+                .unwrap(),
+            0x72
+        );
+        assert_eq!(
+            tls.resolve_relocation(TlsRelocation::DtpOff, 3, 2, 0)
+                .unwrap(),
+            2
+        );
+
+        let mut area = vec![0xff; 0x80];
+        tls.initialize(&mut area).unwrap();
+        assert!(area[..0x10].iter().all(|&b| b == 0xff));
+        assert_eq!(&area[0x10..0x14], TDATA);
+    }
+
+    #[test]
+    fn variant_i_riscv() {
+        let tls = layout(EM_RISCV);
+        assert_eq!(offsets(&tls), [0, 0x20, 0x50]);
+        assert_eq!(tls.area_size(), 0x60);
+        assert_eq!(
+            tls.resolve_relocation(TlsRelocation::TpOff, 2, 4, 0)
+                .unwrap(),
+            0x24
+        );
+        // DTPREL values are biased
+        assert_eq!(
+            tls.resolve_relocation(TlsRelocation::DtpOff, 2, 8, 0)
+                .unwrap(),
+            8u64.wrapping_sub(0x800)
+        );
+    }
+
+    #[test]
+    fn unsupported_machine() {
+        assert!(StaticTls::new(EM_ARM).is_err());
+    }
+}
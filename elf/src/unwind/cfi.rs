@@ -0,0 +1,842 @@
+use std::marker::PhantomData;
+
+use crate::{
+    dwarf::{
+        reader::{truncated, Reader},
+        Format,
+    },
+    raw::Error,
+};
+
+use super::*;
+
+/// An entry of a call frame information section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfiEntry<'a> {
+    Cie(Cie<'a>),
+    Fde(Fde<'a>),
+}
+
+/// A section of call frame information: `.eh_frame` or `.debug_frame`.
+pub trait CfiSection<'a>: Copy {
+    fn data(&self) -> &'a [u8];
+
+    /// Parses the entry at an offset, returning it and the offset of the next entry,
+    /// or `None` at a terminator.
+    fn entry_at(&self, offset: u64) -> Result<Option<(CfiEntry<'a>, u64)>, Error>;
+
+    fn entries(&self) -> EntryIterator<'a, Self> {
+        EntryIterator {
+            section: *self,
+            offset: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    fn cie_at(&self, offset: u64) -> Result<Cie<'a>, Error> {
+        match self.entry_at(offset)? {
+            Some((CfiEntry::Cie(cie), _)) => Ok(cie),
+            _ => Err(Error::Message(format!("no CIE at offset 0x{:x}", offset))),
+        }
+    }
+
+    fn fde_at(&self, offset: u64) -> Result<Fde<'a>, Error> {
+        match self.entry_at(offset)? {
+            Some((CfiEntry::Fde(fde), _)) => Ok(fde),
+            _ => Err(Error::Message(format!("no FDE at offset 0x{:x}", offset))),
+        }
+    }
+
+    /// Finds the FDE covering an address by scanning every entry.
+    fn find_fde(&self, address: u64) -> Result<Option<Fde<'a>>, Error> {
+        for entry in self.entries() {
+            if let CfiEntry::Fde(fde) = entry? {
+                if fde.contains(address) {
+                    return Ok(Some(fde));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Iterates over the CIEs and FDEs of a section, up to its end or terminator.
+pub struct EntryIterator<'a, S> {
+    section: S,
+    offset: u64,
+    _marker: PhantomData<&'a [u8]>,
+}
+
+impl<'a, S: CfiSection<'a>> Iterator for EntryIterator<'a, S> {
+    type Item = Result<CfiEntry<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.section.data().len() as u64;
+        if self.offset >= len {
+            return None;
+        }
+        match self.section.entry_at(self.offset) {
+            Ok(Some((entry, next))) => {
+                self.offset = next;
+                Some(Ok(entry))
+            }
+            Ok(None) => {
+                self.offset = len;
+                None
+            }
+            Err(e) => {
+                self.offset = len;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SectionKind {
+    EhFrame,
+    /// Addresses are of the target's size, which the CIE only records from version 4
+    DebugFrame {
+        address_size: u8,
+    },
+}
+
+/// Parses the CIE or FDE at an offset of a section at `address`. The sections differ in
+/// how CIEs are marked and referenced, and `.debug_frame` has no pointer encodings.
+pub(crate) fn parse_entry(
+    data: &[u8],
+    address: u64,
+    offset: u64,
+    kind: SectionKind,
+) -> Result<Option<(CfiEntry<'_>, u64)>, Error> {
+    let header = match EntryHeader::parse(data, offset, kind)? {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let bases = PointerBases {
+        section: address,
+        ..Default::default()
+    };
+
+    let entry = if header.is_cie {
+        CfiEntry::Cie(Cie::parse(
+            data, offset, header.r, header.end, &bases, kind,
+        )?)
+    } else {
+        // an FDE pointing at itself would otherwise be read as its own CIE
+        if header.cie_offset == offset {
+            return Err(Error::Message(format!(
+                "FDE at offset 0x{:x} refers to itself as its CIE",
+                offset
+            )));
+        }
+        let cie = parse_cie(data, address, header.cie_offset, kind)?;
+        CfiEntry::Fde(Fde::parse(data, offset, cie, header.r, header.end, &bases)?)
+    };
+    Ok(Some((entry, header.end as u64)))
+}
+
+/// Parses the CIE an FDE refers to. Never follows another CIE pointer, so malformed
+/// FDEs referring to each other cannot recurse.
+fn parse_cie(data: &[u8], address: u64, offset: u64, kind: SectionKind) -> Result<Cie<'_>, Error> {
+    let header = EntryHeader::parse(data, offset, kind)?
+        .filter(|header| header.is_cie)
+        .ok_or_else(|| Error::Message(format!("no CIE at offset 0x{:x}", offset)))?;
+    let bases = PointerBases {
+        section: address,
+        ..Default::default()
+    };
+    Cie::parse(data, offset, header.r, header.end, &bases, kind)
+}
+
+/// The length and CIE id or pointer that start every entry.
+struct EntryHeader<'a> {
+    /// Positioned after the CIE id or pointer
+    r: Reader<'a>,
+    end: usize,
+    is_cie: bool,
+    /// For FDEs, the offset of their CIE
+    cie_offset: u64,
+}
+
+impl<'a> EntryHeader<'a> {
+    /// Reads the header of the entry at an offset, or `None` for a zero terminator.
+    fn parse(data: &'a [u8], offset: u64, kind: SectionKind) -> Result<Option<Self>, Error> {
+        let mut r = Reader::at(data, offset as usize);
+        let (length, format) = r.initial_length()?;
+        if length == 0 {
+            return Ok(None);
+        }
+        let end = r
+            .offset()
+            .checked_add(length as usize)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(truncated)?;
+
+        let id_position = r.offset() as u64;
+        let id = r.offset_of(format)?;
+        let (is_cie, cie_offset) = match kind {
+            // the CIE pointer of an FDE is relative to its own position
+            SectionKind::EhFrame => (id == 0, id_position.wrapping_sub(id)),
+            SectionKind::DebugFrame { .. } => {
+                let cie_id = match format {
+                    Format::Dwarf32 => 0xffff_ffff,
+                    Format::Dwarf64 => u64::MAX,
+                };
+                (id == cie_id, id)
+            }
+        };
+        Ok(Some(EntryHeader {
+            r,
+            end,
+            is_cie,
+            cie_offset,
+        }))
+    }
+}
+
+/// A pointer read with a DW_EH_PE_* encoding. Indirect pointers give the address
+/// where the actual value is stored, e.g. a GOT entry holding the personality routine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pointer {
+    Direct(u64),
+    Indirect(u64),
+}
+
+impl Pointer {
+    /// The encoded value, which is the address of the value for indirect pointers.
+    pub fn address(&self) -> u64 {
+        match *self {
+            Pointer::Direct(address) | Pointer::Indirect(address) => address,
+        }
+    }
+}
+
+/// The addresses that relative pointer encodings are applied to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PointerBases {
+    /// The address of the first byte of the data being read, for DW_EH_PE_pcrel
+    pub section: u64,
+    /// For DW_EH_PE_textrel
+    pub text: Option<u64>,
+    /// For DW_EH_PE_datarel, the start of `.eh_frame_hdr` when reading its table
+    pub data: Option<u64>,
+    /// For DW_EH_PE_funcrel, the start of the function
+    pub func: Option<u64>,
+}
+
+/// Reads a pointer with a DW_EH_PE_* encoding, or `None` for DW_EH_PE_omit.
+pub(crate) fn read_pointer(
+    r: &mut Reader,
+    encoding: u8,
+    bases: &PointerBases,
+) -> Result<Option<Pointer>, Error> {
+    if encoding == DW_EH_PE_OMIT {
+        return Ok(None);
+    }
+
+    let position = bases.section.wrapping_add(r.offset() as u64);
+    let base = match encoding & 0x70 {
+        DW_EH_PE_ABSPTR => 0,
+        DW_EH_PE_PCREL => position,
+        DW_EH_PE_TEXTREL => bases.text.ok_or_else(|| missing_base("text"))?,
+        DW_EH_PE_DATAREL => bases.data.ok_or_else(|| missing_base("data"))?,
+        DW_EH_PE_FUNCREL => bases.func.ok_or_else(|| missing_base("function"))?,
+        DW_EH_PE_ALIGNED => {
+            r.skip((position.wrapping_neg() & 7) as usize)?;
+            0
+        }
+        _ => {
+            return Err(Error::Message(format!(
+                "invalid pointer encoding 0x{:02x}",
+                encoding
+            )))
+        }
+    };
+
+    let value = read_encoded_value(r, encoding)?;
+    let address = base.wrapping_add(value);
+    if encoding & DW_EH_PE_INDIRECT != 0 {
+        Ok(Some(Pointer::Indirect(address)))
+    } else {
+        Ok(Some(Pointer::Direct(address)))
+    }
+}
+
+/// Reads the value of a pointer without applying its base, sign-extending signed formats.
+pub(crate) fn read_encoded_value(r: &mut Reader, encoding: u8) -> Result<u64, Error> {
+    Ok(match encoding & 0x0f {
+        DW_EH_PE_ABSPTR => r.u64()?,
+        DW_EH_PE_ULEB128 => r.uleb128()?,
+        DW_EH_PE_UDATA2 => r.u16()? as u64,
+        DW_EH_PE_UDATA4 => r.u32()? as u64,
+        DW_EH_PE_UDATA8 => r.u64()?,
+        DW_EH_PE_SLEB128 => r.sleb128()? as u64,
+        DW_EH_PE_SDATA2 => r.u16()? as i16 as i64 as u64,
+        DW_EH_PE_SDATA4 => r.u32()? as i32 as i64 as u64,
+        DW_EH_PE_SDATA8 => r.u64()?,
+        _ => {
+            return Err(Error::Message(format!(
+                "invalid pointer encoding 0x{:02x}",
+                encoding
+            )))
+        }
+    })
+}
+
+fn missing_base(name: &str) -> Error {
+    Error::Message(format!("no {} base address for relative pointer", name))
+}
+
+/// A Common Information Entry, holding what the FDEs referring to it share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cie<'a> {
+    /// Offset of the entry in its section
+    pub offset: u64,
+    pub version: u8,
+    /// e.g. "zR" or "zPLR", each letter describing augmentation data
+    pub augmentation: &'a str,
+    pub address_size: u8,
+    pub code_alignment_factor: u64,
+    pub data_alignment_factor: i64,
+    /// The column of the rule recovering the return address
+    pub return_address_register: u16,
+    /// Encoding of the addresses in FDEs ('R')
+    pub fde_encoding: u8,
+    /// Encoding of the LSDA pointers in FDEs ('L')
+    pub lsda_encoding: u8,
+    /// The personality routine ('P')
+    pub personality: Option<Pointer>,
+    /// The frames are signal handlers, whose return address is not after a call ('S')
+    pub signal_frame: bool,
+    /// AArch64 pointer authentication with the B key instead of the A key ('B')
+    pub b_key: bool,
+    /// AArch64 memory tagged stack frames ('G')
+    pub mte_tagged: bool,
+    pub initial_instructions: &'a [u8],
+}
+
+impl<'a> Cie<'a> {
+    /// Parses the body of a CIE, after its length and CIE id. `bases.section` is the
+    /// address of `section`, and `end` the offset of the end of the entry.
+    pub(crate) fn parse(
+        section: &'a [u8],
+        offset: u64,
+        mut r: Reader<'a>,
+        end: usize,
+        bases: &PointerBases,
+        kind: SectionKind,
+    ) -> Result<Cie<'a>, Error> {
+        let version = r.u8()?;
+        if !matches!(version, 1 | 3 | 4) {
+            return Err(Error::Message(format!(
+                "unsupported CIE version {}",
+                version
+            )));
+        }
+
+        let augmentation = r.str()?;
+        // the obsolete "eh" augmentation is followed by the address of exception data
+        if augmentation.starts_with("eh") {
+            r.u64()?;
+        }
+
+        let mut address_size = match kind {
+            SectionKind::EhFrame => 8,
+            SectionKind::DebugFrame { address_size } => address_size,
+        };
+        if version == 4 {
+            address_size = r.u8()?;
+            let segment_size = r.u8()?;
+            if segment_size != 0 {
+                return Err(Error::Message(
+                    "segmented addresses are unsupported".to_string(),
+                ));
+            }
+        }
+
+        let code_alignment_factor = r.uleb128()?;
+        let data_alignment_factor = r.sleb128()?;
+        let return_address_register = if version == 1 {
+            r.u8()? as u16
+        } else {
+            r.uleb128()? as u16
+        };
+
+        let mut cie = Cie {
+            offset,
+            version,
+            augmentation,
+            address_size,
+            code_alignment_factor,
+            data_alignment_factor,
+            return_address_register,
+            fde_encoding: DW_EH_PE_ABSPTR,
+            lsda_encoding: DW_EH_PE_OMIT,
+            personality: None,
+            signal_frame: false,
+            b_key: false,
+            mte_tagged: false,
+            initial_instructions: &[],
+        };
+
+        // addresses in .debug_frame are absolute and of the target's size
+        if matches!(kind, SectionKind::DebugFrame { .. }) && address_size == 4 {
+            cie.fde_encoding = DW_EH_PE_UDATA4;
+        }
+
+        if let Some(letters) = augmentation.strip_prefix('z') {
+            let data_end = augmentation_data_end(&mut r, end)?;
+            for letter in letters.chars() {
+                match letter {
+                    'L' => cie.lsda_encoding = r.u8()?,
+                    'P' => {
+                        let encoding = r.u8()?;
+                        cie.personality = read_pointer(&mut r, encoding, bases)?;
+                    }
+                    'R' => cie.fde_encoding = r.u8()?,
+                    'S' => cie.signal_frame = true,
+                    'B' => cie.b_key = true,
+                    'G' => cie.mte_tagged = true,
+                    // the length lets the rest of the augmentation data be skipped
+                    _ => break,
+                }
+            }
+            r = Reader::at(section, data_end);
+        } else if !augmentation.is_empty() && !augmentation.starts_with("eh") {
+            return Err(Error::Message(format!(
+                "unsupported CIE augmentation {:?}",
+                augmentation
+            )));
+        }
+
+        cie.initial_instructions = section.get(r.offset()..end).ok_or_else(truncated)?;
+        Ok(cie)
+    }
+}
+
+/// Reads the length of the augmentation data of an entry ending at `end`, and returns
+/// the offset of the end of the data.
+fn augmentation_data_end(r: &mut Reader, end: usize) -> Result<usize, Error> {
+    let len = r.uleb128()?;
+    usize::try_from(len)
+        .ok()
+        .and_then(|len| r.offset().checked_add(len))
+        .filter(|&data_end| data_end <= end)
+        .ok_or_else(truncated)
+}
+
+/// A Frame Description Entry, describing how to unwind a range of code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fde<'a> {
+    /// Offset of the entry in its section
+    pub offset: u64,
+    pub cie: Cie<'a>,
+    /// The address of the first instruction described
+    pub initial_location: u64,
+    pub address_range: u64,
+    /// The language-specific data area, e.g. the C++ exception tables of the function
+    pub lsda: Option<Pointer>,
+    pub instructions: &'a [u8],
+    /// The address of `instructions`, for DW_CFA_set_loc with relative encodings
+    pub(crate) instructions_address: u64,
+}
+
+impl<'a> Fde<'a> {
+    /// Parses the body of an FDE, after its length and CIE pointer.
+    pub(crate) fn parse(
+        section: &'a [u8],
+        offset: u64,
+        cie: Cie<'a>,
+        mut r: Reader<'a>,
+        end: usize,
+        bases: &PointerBases,
+    ) -> Result<Fde<'a>, Error> {
+        let initial_location =
+            read_pointer(&mut r, cie.fde_encoding, bases)?.map_or(0, |p| p.address());
+        // the range is a length, so only the format of the encoding applies
+        let address_range = read_encoded_value(&mut r, cie.fde_encoding & 0x0f)?;
+
+        let mut lsda = None;
+        if cie.augmentation.starts_with('z') {
+            let data_end = augmentation_data_end(&mut r, end)?;
+            if cie.augmentation.contains('L') {
+                let bases = PointerBases {
+                    func: Some(initial_location),
+                    ..*bases
+                };
+                lsda = read_pointer(&mut r, cie.lsda_encoding, &bases)?;
+            }
+            r = Reader::at(section, data_end);
+        }
+
+        let instructions = section.get(r.offset()..end).ok_or_else(truncated)?;
+        Ok(Fde {
+            offset,
+            instructions_address: bases.section.wrapping_add(r.offset() as u64),
+            cie,
+            initial_location,
+            address_range,
+            lsda,
+            instructions,
+        })
+    }
+
+    pub fn end(&self) -> u64 {
+        self.initial_location.wrapping_add(self.address_range)
+    }
+
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.initial_location && address < self.end()
+    }
+
+    /// Executes the call frame instructions of the CIE and FDE, returning the rows of
+    /// the unwind table for the described range, in address order.
+    pub fn rows(&self) -> Result<Vec<UnwindRow<'a>>, Error> {
+        let mut rows = vec![];
+        self.execute(|row| {
+            rows.push(row);
+            false
+        })?;
+        Ok(rows)
+    }
+
+    /// The row of the unwind table that applies at an address.
+    pub fn row_for(&self, address: u64) -> Result<Option<UnwindRow<'a>>, Error> {
+        if !self.contains(address) {
+            return Ok(None);
+        }
+        let mut found = None;
+        self.execute(|row| {
+            if address >= row.start && address < row.end {
+                found = Some(row);
+                true
+            } else {
+                false
+            }
+        })?;
+        Ok(found)
+    }
+
+    /// Runs the instructions, passing each completed row to `emit` until it returns true.
+    fn execute<F: FnMut(UnwindRow<'a>) -> bool>(&self, mut emit: F) -> Result<(), Error> {
+        let mut state = RowState {
+            cfa: CfaRule::RegisterOffset {
+                register: 0,
+                offset: 0,
+            },
+            registers: vec![],
+            return_address_signed: false,
+        };
+        // the initial instructions cannot advance the location
+        let mut location = self.initial_location;
+        let mut stack = vec![];
+        execute_instructions(
+            &self.cie,
+            self.cie.initial_instructions,
+            0,
+            None,
+            &mut state,
+            &mut stack,
+            &mut location,
+            &mut |_| false,
+        )?;
+        let initial = state.clone();
+
+        let mut done = false;
+        execute_instructions(
+            &self.cie,
+            self.instructions,
+            self.instructions_address,
+            Some(&initial),
+            &mut state,
+            &mut stack,
+            &mut location,
+            &mut |row| {
+                done = emit(row);
+                done
+            },
+        )?;
+        if !done && location < self.end() {
+            emit(state.row(location, self.end()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RowState<'a> {
+    cfa: CfaRule<'a>,
+    registers: Vec<(u16, RegisterRule<'a>)>,
+    return_address_signed: bool,
+}
+
+impl<'a> RowState<'a> {
+    fn row(&self, start: u64, end: u64) -> UnwindRow<'a> {
+        UnwindRow {
+            start,
+            end,
+            cfa: self.cfa,
+            registers: self.registers.clone(),
+            return_address_signed: self.return_address_signed,
+        }
+    }
+
+    fn set(&mut self, register: u16, rule: Option<RegisterRule<'a>>) {
+        self.registers.retain(|(r, _)| *r != register);
+        if let Some(rule) = rule {
+            self.registers.push((register, rule));
+            self.registers.sort_by_key(|(r, _)| *r);
+        }
+    }
+}
+
+/// The location after advancing by a delta in units of the code alignment factor.
+fn advance(cie: &Cie, location: u64, delta: u64) -> Result<u64, Error> {
+    delta
+        .checked_mul(cie.code_alignment_factor)
+        .and_then(|delta| location.checked_add(delta))
+        .ok_or_else(|| Error::Message("CFA location advanced past the end of memory".to_string()))
+}
+
+/// Executes call frame instructions, emitting a row each time the location advances.
+/// `initial` is the state after the CIE's instructions, which DW_CFA_restore returns to.
+#[allow(clippy::too_many_arguments)]
+fn execute_instructions<'a>(
+    cie: &Cie<'a>,
+    instructions: &'a [u8],
+    address: u64,
+    initial: Option<&RowState<'a>>,
+    state: &mut RowState<'a>,
+    stack: &mut Vec<RowState<'a>>,
+    location: &mut u64,
+    emit: &mut dyn FnMut(UnwindRow<'a>) -> bool,
+) -> Result<(), Error> {
+    let mut r = Reader::new(instructions);
+    let bases = PointerBases {
+        section: address,
+        ..Default::default()
+    };
+    let factored = |offset: u64| (offset as i64).wrapping_mul(cie.data_alignment_factor);
+    let factored_signed = |offset: i64| offset.wrapping_mul(cie.data_alignment_factor);
+
+    while !r.is_empty() {
+        let op = r.u8()?;
+        let mut advance_to = None;
+
+        match (op & 0xc0, op & 0x3f) {
+            (DW_CFA_ADVANCE_LOC, delta) => {
+                advance_to = Some(advance(cie, *location, delta as u64)?)
+            }
+            (DW_CFA_OFFSET, register) => {
+                let offset = factored(r.uleb128()?);
+                state.set(register as u16, Some(RegisterRule::Offset(offset)));
+            }
+            (DW_CFA_RESTORE, register) => restore(state, initial, register as u16),
+            _ => match op {
+                DW_CFA_NOP => {}
+                DW_CFA_SET_LOC => {
+                    let pointer = read_pointer(&mut r, cie.fde_encoding, &bases)?;
+                    advance_to = Some(pointer.map_or(0, |p| p.address()));
+                }
+                DW_CFA_ADVANCE_LOC1 => advance_to = Some(advance(cie, *location, r.u8()? as u64)?),
+                DW_CFA_ADVANCE_LOC2 => advance_to = Some(advance(cie, *location, r.u16()? as u64)?),
+                DW_CFA_ADVANCE_LOC4 => advance_to = Some(advance(cie, *location, r.u32()? as u64)?),
+                DW_CFA_OFFSET_EXTENDED => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored(r.uleb128()?);
+                    state.set(register, Some(RegisterRule::Offset(offset)));
+                }
+                DW_CFA_RESTORE_EXTENDED => {
+                    let register = r.uleb128()? as u16;
+                    restore(state, initial, register);
+                }
+                DW_CFA_UNDEFINED => {
+                    state.set(r.uleb128()? as u16, Some(RegisterRule::Undefined));
+                }
+                DW_CFA_SAME_VALUE => {
+                    state.set(r.uleb128()? as u16, Some(RegisterRule::SameValue));
+                }
+                DW_CFA_REGISTER => {
+                    let register = r.uleb128()? as u16;
+                    let other = r.uleb128()? as u16;
+                    state.set(register, Some(RegisterRule::Register(other)));
+                }
+                DW_CFA_REMEMBER_STATE => stack.push(state.clone()),
+                DW_CFA_RESTORE_STATE => {
+                    let saved = stack.pop().ok_or_else(|| {
+                        Error::Message("DW_CFA_restore_state without saved state".to_string())
+                    })?;
+                    // the location is not part of the saved state
+                    *state = saved;
+                }
+                DW_CFA_DEF_CFA => {
+                    let register = r.uleb128()? as u16;
+                    let offset = r.uleb128()? as i64;
+                    state.cfa = CfaRule::RegisterOffset { register, offset };
+                }
+                DW_CFA_DEF_CFA_SF => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored_signed(r.sleb128()?);
+                    state.cfa = CfaRule::RegisterOffset { register, offset };
+                }
+                DW_CFA_DEF_CFA_REGISTER => {
+                    let register = r.uleb128()? as u16;
+                    state.cfa = match state.cfa {
+                        CfaRule::RegisterOffset { offset, .. } => {
+                            CfaRule::RegisterOffset { register, offset }
+                        }
+                        CfaRule::Expression(_) => CfaRule::RegisterOffset {
+                            register,
+                            offset: 0,
+                        },
+                    };
+                }
+                DW_CFA_DEF_CFA_OFFSET | DW_CFA_DEF_CFA_OFFSET_SF => {
+                    let offset = if op == DW_CFA_DEF_CFA_OFFSET {
+                        r.uleb128()? as i64
+                    } else {
+                        factored_signed(r.sleb128()?)
+                    };
+                    match &mut state.cfa {
+                        CfaRule::RegisterOffset { offset: o, .. } => *o = offset,
+                        CfaRule::Expression(_) => {
+                            return Err(Error::Message(
+                                "CFA offset set on an expression rule".to_string(),
+                            ))
+                        }
+                    }
+                }
+                DW_CFA_DEF_CFA_EXPRESSION => {
+                    let len = r.uleb128()? as usize;
+                    state.cfa = CfaRule::Expression(r.bytes(len)?);
+                }
+                DW_CFA_EXPRESSION | DW_CFA_VAL_EXPRESSION => {
+                    let register = r.uleb128()? as u16;
+                    let len = r.uleb128()? as usize;
+                    let expr = r.bytes(len)?;
+                    let rule = if op == DW_CFA_EXPRESSION {
+                        RegisterRule::Expression(expr)
+                    } else {
+                        RegisterRule::ValExpression(expr)
+                    };
+                    state.set(register, Some(rule));
+                }
+                DW_CFA_OFFSET_EXTENDED_SF => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored_signed(r.sleb128()?);
+                    state.set(register, Some(RegisterRule::Offset(offset)));
+                }
+                DW_CFA_VAL_OFFSET => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored(r.uleb128()?);
+                    state.set(register, Some(RegisterRule::ValOffset(offset)));
+                }
+                DW_CFA_VAL_OFFSET_SF => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored_signed(r.sleb128()?);
+                    state.set(register, Some(RegisterRule::ValOffset(offset)));
+                }
+                // shares its opcode with DW_CFA_GNU_window_save, which is SPARC only
+                DW_CFA_AARCH64_NEGATE_RA_STATE => {
+                    state.return_address_signed = !state.return_address_signed
+                }
+                DW_CFA_GNU_ARGS_SIZE => {
+                    r.uleb128()?;
+                }
+                DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED => {
+                    let register = r.uleb128()? as u16;
+                    let offset = factored(r.uleb128()?).wrapping_neg();
+                    state.set(register, Some(RegisterRule::Offset(offset)));
+                }
+                _ => {
+                    return Err(Error::Message(format!(
+                        "unknown call frame instruction 0x{:02x}",
+                        op
+                    )))
+                }
+            },
+        }
+
+        if let Some(next) = advance_to {
+            if initial.is_none() {
+                return Err(Error::Message(
+                    "CIE initial instructions cannot advance the location".to_string(),
+                ));
+            }
+            if next > *location && emit(state.row(*location, next)) {
+                return Ok(());
+            }
+            *location = next;
+        }
+    }
+    Ok(())
+}
+
+fn restore<'a>(state: &mut RowState<'a>, initial: Option<&RowState<'a>>, register: u16) {
+    let rule = initial.and_then(|initial| {
+        initial
+            .registers
+            .iter()
+            .find(|(r, _)| *r == register)
+            .map(|(_, rule)| *rule)
+    });
+    state.set(register, rule);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unwind::{debug_frame::DebugFrame, eh_frame::EhFrame};
+
+    /// An entry with a length of 12 and the given CIE id or pointer, padded with zeros.
+    fn entry(id: u32) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.extend_from_slice(&12u32.to_le_bytes());
+        buf.extend_from_slice(&id.to_le_bytes());
+        buf.extend_from_slice(&[0; 8]);
+        buf
+    }
+
+    #[test]
+    fn eh_frame_fde_pointing_at_itself() {
+        let data = entry(4);
+        let eh_frame = EhFrame::new(&data, 0);
+        assert!(eh_frame.entries().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn debug_frame_fde_pointing_at_itself() {
+        let data = entry(0);
+        let debug_frame = DebugFrame::new(&data, 8);
+        assert!(debug_frame.entries().next().unwrap().is_err());
+    }
+
+    #[test]
+    fn debug_frame_fdes_pointing_at_each_other() {
+        let mut data = entry(16);
+        data.extend(entry(0));
+        let debug_frame = DebugFrame::new(&data, 8);
+        assert!(debug_frame.entries().next().unwrap().is_err());
+        assert!(debug_frame.fde_at(16).is_err());
+    }
+
+    #[test]
+    fn debug_frame_version_1_uses_target_address_size() {
+        let mut data = vec![];
+        // CIE: version 1, no augmentation, factors 1 and -4, lr, DW_CFA_def_cfa sp, 0
+        data.extend_from_slice(&12u32.to_le_bytes());
+        data.extend_from_slice(&[0xff, 0xff, 0xff, 0xff, 1, 0, 1, 0x7c, 14, 0x0c, 13, 0]);
+        // FDE with 32-bit initial location and address range
+        data.extend_from_slice(&12u32.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0x1000u32.to_le_bytes());
+        data.extend_from_slice(&0x20u32.to_le_bytes());
+
+        let fde = DebugFrame::new(&data, 4).fde_at(16).unwrap();
+        assert_eq!(fde.cie.address_size, 4);
+        assert_eq!(fde.initial_location, 0x1000);
+        assert_eq!(fde.address_range, 0x20);
+        assert!(DebugFrame::new(&data, 8).fde_at(16).is_err());
+    }
+}
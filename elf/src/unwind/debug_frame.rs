@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+
+use crate::raw::{compression::SectionStorage, header::Headers, Error, ELF_CLASS_32};
+
+use super::cfi::{parse_entry, CfiEntry, CfiSection, SectionKind};
+
+/// The `.debug_frame` section: call frame information in the DWARF debugging format.
+/// Unlike `.eh_frame` it is not loaded at runtime, so it is often only present in
+/// unstripped objects or separate debug files.
+#[derive(Debug, Clone, Copy)]
+pub struct DebugFrame<'a> {
+    data: &'a [u8],
+    address_size: u8,
+}
+
+impl<'a> DebugFrame<'a> {
+    /// `address_size` is the size of target addresses in bytes, which CIEs before
+    /// version 4 do not record, e.g. 4 for 32-bit ARM.
+    pub fn new(data: &'a [u8], address_size: u8) -> DebugFrame<'a> {
+        DebugFrame { data, address_size }
+    }
+
+    /// Borrows the `.debug_frame` section, if the object has one. Fails if the section
+    /// is compressed, in which case `load_with` decompresses it into a `SectionStorage`.
+    pub fn load<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+    ) -> Result<Option<DebugFrame<'a>>, Error> {
+        match elf.find_section_data_by_name(buf, ".debug_frame")? {
+            Some(Cow::Borrowed(data)) => Ok(Some(DebugFrame::new(data, address_size(elf)))),
+            Some(Cow::Owned(_)) => Err(Error::Message(
+                "section .debug_frame is compressed".to_string(),
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the `.debug_frame` section, decompressing it into `storage` if needed.
+    pub fn load_with<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+        storage: &'a SectionStorage,
+    ) -> Result<Option<DebugFrame<'a>>, Error> {
+        Ok(elf
+            .find_section_data_by_name(buf, ".debug_frame")?
+            .map(|data| DebugFrame::new(storage.store(data), address_size(elf))))
+    }
+}
+
+/// The size of addresses in an object, from its ELF class.
+pub(crate) fn address_size(elf: &Headers) -> u8 {
+    if elf.header.e_ident.class == ELF_CLASS_32 {
+        4
+    } else {
+        8
+    }
+}
+
+impl<'a> CfiSection<'a> for DebugFrame<'a> {
+    fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn entry_at(&self, offset: u64) -> Result<Option<(CfiEntry<'a>, u64)>, Error> {
+        // addresses are absolute, so the section's own address is never needed
+        let kind = SectionKind::DebugFrame {
+            address_size: self.address_size,
+        };
+        parse_entry(self.data, 0, offset, kind)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::raw::{header::Headers, mapping::AddressMap, Error};
+
+use super::{
+    cfi::{parse_entry, CfiEntry, CfiSection, Fde, SectionKind},
+    eh_frame_hdr::EhFrameHdr,
+};
+
+/// The `.eh_frame` section: the call frame information used to unwind the stack
+/// for exceptions, which unlike `.debug_frame` is loaded at runtime.
+#[derive(Debug, Clone, Copy)]
+pub struct EhFrame<'a> {
+    data: &'a [u8],
+    address: u64,
+}
+
+impl<'a> EhFrame<'a> {
+    /// `address` is the virtual address of `data`, which pc-relative pointers are relative to.
+    pub fn new(data: &'a [u8], address: u64) -> EhFrame<'a> {
+        EhFrame { data, address }
+    }
+
+    /// Reads the `.eh_frame` section, if the object has one.
+    pub fn load<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers) -> Result<Option<EhFrame<'a>>, Error> {
+        match elf.find_section_header_by_name(".eh_frame") {
+            Some(hdr) => Ok(Some(EhFrame::new(
+                hdr.get_section_buffer(buf)?,
+                hdr.sh_addr,
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads `.eh_frame` through the pointer in `.eh_frame_hdr`, for objects without
+    /// section headers. The section ends at its zero terminator, so the data extends
+    /// to the end of the segment.
+    pub fn load_from_hdr<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers<'a>,
+        hdr: &EhFrameHdr,
+    ) -> Result<EhFrame<'a>, Error> {
+        let address = hdr.eh_frame_ptr;
+        let map = AddressMap::new(buf, elf);
+        let ph = map
+            .find_segment(address)
+            .ok_or(Error::UnmappedAddress(address))?;
+        // find_segment guarantees address >= p_vaddr
+        let size = ph.get_filesz().saturating_sub(address - ph.get_vaddr());
+        Ok(EhFrame::new(map.read_file_backed(address, size)?, address))
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Parses the FDE at a virtual address, as given by the `.eh_frame_hdr` table.
+    pub fn fde_at_address(&self, address: u64) -> Result<Fde<'a>, Error> {
+        self.fde_at(address.wrapping_sub(self.address))
+    }
+}
+
+impl<'a> CfiSection<'a> for EhFrame<'a> {
+    fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn entry_at(&self, offset: u64) -> Result<Option<(CfiEntry<'a>, u64)>, Error> {
+        parse_entry(self.data, self.address, offset, SectionKind::EhFrame)
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{
+    dwarf::reader::Reader,
+    raw::{header::Headers, Error, PT_GNU_EH_FRAME},
+};
+
+use super::{
+    cfi::{read_pointer, PointerBases},
+    *,
+};
+
+/// The `.eh_frame_hdr` section, pointed to by the PT_GNU_EH_FRAME segment. It locates
+/// `.eh_frame` and holds a table of FDEs sorted by address for binary search.
+#[derive(Debug, Clone, Copy)]
+pub struct EhFrameHdr<'a> {
+    data: &'a [u8],
+    address: u64,
+    pub version: u8,
+    /// The address of `.eh_frame`
+    pub eh_frame_ptr: u64,
+    pub fde_count: u64,
+    pub table_encoding: u8,
+    table_offset: usize,
+}
+
+impl<'a> EhFrameHdr<'a> {
+    /// `address` is the virtual address of `data`.
+    pub fn parse(data: &'a [u8], address: u64) -> Result<EhFrameHdr<'a>, Error> {
+        let mut r = Reader::new(data);
+        let version = r.u8()?;
+        if version != 1 {
+            return Err(Error::Message(format!(
+                "unsupported .eh_frame_hdr version {}",
+                version
+            )));
+        }
+        let eh_frame_ptr_encoding = r.u8()?;
+        let fde_count_encoding = r.u8()?;
+        let table_encoding = r.u8()?;
+
+        let bases = PointerBases {
+            section: address,
+            data: Some(address),
+            ..Default::default()
+        };
+        let eh_frame_ptr =
+            read_pointer(&mut r, eh_frame_ptr_encoding, &bases)?.map_or(0, |p| p.address());
+        let fde_count =
+            read_pointer(&mut r, fde_count_encoding, &bases)?.map_or(0, |p| p.address());
+
+        let mut hdr = EhFrameHdr {
+            data,
+            address,
+            version,
+            eh_frame_ptr,
+            fde_count: 0,
+            table_encoding,
+            table_offset: r.offset(),
+        };
+        if table_encoding != DW_EH_PE_OMIT {
+            // a count larger than the table is truncated to the entries that are present
+            let entries = match hdr.entry_size() {
+                Some(size) => ((data.len() - hdr.table_offset) / size) as u64,
+                None => u64::MAX,
+            };
+            hdr.fde_count = fde_count.min(entries);
+        }
+        Ok(hdr)
+    }
+
+    /// Reads `.eh_frame_hdr` through its section, or through the PT_GNU_EH_FRAME segment
+    /// for objects without section headers.
+    pub fn load<A: AsRef<[u8]>>(
+        buf: &'a A,
+        elf: &Headers,
+    ) -> Result<Option<EhFrameHdr<'a>>, Error> {
+        if let Some(hdr) = elf.find_section_header_by_name(".eh_frame_hdr") {
+            return EhFrameHdr::parse(hdr.get_section_buffer(buf)?, hdr.sh_addr).map(Some);
+        }
+
+        let ph = match elf
+            .program_headers
+            .iter()
+            .find(|ph| ph.get_type() == PT_GNU_EH_FRAME)
+        {
+            Some(ph) => ph,
+            None => return Ok(None),
+        };
+        let start = ph.get_offset() as usize;
+        let data = start
+            .checked_add(ph.get_filesz() as usize)
+            .and_then(|end| buf.as_ref().get(start..end))
+            .ok_or_else(|| Error::Message("invalid PT_GNU_EH_FRAME segment".to_string()))?;
+        EhFrameHdr::parse(data, ph.get_vaddr()).map(Some)
+    }
+
+    /// The size of a table entry, if entries have a fixed size and can be searched.
+    fn entry_size(&self) -> Option<usize> {
+        match self.table_encoding & 0x0f {
+            DW_EH_PE_UDATA2 | DW_EH_PE_SDATA2 => Some(4),
+            DW_EH_PE_UDATA4 | DW_EH_PE_SDATA4 => Some(8),
+            DW_EH_PE_ABSPTR | DW_EH_PE_UDATA8 | DW_EH_PE_SDATA8 => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Whether the section has a table that `find_fde` can search.
+    pub fn has_table(&self) -> bool {
+        self.fde_count > 0 && self.entry_size().is_some()
+    }
+
+    /// The initial location of the function and the address of its FDE, for an
+    /// entry of the table.
+    pub fn entry(&self, index: u64) -> Result<(u64, u64), Error> {
+        let size = self.entry_size().ok_or_else(|| {
+            Error::Message("variable-size .eh_frame_hdr entries cannot be indexed".to_string())
+        })?;
+        if index >= self.fde_count {
+            return Err(Error::Message(format!(
+                "FDE table index {} is out of bounds",
+                index
+            )));
+        }
+
+        let mut r = Reader::at(self.data, self.table_offset + index as usize * size);
+        let bases = PointerBases {
+            section: self.address,
+            data: Some(self.address),
+            ..Default::default()
+        };
+        let location =
+            read_pointer(&mut r, self.table_encoding, &bases)?.map_or(0, |p| p.address());
+        let fde = read_pointer(&mut r, self.table_encoding, &bases)?.map_or(0, |p| p.address());
+        Ok((location, fde))
+    }
+
+    /// Finds the address of the last FDE starting at or before an address. The FDE
+    /// must still be checked to cover the address.
+    pub fn find_fde(&self, address: u64) -> Result<Option<u64>, Error> {
+        let (mut low, mut high) = (0, self.fde_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.entry(mid)?.0 <= address {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.entry(low - 1)?.1))
+    }
+}
@@ -0,0 +1,586 @@
+use crate::{dwarf::reader::truncated, raw::Error};
+
+use super::*;
+
+/// `.ARM.exidx` entry data marking a function that cannot be unwound.
+pub const EXIDX_CANTUNWIND: u32 = 1;
+
+/// DWARF register number of VFP register d0 on 32-bit ARM.
+pub const ARM_D0: u16 = 256;
+/// DWARF register number of iWMMXt register wR0 on 32-bit ARM.
+pub const ARM_WR0: u16 = 112;
+/// DWARF register number of iWMMXt control register wCGR0 on 32-bit ARM.
+pub const ARM_WCGR0: u16 = 104;
+
+/// How to unwind a function described by the ARM exception handling ABI.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EhabiData<'a> {
+    /// EXIDX_CANTUNWIND
+    CantUnwind,
+    /// The ARM compact model, handled by the personality routine
+    /// `__aeabi_unwind_cpp_pr<personality_index>`
+    Compact {
+        personality_index: u8,
+        opcodes: Vec<u8>,
+        /// Descriptors of the exception handling scopes for pr1 and pr2, up to
+        /// the end of `.ARM.extab`
+        descriptors: &'a [u8],
+    },
+    /// A generic personality routine, such as `__gxx_personality_v0`. The unwind
+    /// opcodes follow it in the layout GCC uses.
+    Generic {
+        personality: u64,
+        opcodes: Vec<u8>,
+        /// The language-specific data after the opcodes, up to the end of `.ARM.extab`
+        data: &'a [u8],
+    },
+}
+
+/// An entry of the `.ARM.exidx` table, covering a function up to the next entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExidxEntry<'a> {
+    /// The address of the first instruction of the function
+    pub function: u64,
+    /// The address of the next entry's function
+    pub end: u64,
+    pub data: EhabiData<'a>,
+}
+
+impl<'a> ExidxEntry<'a> {
+    pub fn contains(&self, address: u64) -> bool {
+        address >= self.function && address < self.end
+    }
+
+    pub fn opcodes(&self) -> &[u8] {
+        match &self.data {
+            EhabiData::CantUnwind => &[],
+            EhabiData::Compact { opcodes, .. } | EhabiData::Generic { opcodes, .. } => opcodes,
+        }
+    }
+
+    /// Translates the unwind opcodes into a row of rules that recovers the caller's
+    /// registers, with the caller's program counter in the `ARM_PC` column. Returns
+    /// `None` for functions that cannot be unwound.
+    pub fn row(&self) -> Result<Option<UnwindRow<'static>>, Error> {
+        if self.data == EhabiData::CantUnwind {
+            return Ok(None);
+        }
+        let mut row = match opcodes_to_row(self.opcodes())? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        row.start = self.function;
+        row.end = self.end;
+        Ok(Some(row))
+    }
+}
+
+/// The `.ARM.exidx` index table of 32-bit ARM objects and the `.ARM.extab` entries
+/// it refers to. The table has an entry for each function, sorted by address.
+///
+/// `Headers` only reads 64-bit objects, so the contents and addresses of the sections
+/// are passed in directly.
+#[derive(Debug, Clone, Copy)]
+pub struct ArmExidx<'a> {
+    exidx: &'a [u8],
+    exidx_address: u64,
+    extab: &'a [u8],
+    extab_address: u64,
+}
+
+impl<'a> ArmExidx<'a> {
+    /// `extab` may be empty when every entry is inline.
+    pub fn new(
+        exidx: &'a [u8],
+        exidx_address: u64,
+        extab: &'a [u8],
+        extab_address: u64,
+    ) -> ArmExidx<'a> {
+        ArmExidx {
+            exidx,
+            exidx_address,
+            extab,
+            extab_address,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.exidx.len() / 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn exidx_word(&self, index: usize, word: usize) -> Result<(u32, u64), Error> {
+        let offset = index * 8 + word * 4;
+        let bytes = self.exidx.get(offset..offset + 4).ok_or_else(truncated)?;
+        Ok((
+            u32::from_le_bytes(bytes.try_into().unwrap()),
+            self.exidx_address + offset as u64,
+        ))
+    }
+
+    fn function(&self, index: usize) -> Result<u64, Error> {
+        let (word, place) = self.exidx_word(index, 0)?;
+        Ok(prel31(word, place))
+    }
+
+    pub fn entry(&self, index: usize) -> Result<ExidxEntry<'a>, Error> {
+        if index >= self.len() {
+            return Err(Error::Message(format!(
+                ".ARM.exidx index {} is out of bounds",
+                index
+            )));
+        }
+
+        let function = self.function(index)?;
+        let end = if index + 1 < self.len() {
+            self.function(index + 1)?
+        } else {
+            u64::MAX
+        };
+
+        let (word, place) = self.exidx_word(index, 1)?;
+        let data = if word == EXIDX_CANTUNWIND {
+            EhabiData::CantUnwind
+        } else if word & 0x8000_0000 != 0 {
+            // the compact model inline, which only fits personality routine 0
+            let personality_index = ((word >> 24) & 0x0f) as u8;
+            if personality_index != 0 {
+                return Err(Error::Message(format!(
+                    "invalid inline .ARM.exidx personality index {}",
+                    personality_index
+                )));
+            }
+            EhabiData::Compact {
+                personality_index,
+                opcodes: word.to_be_bytes()[1..].to_vec(),
+                descriptors: &[],
+            }
+        } else {
+            self.extab_entry(prel31(word, place))?
+        };
+
+        Ok(ExidxEntry {
+            function,
+            end,
+            data,
+        })
+    }
+
+    fn extab_entry(&self, address: u64) -> Result<EhabiData<'a>, Error> {
+        let offset = address
+            .checked_sub(self.extab_address)
+            .map(|offset| offset as usize)
+            .filter(|&offset| offset < self.extab.len())
+            .ok_or(Error::UnmappedAddress(address))?;
+        let words = &self.extab[offset..];
+        let word = |index: usize| -> Result<u32, Error> {
+            let bytes = words.get(index * 4..index * 4 + 4).ok_or_else(truncated)?;
+            Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+        };
+
+        let first = word(0)?;
+        if first & 0x8000_0000 != 0 {
+            let personality_index = ((first >> 24) & 0x0f) as u8;
+            let (opcodes, count) = match personality_index {
+                0 => (first.to_be_bytes()[1..].to_vec(), 0),
+                1 | 2 => {
+                    let count = ((first >> 16) & 0xff) as usize;
+                    let mut opcodes = first.to_be_bytes()[2..].to_vec();
+                    for index in 1..=count {
+                        opcodes.extend(word(index)?.to_be_bytes());
+                    }
+                    (opcodes, count)
+                }
+                _ => {
+                    return Err(Error::Message(format!(
+                        "unknown EHABI personality index {}",
+                        personality_index
+                    )))
+                }
+            };
+            return Ok(EhabiData::Compact {
+                personality_index,
+                opcodes,
+                descriptors: &words[(count + 1) * 4..],
+            });
+        }
+
+        let personality = prel31(first, address);
+        let second = word(1)?;
+        let count = (second >> 24) as usize;
+        let mut opcodes = second.to_be_bytes()[1..].to_vec();
+        for index in 2..2 + count {
+            opcodes.extend(word(index)?.to_be_bytes());
+        }
+        Ok(EhabiData::Generic {
+            personality,
+            opcodes,
+            data: &words[(count + 2) * 4..],
+        })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = Result<ExidxEntry<'a>, Error>> + '_ {
+        (0..self.len()).map(|index| self.entry(index))
+    }
+
+    /// Finds the entry of the function containing an address by binary search.
+    pub fn find(&self, address: u64) -> Result<Option<ExidxEntry<'a>>, Error> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.function(mid)? <= address {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        if low == 0 {
+            return Ok(None);
+        }
+        self.entry(low - 1).map(Some)
+    }
+}
+
+/// Resolves a 31-bit place-relative offset.
+fn prel31(word: u32, place: u64) -> u64 {
+    let offset = ((word << 1) as i32 >> 1) as i64;
+    (place as i64).wrapping_add(offset) as u32 as u64
+}
+
+/// Translates EHABI unwind opcodes into CFA and register rules. The opcodes adjust and
+/// pop from a virtual stack pointer, which starts as `sp` and ends as the caller's `sp`,
+/// i.e. the CFA. Returns `None` for the "refuse to unwind" opcode.
+pub fn opcodes_to_row(opcodes: &[u8]) -> Result<Option<UnwindRow<'static>>, Error> {
+    let mut base = ARM_SP;
+    let mut vsp: i64 = 0;
+    // registers with their offset from the base register
+    let mut saved: Vec<(u16, i64)> = vec![];
+
+    let spare = |op: u8| Error::Message(format!("spare EHABI unwind opcode 0x{:02x}", op));
+    let pop = |saved: &mut Vec<(u16, i64)>, vsp: &mut i64, register: u16, size: i64| {
+        saved.retain(|(r, _)| *r != register);
+        saved.push((register, *vsp));
+        *vsp += size;
+    };
+
+    let mut iter = opcodes.iter().copied();
+    let mut next = |op: u8| iter.next().ok_or_else(|| spare(op));
+    while let Ok(op) = next(0) {
+        match op {
+            0x00..=0x3f => vsp += ((op as i64 & 0x3f) << 2) + 4,
+            0x40..=0x7f => vsp -= ((op as i64 & 0x3f) << 2) + 4,
+            0x80..=0x8f => {
+                let mask = ((op as u16 & 0x0f) << 8) | next(op)? as u16;
+                if mask == 0 {
+                    return Ok(None);
+                }
+                for bit in 0..12 {
+                    if mask & (1 << bit) != 0 {
+                        let register = 4 + bit;
+                        if register == ARM_SP {
+                            return Err(Error::Message(
+                                "popping sp in EHABI opcodes is unsupported".to_string(),
+                            ));
+                        }
+                        pop(&mut saved, &mut vsp, register, 4);
+                    }
+                }
+            }
+            0x90..=0x9f => {
+                let register = (op & 0x0f) as u16;
+                if register == ARM_SP || register == ARM_PC {
+                    return Err(spare(op));
+                }
+                if !saved.is_empty() {
+                    return Err(Error::Message(
+                        "EHABI opcodes set vsp after popping registers".to_string(),
+                    ));
+                }
+                base = register;
+                vsp = 0;
+            }
+            0xa0..=0xaf => {
+                for register in 4..=4 + (op & 0x07) as u16 {
+                    pop(&mut saved, &mut vsp, register, 4);
+                }
+                if op & 0x08 != 0 {
+                    pop(&mut saved, &mut vsp, ARM_LR, 4);
+                }
+            }
+            0xb0 => break,
+            0xb1 => {
+                let mask = next(op)?;
+                if mask == 0 || mask & 0xf0 != 0 {
+                    return Err(spare(op));
+                }
+                for register in 0..4 {
+                    if mask & (1 << register) != 0 {
+                        pop(&mut saved, &mut vsp, register, 4);
+                    }
+                }
+            }
+            0xb2 => {
+                let mut value = 0u64;
+                let mut shift = 0;
+                loop {
+                    let byte = next(op)?;
+                    if shift < 64 {
+                        value |= ((byte & 0x7f) as u64) << shift;
+                    }
+                    shift += 7;
+                    if byte & 0x80 == 0 {
+                        break;
+                    }
+                }
+                vsp += 0x204 + ((value as i64) << 2);
+            }
+            // FSTMFDX pushes an extra word after the registers
+            0xb3 => {
+                let byte = next(op)?;
+                let first = (byte >> 4) as u16;
+                for register in first..=first + (byte & 0x0f) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_D0 + register, 8);
+                }
+                vsp += 4;
+            }
+            0xb8..=0xbf => {
+                for register in 8..=8 + (op & 0x07) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_D0 + register, 8);
+                }
+                vsp += 4;
+            }
+            0xc0..=0xc5 => {
+                for register in 10..=10 + (op & 0x07) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_WR0 + register, 8);
+                }
+            }
+            0xc6 => {
+                let byte = next(op)?;
+                let first = (byte >> 4) as u16;
+                for register in first..=first + (byte & 0x0f) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_WR0 + register, 8);
+                }
+            }
+            0xc7 => {
+                let mask = next(op)?;
+                if mask == 0 || mask & 0xf0 != 0 {
+                    return Err(spare(op));
+                }
+                for register in 0..4 {
+                    if mask & (1 << register) != 0 {
+                        pop(&mut saved, &mut vsp, ARM_WCGR0 + register, 4);
+                    }
+                }
+            }
+            0xc8 | 0xc9 => {
+                let byte = next(op)?;
+                let first = (byte >> 4) as u16 + if op == 0xc8 { 16 } else { 0 };
+                for register in first..=first + (byte & 0x0f) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_D0 + register, 8);
+                }
+            }
+            0xd0..=0xd7 => {
+                for register in 8..=8 + (op & 0x07) as u16 {
+                    pop(&mut saved, &mut vsp, ARM_D0 + register, 8);
+                }
+            }
+            _ => return Err(spare(op)),
+        }
+    }
+
+    let mut registers: Vec<(u16, RegisterRule)> = saved
+        .iter()
+        .map(|&(register, offset)| (register, RegisterRule::Offset(offset - vsp)))
+        .collect();
+    // without a popped pc, the function returns to the (possibly restored) lr
+    if !saved.iter().any(|(r, _)| *r == ARM_PC) {
+        let rule = registers
+            .iter()
+            .find(|(r, _)| *r == ARM_LR)
+            .map_or(RegisterRule::Register(ARM_LR), |(_, rule)| *rule);
+        registers.push((ARM_PC, rule));
+    }
+    registers.sort_by_key(|(r, _)| *r);
+
+    Ok(Some(UnwindRow {
+        start: 0,
+        end: u64::MAX,
+        cfa: CfaRule::RegisterOffset {
+            register: base,
+            offset: vsp,
+        },
+        registers,
+        return_address_signed: false,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cfa: i64, registers: &[(u16, RegisterRule<'static>)]) -> UnwindRow<'static> {
+        UnwindRow {
+            start: 0,
+            end: u64::MAX,
+            cfa: CfaRule::RegisterOffset {
+                register: ARM_SP,
+                offset: cfa,
+            },
+            registers: registers.to_vec(),
+            return_address_signed: false,
+        }
+    }
+
+    #[test]
+    fn opcodes() {
+        use RegisterRule::{Offset, Register};
+        let lr = Register(ARM_LR);
+        let cases: &[(&[u8], UnwindRow)] = &[
+            // pop {r4, pc}
+            (
+                &[0x88, 0x01],
+                row(8, &[(4, Offset(-8)), (ARM_PC, Offset(-4))]),
+            ),
+            // pop {r4, lr}
+            (
+                &[0xa8],
+                row(
+                    8,
+                    &[(4, Offset(-8)), (ARM_LR, Offset(-4)), (ARM_PC, Offset(-4))],
+                ),
+            ),
+            // pop {r0, r1}, then finish before the trailing opcode
+            (
+                &[0xb1, 0x03, 0xb0, 0xa8],
+                row(8, &[(0, Offset(-8)), (1, Offset(-4)), (ARM_PC, lr)]),
+            ),
+            // vsp += 0x204 + (uleb128 << 2)
+            (&[0xb2, 0x01], row(0x208, &[(ARM_PC, lr)])),
+            (&[0xb2, 0x81, 0x01], row(0x408, &[(ARM_PC, lr)])),
+            // FSTMFDX {d1-d3}, with its extra word
+            (
+                &[0xb3, 0x12],
+                row(
+                    28,
+                    &[
+                        (ARM_PC, lr),
+                        (ARM_D0 + 1, Offset(-28)),
+                        (ARM_D0 + 2, Offset(-20)),
+                        (ARM_D0 + 3, Offset(-12)),
+                    ],
+                ),
+            ),
+            // VPOP {d16-d17}
+            (
+                &[0xc8, 0x01],
+                row(
+                    16,
+                    &[
+                        (ARM_PC, lr),
+                        (ARM_D0 + 16, Offset(-16)),
+                        (ARM_D0 + 17, Offset(-8)),
+                    ],
+                ),
+            ),
+        ];
+        for (opcodes, expected) in cases {
+            assert_eq!(
+                opcodes_to_row(opcodes).unwrap().as_ref(),
+                Some(expected),
+                "{:02x?}",
+                opcodes
+            );
+        }
+
+        // refuse to unwind
+        assert_eq!(opcodes_to_row(&[0x80, 0x00]).unwrap(), None);
+        // spare encodings and truncated opcodes
+        for opcodes in [&[0xb1, 0x00][..], &[0xb1, 0x10], &[0x80], &[0xb2, 0x80]] {
+            assert!(opcodes_to_row(opcodes).is_err(), "{:02x?}", opcodes);
+        }
+    }
+
+    /// A prel31 word at `place` referring to `target`.
+    fn prel31_to(target: u64, place: u64) -> u32 {
+        target.wrapping_sub(place) as u32 & 0x7fff_ffff
+    }
+
+    #[test]
+    fn entries() {
+        let (exidx_address, extab_address) = (0x1000, 0x2000);
+        let exidx_words = [
+            prel31_to(0x800, 0x1000),
+            0x80a8_b0b0,
+            prel31_to(0x900, 0x1008),
+            prel31_to(0x2000, 0x100c),
+            prel31_to(0xa00, 0x1010),
+            EXIDX_CANTUNWIND,
+            prel31_to(0xb00, 0x1018),
+            prel31_to(0x2010, 0x101c),
+        ];
+        let extab_words = [
+            // a generic personality routine with one extra word of opcodes, then data
+            prel31_to(0x3000, 0x2000),
+            0x01a8_b0b0,
+            0xb0b0_b0b0,
+            0xdead_beef,
+            // personality routine 1 with one extra word of opcodes, then descriptors
+            0x8101_a8b0,
+            0xb0b0_b0b0,
+            0,
+        ];
+        let exidx: Vec<u8> = exidx_words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let extab: Vec<u8> = extab_words.iter().flat_map(|w| w.to_le_bytes()).collect();
+        let table = ArmExidx::new(&exidx, exidx_address, &extab, extab_address);
+        assert_eq!(table.len(), 4);
+
+        let inline = table.entry(0).unwrap();
+        assert_eq!((inline.function, inline.end), (0x800, 0x900));
+        assert_eq!(
+            inline.data,
+            EhabiData::Compact {
+                personality_index: 0,
+                opcodes: vec![0xa8, 0xb0, 0xb0],
+                descriptors: &[],
+            }
+        );
+        assert_eq!(inline.row().unwrap().unwrap().start, 0x800);
+
+        let generic = table.entry(1).unwrap();
+        assert_eq!((generic.function, generic.end), (0x900, 0xa00));
+        match &generic.data {
+            EhabiData::Generic {
+                personality,
+                opcodes,
+                data,
+            } => {
+                assert_eq!(*personality, 0x3000);
+                assert_eq!(opcodes, &[0xa8, 0xb0, 0xb0, 0xb0, 0xb0, 0xb0, 0xb0]);
+                assert_eq!(&data[..4], &0xdead_beefu32.to_le_bytes());
+            }
+            data => panic!("unexpected entry data {:?}", data),
+        }
+
+        let cant_unwind = table.entry(2).unwrap();
+        assert_eq!(cant_unwind.data, EhabiData::CantUnwind);
+        assert_eq!(cant_unwind.row().unwrap(), None);
+
+        let compact = table.entry(3).unwrap();
+        assert_eq!((compact.function, compact.end), (0xb00, u64::MAX));
+        assert_eq!(
+            compact.data,
+            EhabiData::Compact {
+                personality_index: 1,
+                opcodes: vec![0xa8, 0xb0, 0xb0, 0xb0, 0xb0, 0xb0],
+                descriptors: &[0; 4],
+            }
+        );
+
+        assert_eq!(table.find(0x7ff).unwrap(), None);
+        assert_eq!(table.find(0x950).unwrap().unwrap().function, 0x900);
+        assert!(table.entry(4).is_err());
+    }
+}
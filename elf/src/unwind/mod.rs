@@ -0,0 +1,468 @@
+use crate::{
+    coredump::{registers::Registers, CoreDump},
+    dwarf::expression::{self, ExpressionContext},
+    raw::{header::Headers, mapping::AddressMap, Error, EM_AARCH64, EM_ARM, EM_X86_64},
+};
+
+use self::{
+    cfi::{CfiSection, Fde},
+    debug_frame::DebugFrame,
+    eh_frame::EhFrame,
+    eh_frame_hdr::EhFrameHdr,
+};
+
+pub mod cfi;
+pub mod debug_frame;
+pub mod eh_frame;
+pub mod eh_frame_hdr;
+pub mod ehabi;
+
+pub const DW_CFA_ADVANCE_LOC: u8 = 0x40;
+pub const DW_CFA_OFFSET: u8 = 0x80;
+pub const DW_CFA_RESTORE: u8 = 0xc0;
+pub const DW_CFA_NOP: u8 = 0x00;
+pub const DW_CFA_SET_LOC: u8 = 0x01;
+pub const DW_CFA_ADVANCE_LOC1: u8 = 0x02;
+pub const DW_CFA_ADVANCE_LOC2: u8 = 0x03;
+pub const DW_CFA_ADVANCE_LOC4: u8 = 0x04;
+pub const DW_CFA_OFFSET_EXTENDED: u8 = 0x05;
+pub const DW_CFA_RESTORE_EXTENDED: u8 = 0x06;
+pub const DW_CFA_UNDEFINED: u8 = 0x07;
+pub const DW_CFA_SAME_VALUE: u8 = 0x08;
+pub const DW_CFA_REGISTER: u8 = 0x09;
+pub const DW_CFA_REMEMBER_STATE: u8 = 0x0a;
+pub const DW_CFA_RESTORE_STATE: u8 = 0x0b;
+pub const DW_CFA_DEF_CFA: u8 = 0x0c;
+pub const DW_CFA_DEF_CFA_REGISTER: u8 = 0x0d;
+pub const DW_CFA_DEF_CFA_OFFSET: u8 = 0x0e;
+pub const DW_CFA_DEF_CFA_EXPRESSION: u8 = 0x0f;
+pub const DW_CFA_EXPRESSION: u8 = 0x10;
+pub const DW_CFA_OFFSET_EXTENDED_SF: u8 = 0x11;
+pub const DW_CFA_DEF_CFA_SF: u8 = 0x12;
+pub const DW_CFA_DEF_CFA_OFFSET_SF: u8 = 0x13;
+pub const DW_CFA_VAL_OFFSET: u8 = 0x14;
+pub const DW_CFA_VAL_OFFSET_SF: u8 = 0x15;
+pub const DW_CFA_VAL_EXPRESSION: u8 = 0x16;
+pub const DW_CFA_AARCH64_NEGATE_RA_STATE: u8 = 0x2d;
+pub const DW_CFA_GNU_ARGS_SIZE: u8 = 0x2e;
+pub const DW_CFA_GNU_NEGATIVE_OFFSET_EXTENDED: u8 = 0x2f;
+
+pub const DW_EH_PE_ABSPTR: u8 = 0x00;
+pub const DW_EH_PE_ULEB128: u8 = 0x01;
+pub const DW_EH_PE_UDATA2: u8 = 0x02;
+pub const DW_EH_PE_UDATA4: u8 = 0x03;
+pub const DW_EH_PE_UDATA8: u8 = 0x04;
+pub const DW_EH_PE_SLEB128: u8 = 0x09;
+pub const DW_EH_PE_SDATA2: u8 = 0x0a;
+pub const DW_EH_PE_SDATA4: u8 = 0x0b;
+pub const DW_EH_PE_SDATA8: u8 = 0x0c;
+pub const DW_EH_PE_PCREL: u8 = 0x10;
+pub const DW_EH_PE_TEXTREL: u8 = 0x20;
+pub const DW_EH_PE_DATAREL: u8 = 0x30;
+pub const DW_EH_PE_FUNCREL: u8 = 0x40;
+pub const DW_EH_PE_ALIGNED: u8 = 0x50;
+pub const DW_EH_PE_INDIRECT: u8 = 0x80;
+pub const DW_EH_PE_OMIT: u8 = 0xff;
+
+/// DWARF register number of `rsp` on x86_64.
+pub const X86_64_RSP: u16 = 7;
+/// DWARF register number of `sp` on AArch64.
+pub const AARCH64_SP: u16 = 31;
+/// DWARF register numbers of `sp`, `lr` and `pc` on 32-bit ARM.
+pub const ARM_SP: u16 = 13;
+pub const ARM_LR: u16 = 14;
+pub const ARM_PC: u16 = 15;
+
+/// The rule computing the Canonical Frame Address, the value of the stack pointer
+/// at the call site in the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfaRule<'a> {
+    RegisterOffset { register: u16, offset: i64 },
+    Expression(&'a [u8]),
+}
+
+/// The rule recovering a register of the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterRule<'a> {
+    Undefined,
+    SameValue,
+    /// Saved at CFA + offset
+    Offset(i64),
+    /// The value is CFA + offset
+    ValOffset(i64),
+    /// Saved in another register
+    Register(u16),
+    /// Saved at the address computed by the expression, with the CFA pushed first
+    Expression(&'a [u8]),
+    /// The value is computed by the expression, with the CFA pushed first
+    ValExpression(&'a [u8]),
+}
+
+/// A row of the unwind table: how to recover the caller's frame for the
+/// addresses from `start` up to `end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwindRow<'a> {
+    pub start: u64,
+    pub end: u64,
+    pub cfa: CfaRule<'a>,
+    /// Registers without a rule keep their value in the caller
+    pub registers: Vec<(u16, RegisterRule<'a>)>,
+    /// AArch64 pointer authentication: the return address is signed and must be
+    /// stripped before use
+    pub return_address_signed: bool,
+}
+
+impl<'a> UnwindRow<'a> {
+    pub fn register(&self, register: u16) -> RegisterRule<'a> {
+        self.registers
+            .iter()
+            .find(|(r, _)| *r == register)
+            .map_or(RegisterRule::SameValue, |(_, rule)| *rule)
+    }
+}
+
+/// Reads target memory while unwinding, e.g. from a core dump or a stack sample.
+pub trait Memory {
+    fn read_u64(&self, address: u64) -> Option<u64>;
+}
+
+impl<F: Fn(u64) -> Option<u64>> Memory for F {
+    fn read_u64(&self, address: u64) -> Option<u64> {
+        self(address)
+    }
+}
+
+impl Memory for AddressMap<'_> {
+    fn read_u64(&self, address: u64) -> Option<u64> {
+        AddressMap::read_u64(self, address).ok()
+    }
+}
+
+impl Memory for CoreDump<'_> {
+    fn read_u64(&self, address: u64) -> Option<u64> {
+        let bytes = self.read_memory(address, 8).ok()?;
+        Some(u64::from_le_bytes(bytes.as_ref().try_into().ok()?))
+    }
+}
+
+/// The registers of a frame, by DWARF register number, and its program counter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisterSet {
+    pub pc: u64,
+    /// Whether the frame was interrupted by a signal, as its callee is a signal
+    /// trampoline: the pc is then the faulting instruction, not a return address.
+    pub signal_frame: bool,
+    values: Vec<Option<u64>>,
+}
+
+impl RegisterSet {
+    pub fn new(pc: u64) -> RegisterSet {
+        RegisterSet {
+            pc,
+            signal_frame: false,
+            values: vec![],
+        }
+    }
+
+    pub fn get(&self, register: u16) -> Option<u64> {
+        self.values.get(register as usize).copied().flatten()
+    }
+
+    pub fn set(&mut self, register: u16, value: Option<u64>) {
+        let index = register as usize;
+        if index >= self.values.len() {
+            self.values.resize(index + 1, None);
+        }
+        self.values[index] = value;
+    }
+
+    /// Maps the registers of a thread in a core dump to their DWARF numbers.
+    pub fn from_registers(registers: &Registers) -> Option<RegisterSet> {
+        match registers {
+            Registers::X86_64(regs) => {
+                let mut set = RegisterSet::new(regs.rip);
+                let values = [
+                    regs.rax, regs.rdx, regs.rcx, regs.rbx, regs.rsi, regs.rdi, regs.rbp, regs.rsp,
+                    regs.r8, regs.r9, regs.r10, regs.r11, regs.r12, regs.r13, regs.r14, regs.r15,
+                ];
+                for (register, value) in values.into_iter().enumerate() {
+                    set.set(register as u16, Some(value));
+                }
+                Some(set)
+            }
+            Registers::AArch64(regs) => {
+                let mut set = RegisterSet::new(regs.pc);
+                for (register, &value) in regs.x.iter().enumerate() {
+                    set.set(register as u16, Some(value));
+                }
+                set.set(AARCH64_SP, Some(regs.sp));
+                Some(set)
+            }
+            Registers::Unknown(_) => None,
+        }
+    }
+}
+
+/// The DWARF register number of the stack pointer, which takes the value of the
+/// CFA in the caller.
+pub fn stack_pointer_register(machine: u16) -> Option<u16> {
+    match machine {
+        EM_X86_64 => Some(X86_64_RSP),
+        EM_AARCH64 => Some(AARCH64_SP),
+        EM_ARM => Some(ARM_SP),
+        _ => None,
+    }
+}
+
+/// The size of an address and of the registers saved on the stack.
+fn address_size(machine: u16) -> u8 {
+    match machine {
+        EM_ARM => 4,
+        _ => 8,
+    }
+}
+
+struct FrameContext<'r, M> {
+    registers: &'r RegisterSet,
+    memory: &'r M,
+}
+
+impl<M: Memory> ExpressionContext for FrameContext<'_, M> {
+    fn register(&self, register: u16) -> Option<u64> {
+        self.registers.get(register)
+    }
+
+    fn read_memory(&self, address: u64, size: u8) -> Option<u64> {
+        let value = self.memory.read_u64(address)?;
+        match size {
+            8 => Some(value),
+            1..=7 => Some(value & ((1 << (size * 8)) - 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Applies an unwind row to the registers of a frame, returning the registers of its caller.
+/// `return_address_register` is the column of the CIE holding the caller's program counter.
+pub fn unwind_frame<M: Memory>(
+    row: &UnwindRow,
+    return_address_register: u16,
+    machine: u16,
+    registers: &RegisterSet,
+    memory: &M,
+) -> Result<RegisterSet, Error> {
+    let context = FrameContext { registers, memory };
+    let address_size = address_size(machine);
+
+    let cfa = match row.cfa {
+        CfaRule::RegisterOffset { register, offset } => registers
+            .get(register)
+            .ok_or_else(|| Error::Message(format!("CFA register {} is not known", register)))?
+            .wrapping_add(offset as u64),
+        CfaRule::Expression(expr) => expression::evaluate(expr, address_size, None, &context)?,
+    };
+
+    let read = |address: u64| {
+        context
+            .read_memory(address, address_size)
+            .ok_or(Error::UnmappedAddress(address))
+    };
+
+    let mut caller = registers.clone();
+    for &(register, rule) in &row.registers {
+        let value = match rule {
+            RegisterRule::Undefined => None,
+            RegisterRule::SameValue => registers.get(register),
+            RegisterRule::Offset(offset) => Some(read(cfa.wrapping_add(offset as u64))?),
+            RegisterRule::ValOffset(offset) => Some(cfa.wrapping_add(offset as u64)),
+            RegisterRule::Register(other) => registers.get(other),
+            RegisterRule::Expression(expr) => Some(read(expression::evaluate(
+                expr,
+                address_size,
+                Some(cfa),
+                &context,
+            )?)?),
+            RegisterRule::ValExpression(expr) => Some(expression::evaluate(
+                expr,
+                address_size,
+                Some(cfa),
+                &context,
+            )?),
+        };
+        caller.set(register, value);
+    }
+
+    if let Some(sp) = stack_pointer_register(machine) {
+        caller.set(sp, Some(cfa));
+    }
+
+    let mut return_address = caller
+        .get(return_address_register)
+        .ok_or_else(|| Error::Message("return address is not known".to_string()))?;
+    if row.return_address_signed {
+        // strip the pointer authentication code above the 48-bit virtual address
+        return_address &= 0x0000_ffff_ffff_ffff;
+    }
+    caller.pc = return_address;
+
+    Ok(caller)
+}
+
+/// Unwinds the stack of a process using the call frame information of an object
+/// loaded in it, from `.eh_frame` or else `.debug_frame`.
+pub struct Unwinder<'a> {
+    eh_frame: Option<EhFrame<'a>>,
+    eh_frame_hdr: Option<EhFrameHdr<'a>>,
+    debug_frame: Option<DebugFrame<'a>>,
+    machine: u16,
+    load_bias: u64,
+}
+
+impl<'a> Unwinder<'a> {
+    /// Locates `.eh_frame` and `.eh_frame_hdr` through the section headers or,
+    /// for objects without them, through the PT_GNU_EH_FRAME segment.
+    pub fn new<A: AsRef<[u8]>>(buf: &'a A, elf: &Headers<'a>) -> Result<Unwinder<'a>, Error> {
+        let eh_frame_hdr = EhFrameHdr::load(buf, elf)?.filter(|hdr| hdr.has_table());
+        let eh_frame = match (EhFrame::load(buf, elf)?, &eh_frame_hdr) {
+            (Some(eh_frame), _) => Some(eh_frame),
+            (None, Some(hdr)) => Some(EhFrame::load_from_hdr(buf, elf, hdr)?),
+            (None, None) => None,
+        };
+        // a compressed .debug_frame has to be decompressed by the caller, see `set_debug_frame`
+        let debug_frame = match elf.find_section_header_by_name(".debug_frame") {
+            Some(hdr) if !elf.is_section_compressed(hdr) => Some(DebugFrame::new(
+                hdr.get_section_buffer(buf)?,
+                debug_frame::address_size(elf),
+            )),
+            _ => None,
+        };
+        let has_debug_frame = elf.find_section_header_by_name(".debug_frame").is_some()
+            || elf.find_section_header_by_name(".zdebug_frame").is_some();
+        if eh_frame.is_none() && !has_debug_frame {
+            return Err(Error::Message(
+                "no .eh_frame or .debug_frame in object".to_string(),
+            ));
+        }
+
+        Ok(Unwinder {
+            eh_frame,
+            eh_frame_hdr,
+            debug_frame,
+            machine: elf.header.e_machine,
+            load_bias: 0,
+        })
+    }
+
+    /// Sets the difference between the runtime and link-time addresses of the object,
+    /// e.g. the load address of a position-independent executable.
+    pub fn set_load_bias(&mut self, load_bias: u64) -> &mut Self {
+        self.load_bias = load_bias;
+        self
+    }
+
+    pub fn eh_frame(&self) -> Option<&EhFrame<'a>> {
+        self.eh_frame.as_ref()
+    }
+
+    pub fn debug_frame(&self) -> Option<&DebugFrame<'a>> {
+        self.debug_frame.as_ref()
+    }
+
+    /// Replaces the `.debug_frame` used for code without `.eh_frame`, e.g. with one
+    /// decompressed by `DebugFrame::load_with` or read from a separate debug file.
+    pub fn set_debug_frame(&mut self, debug_frame: Option<DebugFrame<'a>>) -> &mut Self {
+        self.debug_frame = debug_frame;
+        self
+    }
+
+    /// Finds the FDE covering a link-time address, using the binary search table of
+    /// `.eh_frame_hdr` when available. `.debug_frame` is searched for code that
+    /// `.eh_frame` does not describe.
+    pub fn find_fde(&self, address: u64) -> Result<Option<Fde<'a>>, Error> {
+        let fde = match (&self.eh_frame, &self.eh_frame_hdr) {
+            (Some(eh_frame), Some(hdr)) => match hdr.find_fde(address)? {
+                Some(fde_address) => {
+                    Some(eh_frame.fde_at_address(fde_address)?).filter(|fde| fde.contains(address))
+                }
+                None => None,
+            },
+            (Some(eh_frame), None) => eh_frame.find_fde(address)?,
+            (None, _) => None,
+        };
+        match (fde, &self.debug_frame) {
+            (Some(fde), _) => Ok(Some(fde)),
+            (None, Some(debug_frame)) => debug_frame.find_fde(address),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Recovers the registers of the caller of a frame, or `None` if the frame has
+    /// no unwind information or is the outermost one.
+    pub fn step<M: Memory>(
+        &self,
+        registers: &RegisterSet,
+        memory: &M,
+        is_first_frame: bool,
+    ) -> Result<Option<RegisterSet>, Error> {
+        let pc = registers.pc.wrapping_sub(self.load_bias);
+        // return addresses point after the call, which may be past the end of the caller,
+        // but a frame interrupted by a signal resumes at the exact instruction
+        let lookup = if is_first_frame || registers.signal_frame {
+            pc
+        } else {
+            pc.wrapping_sub(1)
+        };
+
+        let fde = match self.find_fde(lookup)? {
+            Some(fde) => fde,
+            None => return Ok(None),
+        };
+        let mut row = match fde.row_for(lookup)? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        // rows are in link-time addresses, but register values are runtime addresses
+        row.start = row.start.wrapping_add(self.load_bias);
+        row.end = row.end.wrapping_add(self.load_bias);
+
+        // an undefined return address marks the outermost frame, e.g. _start
+        let return_address_register = fde.cie.return_address_register;
+        if row.register(return_address_register) == RegisterRule::Undefined {
+            return Ok(None);
+        }
+
+        let mut caller = unwind_frame(
+            &row,
+            return_address_register,
+            self.machine,
+            registers,
+            memory,
+        )?;
+        // the caller of a signal trampoline ('S' augmentation) was interrupted by the signal
+        caller.signal_frame = fde.cie.signal_frame;
+        Ok(Some(caller).filter(|caller| caller.pc != 0))
+    }
+
+    /// Walks the stack from a frame, returning the program counter of each frame,
+    /// innermost first. Unwinding stops at the outermost frame, at a frame without
+    /// unwind information or after `max_frames` frames.
+    pub fn backtrace<M: Memory>(
+        &self,
+        registers: &RegisterSet,
+        memory: &M,
+        max_frames: usize,
+    ) -> Result<Vec<u64>, Error> {
+        let mut frames = vec![registers.pc];
+        let mut current = registers.clone();
+        while frames.len() < max_frames {
+            match self.step(&current, memory, frames.len() == 1)? {
+                Some(caller) => {
+                    frames.push(caller.pc);
+                    current = caller;
+                }
+                None => break,
+            }
+        }
+        Ok(frames)
+    }
+}
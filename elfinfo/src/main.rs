@@ -1,30 +1,42 @@
 use std::{fs::File, path::PathBuf};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use elf::{
+    debuglink::DebugFileLocator,
+    dwarf::{line::LineTable, DwarfSections},
+    initfini::InitFini,
     parsed::{
-        dynamic::DynamicTag,
+        dynamic::{DynamicTag, DynamicValue},
         header::Header,
+        note::{GnuPropertySummary, Note},
         relocation::RelocationType,
-        segment::{SegmentFlag, SegmentType},
+        section::{self, SectionHeader},
+        segment::ProgramHeader,
         symbol::SymbolType,
     },
     raw::{
+        compression::SectionStorage,
         dynamic::DynamicTable,
+        group::SectionGroup,
         header::Headers,
+        note,
         relocation::{Rela, RelocationTable},
         string::StringTable,
         symbol::SymbolTable,
-        SHT_DYNAMIC, SHT_DYNSYM, SHT_RELA, SHT_SYMTAB,
+        EM_X86_64, SHT_DYNAMIC, SHT_DYNSYM, SHT_RELA, SHT_SYMTAB,
     },
+    symbolizer::Symbolizer,
 };
-use enumflags2::BitFlags;
 use memmap2::Mmap;
 use num_traits::FromPrimitive;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, long)]
     all: bool,
 
@@ -56,18 +68,63 @@ struct Cli {
     #[arg(long, short)]
     dynamic: bool,
 
+    /// Display the notes
+    #[arg(long, short)]
+    notes: bool,
+
     /// Display section-to-segment mapping
     #[arg(long)]
     section_mapping: bool,
 
+    /// Display the constructors and destructors
+    #[arg(long)]
+    init_fini: bool,
+
+    /// Display the section groups
+    #[arg(long, short = 'g')]
+    section_groups: bool,
+
+    /// Display the source line at an address, e.g. 0x1149
+    #[arg(long, value_name = "ADDRESS", value_parser = parse_address)]
+    line: Option<u64>,
+
     /// Path to the ELF file
-    file: PathBuf,
+    #[arg(required = true)]
+    file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Translate addresses into function names and source lines, including inlined calls
+    Addr2line {
+        /// Path to the ELF file
+        #[arg(short, long)]
+        exe: PathBuf,
+
+        /// Additional directories to search for separate debug files
+        #[arg(long, value_name = "DIR")]
+        debug_dir: Vec<PathBuf>,
+
+        /// Addresses to translate, e.g. 0x1149
+        #[arg(required = true, value_parser = parse_address)]
+        addresses: Vec<u64>,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    let file = File::open(cli.file).unwrap();
+    if let Some(Command::Addr2line {
+        exe,
+        debug_dir,
+        addresses,
+    }) = cli.command
+    {
+        addr2line(exe, &debug_dir, &addresses);
+        return;
+    }
+
+    let file = File::open(cli.file.unwrap()).unwrap();
 
     let mmap = unsafe { Mmap::map(&file).unwrap() };
 
@@ -94,8 +151,8 @@ fn main() {
     if cli.section_headers || cli.all {
         println!("ELF section headers:");
         println!(
-            "\t{:<24} {:<16} {:<16} {:<16} {:<16}",
-            "Name", "Type", "Offset", "Address", "Size"
+            "\t{:<24} {:<16} {:<16} {:<16} {:<16} {:<5}",
+            "Name", "Type", "Offset", "Address", "Size", "Flags"
         );
 
         for s in elf.section_headers.iter() {
@@ -105,11 +162,52 @@ fn main() {
                 .get_string(s.sh_name as usize)
                 .to_str()
                 .unwrap();
-            let sh_type = s.sh_type;
-            let sh_offset = s.sh_offset;
-            let sh_size = s.sh_size;
-            let sh_addr = s.sh_addr;
-            println!("\t{name:<24} {sh_type:016x} {sh_offset:016x} {sh_addr:016x} {sh_size:016x}");
+            let sh = SectionHeader::from_raw(s, header.machine);
+            let sh_type = sh.r#type.to_string();
+            let sh_offset = sh.offset;
+            let sh_size = sh.size;
+            let sh_addr = sh.addr;
+            let sh_flags = section::flag_letters(s.sh_flags);
+            println!("\t{name:<24} {sh_type:<16} {sh_offset:016x} {sh_addr:016x} {sh_size:016x} {sh_flags:<5}");
+        }
+
+        println!("Key to Flags:");
+        println!("\tW (write), A (alloc), X (execute), M (merge), S (strings), I (info),");
+        println!("\tL (link order), O (extra OS processing required), G (group), T (TLS),");
+        println!("\tC (compressed), x (unknown), o (OS specific), E (exclude),");
+        println!("\tR (retain), p (processor specific)");
+
+        println!();
+    }
+
+    if cli.section_groups || cli.all {
+        let groups = SectionGroup::parse_all(&mmap, &elf).unwrap();
+        if groups.is_empty() {
+            println!("There are no section groups in this file.");
+        }
+
+        for group in groups.iter() {
+            let get_name = |index: usize| {
+                elf.get_section_header_by_index(index)
+                    .map_or("<invalid>", |sh| {
+                        elf.sh_names
+                            .get_string(sh.sh_name as usize)
+                            .to_str()
+                            .unwrap()
+                    })
+            };
+            let kind = if group.is_comdat() { "COMDAT " } else { "" };
+            println!(
+                "{kind}group section [{:>5}] `{}' [{}] contains {} sections:",
+                group.index,
+                get_name(group.index),
+                group.signature,
+                group.members.len()
+            );
+            println!("\t[Index]    Name");
+            for &member in group.members.iter() {
+                println!("\t[{member:>5}]   {}", get_name(member as usize));
+            }
         }
 
         println!();
@@ -118,21 +216,20 @@ fn main() {
     if cli.program_headers || cli.all {
         println!("ELF program headers:");
         println!(
-            "\t{:<24} {:<16} {:<16} {:<16} {:<16} {:<16}",
+            "\t{:<28} {:<16} {:<16} {:<16} {:<16} {:<16}",
             "Type", "Offset", "Start", "Mem Size", "File Size", "Flags"
         );
 
         for h in elf.program_headers.iter() {
-            let elf_type = SegmentType::from_u32(h.get_type());
-            let flags = BitFlags::<SegmentFlag>::from_bits((h.get_flags() & 0xff) as u8).unwrap();
-            let flags_vec = flags.iter().collect::<Vec<_>>();
+            let ph = ProgramHeader::from_raw(h, header.machine);
+            let flags_vec = ph.flags.iter().collect::<Vec<_>>();
             println!(
-                "\t{:<24} 0x{:014x} 0x{:014x} 0x{:014x} 0x{:014x} {:?}",
-                format!("{:?} (0x{:02x})", elf_type, h.get_type()),
-                h.get_offset(),
-                h.get_vaddr(),
-                h.get_memsz(),
-                h.get_filesz(),
+                "\t{:<28} 0x{:014x} 0x{:014x} 0x{:014x} 0x{:014x} {:?}",
+                format!("{} (0x{:02x})", ph.r#type, h.get_type()),
+                ph.offset,
+                ph.vaddr,
+                ph.memsz,
+                ph.filesz,
                 flags_vec
             );
         }
@@ -163,7 +260,9 @@ fn main() {
             let strtab = StringTable::parse(&mmap, symstr_hdr).unwrap();
 
             for (index, sym) in symtab.iter().enumerate() {
-                let st_type = SymbolType::from_u8(sym.get_type()).unwrap();
+                let st_type = SymbolType::from_u8(sym.get_type())
+                    .map(|t| format!("{:?}", t))
+                    .unwrap_or_else(|| format!("0x{:x}", sym.get_type()));
 
                 let name = if sym.get_name() == 0 {
                     ""
@@ -172,7 +271,7 @@ fn main() {
                 };
 
                 println!(
-                    "\t{index:>3}: {:<32} 0x{:08x} {:>6} {:?}",
+                    "\t{index:>3}: {:<32} 0x{:08x} {:>6} {}",
                     name,
                     sym.get_value(),
                     sym.get_size(),
@@ -199,10 +298,12 @@ fn main() {
         let symtab = SymbolTable::parse(&mmap, &elf, sh).unwrap();
 
         for (index, sym) in symtab.symbols_iter().enumerate() {
-            let st_type = SymbolType::from_u8(sym.info & 0xf).unwrap();
+            let st_type = SymbolType::from_u8(sym.info & 0xf)
+                .map(|t| format!("{:?}", t))
+                .unwrap_or_else(|| format!("0x{:x}", sym.info & 0xf));
 
             println!(
-                "\t{index:>3}: {:<32} 0x{:08x} {:>6} {:?}",
+                "\t{index:>3}: {:<32} 0x{:08x} {:>6} {}",
                 sym.name, sym.value, sym.size, st_type
             );
         }
@@ -267,14 +368,100 @@ fn main() {
 
         let dyntab = DynamicTable::parse_section(&mmap, sh).unwrap();
 
+        // the sh_link attribute for a dynamic section designates the string table for its entries
+        let dynstr_hdr = elf
+            .get_section_header_by_index(sh.sh_link as usize)
+            .unwrap();
+        let dynstr = StringTable::parse(&mmap, dynstr_hdr).unwrap();
+
         for dynamic in dyntab.iter() {
-            let tag = DynamicTag::from_u64(dynamic.get_tag());
-            println!(
-                "\t{:<16} {:016x}",
-                tag.map(|t| format!("{t:?}"))
-                    .unwrap_or(format!("{:016x}", dynamic.get_tag())),
-                dynamic.get_value()
-            );
+            match DynamicTag::from_u64(dynamic.get_tag()) {
+                Some(tag) => match DynamicValue::from_raw(tag, dynamic.get_value(), &dynstr) {
+                    Ok(value) => println!("\t{:<16} {}", tag.name(), value),
+                    // e.g. a string offset outside .dynstr
+                    Err(_) => println!("\t{:<16} {:016x}", tag.name(), dynamic.get_value()),
+                },
+                None => println!(
+                    "\t{:<16} {:016x}",
+                    format!("{:016x}", dynamic.get_tag()),
+                    dynamic.get_value()
+                ),
+            }
+        }
+
+        println!();
+    }
+
+    if cli.notes || cli.all {
+        let mut parsed_notes = vec![];
+
+        for notes in note::note_iterators(&mmap, &elf).unwrap() {
+            println!("Notes:");
+            println!("\t{:<12} {:<10} Description", "Owner", "Data size");
+
+            for n in notes {
+                let n = match n {
+                    Ok(n) => n,
+                    Err(err) => {
+                        println!("\t<invalid note: {}>", err);
+                        continue;
+                    }
+                };
+                let owner = String::from_utf8_lossy(n.name);
+                match Note::from_raw(&n, header.machine) {
+                    Ok(parsed) => {
+                        println!("\t{:<12} 0x{:08x} {}", owner, n.desc.len(), parsed);
+                        parsed_notes.push(parsed);
+                    }
+                    Err(err) => {
+                        println!("\t{:<12} 0x{:08x} <invalid: {}>", owner, n.desc.len(), err)
+                    }
+                }
+            }
+
+            println!();
+        }
+
+        let summary = GnuPropertySummary::from_notes(&parsed_notes);
+        if summary != GnuPropertySummary::default() {
+            println!("Hardening properties:");
+            if header.machine == EM_X86_64 {
+                println!("\tCET (IBT+SHSTK): {}", summary.has_cet());
+            }
+            if let Some(level) = summary.x86_isa_level() {
+                println!("\tx86 ISA level needed: {}", level.name());
+            }
+            if !summary.aarch64_features.is_empty() {
+                let features = summary.aarch64_features.iter().map(|f| f.name());
+                println!(
+                    "\tAArch64 features: {}",
+                    features.collect::<Vec<_>>().join(", ")
+                );
+            }
+            println!();
+        }
+    }
+
+    if cli.init_fini || cli.all {
+        let storage = SectionStorage::new();
+        let sections = DwarfSections::load_with(&mmap, &elf, &storage).unwrap();
+        let symbolizer = Symbolizer::from_sections(&mmap, &elf, &sections).unwrap();
+        let mut init_fini = InitFini::parse(&mmap, &elf).unwrap();
+        init_fini.symbolize(&symbolizer).unwrap();
+
+        for (title, functions) in [
+            ("Constructors", &init_fini.constructors),
+            ("Destructors", &init_fini.destructors),
+        ] {
+            println!("{title}:");
+            for f in functions.iter() {
+                println!(
+                    "\t0x{:016x} {:<16} {}",
+                    f.address,
+                    format!("{:?}", f.kind),
+                    f.function.unwrap_or("??")
+                );
+            }
         }
 
         println!();
@@ -285,15 +472,10 @@ fn main() {
         println!("\tSegment Sections");
 
         for (i, ph) in elf.program_headers.iter().enumerate() {
-            let ph_addr = ph.get_vaddr();
-            let ph_addr_end = ph_addr + ph.get_memsz();
-
             let mut segments = String::new();
 
             for sh in elf.section_headers.iter() {
-                let sh_addr = sh.sh_addr;
-
-                if sh_addr >= ph.get_vaddr() && sh_addr < ph_addr_end {
+                if ph.contains_address(sh.sh_addr) {
                     let name = elf
                         .sh_names
                         .get_string(sh.sh_name as usize)
@@ -307,4 +489,92 @@ fn main() {
 
         println!();
     }
+
+    if let Some(address) = cli.line {
+        let storage = SectionStorage::new();
+        let sections = DwarfSections::load_with(&mmap, &elf, &storage).unwrap();
+        let lines = LineTable::parse(&sections).unwrap();
+        match lines.find_location(address) {
+            Some(location) => println!(
+                "0x{:x}: {}:{}:{}",
+                address,
+                location.path(),
+                location.line,
+                location.column
+            ),
+            None => println!("0x{:x}: no line information", address),
+        }
+
+        println!();
+    }
+}
+
+fn parse_address(s: &str) -> Result<u64, String> {
+    let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+    u64::from_str_radix(digits, 16).map_err(|e| format!("invalid address {s}: {e}"))
+}
+
+fn addr2line(path: PathBuf, debug_dirs: &[PathBuf], addresses: &[u64]) {
+    let file = File::open(&path).unwrap();
+    let mmap = unsafe { Mmap::map(&file).unwrap() };
+    let elf = Headers::parse(&mmap).unwrap();
+
+    // stripped objects are symbolized with their separate debug file when it can be found
+    let mut locator = DebugFileLocator::new();
+    for dir in debug_dirs {
+        locator.add_directory(dir);
+    }
+    let mut debug_file = None;
+    let mut alt_file = None;
+    let has_debug_info = elf.find_section_header_by_name(".debug_info").is_some()
+        || elf.find_section_header_by_name(".zdebug_info").is_some();
+    if !has_debug_info {
+        debug_file = locator.find_debug_file(&path, &mmap, &elf).unwrap();
+        if let Some(debug) = &debug_file {
+            let debug_elf = Headers::parse(&debug.data).unwrap();
+            alt_file = locator
+                .find_alt_file(&debug.path, &debug.data, &debug_elf)
+                .unwrap();
+        }
+    }
+
+    // debug sections are often compressed in distribution packages
+    let storage = SectionStorage::new();
+    let symbolizer = match &debug_file {
+        Some(debug) => Symbolizer::with_debug_file(
+            &mmap,
+            &elf,
+            &debug.data,
+            alt_file.as_ref().map(|alt| &alt.data),
+            &storage,
+        )
+        .unwrap(),
+        None => {
+            let sections = DwarfSections::load_with(&mmap, &elf, &storage).unwrap();
+            Symbolizer::from_sections(&mmap, &elf, &sections).unwrap()
+        }
+    };
+
+    for &address in addresses {
+        let frames = symbolizer.symbolize(address).unwrap();
+        if frames.is_empty() {
+            println!("0x{address:x}: ?? at ??:?");
+            continue;
+        }
+
+        for (i, frame) in frames.iter().enumerate() {
+            let function = frame.function.unwrap_or("??");
+            let file = frame.file.as_deref().unwrap_or("??");
+            // line 0 means the code has no source line, e.g. compiler-generated code
+            let line = match frame.line {
+                0 => "?".to_string(),
+                line => line.to_string(),
+            };
+            if i == 0 {
+                println!("0x{address:x}: {function} at {file}:{line}");
+            } else {
+                println!(" (inlined by) {function} at {file}:{line}");
+            }
+        }
+    }
 }